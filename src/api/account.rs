@@ -0,0 +1,332 @@
+use crate::models::account::{self, Account, Statement};
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::audit_entry;
+use crate::utils::date;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateAccountBody {
+  pub name: String,
+  pub kind: String,
+  pub opening_balance: String
+}
+
+pub async fn create_account(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateAccountBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let opening_balance = validation::balance(&mut errors, "opening_balance", &form.opening_balance);
+  validate_kind(&mut errors, "kind", &form.kind);
+
+  if !errors.is_empty() {
+    let page = pages::new_account::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut account = Account::new(&name, &form.kind, opening_balance);
+  account.user_id = Some(current.user.id);
+
+  account.insert().map_err(|err| {
+    println!("error when creating account {}", err);
+
+    AppError::internal("error when inserting account into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&account))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/account/{}", account.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateAccountByIdBody {
+  pub name: String,
+  pub kind: String,
+  pub opening_balance: String,
+  pub id: i32
+}
+
+pub async fn update_account_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateAccountByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut account = get_account(form.id)?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let opening_balance = validation::balance(&mut errors, "opening_balance", &form.opening_balance);
+  validate_kind(&mut errors, "kind", &form.kind);
+
+  if !errors.is_empty() {
+    let page = pages::edit_account::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = account.clone();
+  account.name = name;
+  account.kind = form.kind.clone();
+  account.opening_balance = opening_balance;
+
+  account.update().map_err(|err| {
+    println!("error when updating account {}", err);
+
+    AppError::internal("error when updating account in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&account))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/account/{}", account.id))
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteAccountByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_account_by_id(current: AuthenticatedUser, form: web::Form<DeleteAccountByIdBody>) -> Result<HttpResponse, AppError> {
+  let account = get_account(form.id)?;
+
+  account.remove().map_err(|err| {
+    println!("error when removing account {}", err);
+
+    AppError::internal("error when removing account from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&account), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/accounts")
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateStatementBody {
+  pub account_id: i32,
+  pub date: String,
+  pub balance: String
+}
+
+pub async fn create_statement(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateStatementBody>) -> Result<HttpResponse, AppError> {
+  let account = get_account(form.account_id)?;
+
+  let mut errors = FormErrors::new();
+  let date = validation::date(&mut errors, "date", &form.date);
+  let balance = validation::balance(&mut errors, "balance", &form.balance);
+
+  if !errors.is_empty() {
+    let page = pages::new_statement::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut statement = Statement::new(account.id, date, balance);
+
+  statement.insert().map_err(|err| {
+    println!("error when creating statement {}", err);
+
+    AppError::internal("error when inserting statement into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&statement))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/account/{}/reconcile?statement={}", account.id, statement.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteStatementByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_statement_by_id(current: AuthenticatedUser, form: web::Form<DeleteStatementByIdBody>) -> Result<HttpResponse, AppError> {
+  let statement = get_statement(form.id)?;
+
+  statement.remove().map_err(|err| {
+    println!("error when removing statement {}", err);
+
+    AppError::internal("error when removing statement from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&statement), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/account/{}", statement.account_id))
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+/// Clears the entries of the account dated up to the statement whose
+/// `cleared-<kind>-<id>` field is checked, and unclears the others.
+pub async fn reconcile(current: AuthenticatedUser, form: web::Form<HashMap<String, String>>) -> Result<HttpResponse, AppError> {
+  let statement_id = form
+    .get("statement_id")
+    .and_then(|id| id.parse::<i32>().ok())
+    .ok_or_else(|| AppError::not_found("no such statement"))?;
+
+  let statement = get_statement(statement_id)?;
+  let account = get_account(statement.account_id)?;
+
+  let entries = account.get_entries().map_err(|err| {
+    println!("error when fetching account entries {}", err);
+
+    AppError::internal("error when fetching account entries from database")
+  })?;
+
+  let now = date::now();
+
+  for entry in entries.iter().filter(|entry| entry.date <= statement.date) {
+    let cleared = form.contains_key(&format!("cleared-{}-{}", entry.kind, entry.id));
+
+    if cleared == entry.cleared_date.is_some() {
+      continue;
+    }
+
+    let cleared_date = if cleared { Some(now) } else { None };
+
+    if entry.kind == "expense" {
+      set_expense_cleared_date(&current, entry.id, cleared_date)?;
+    }
+    else {
+      set_income_cleared_date(&current, entry.id, cleared_date)?;
+    }
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/account/{}/reconcile?statement={}", account.id, statement.id))
+      .content_type("text/plain")
+      .body("reconciled")
+  )
+}
+
+fn set_expense_cleared_date(current: &AuthenticatedUser, id: i32, cleared_date: Option<i64>) -> Result<(), AppError> {
+  let mut expense = Expense::get_by_id(id).map_err(|err| {
+    println!("error when fetching expense {}", err);
+
+    AppError::internal("error when searching expense from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such expense"))?;
+
+  let before = expense.clone();
+  expense.cleared_date = cleared_date;
+
+  expense.update().map_err(|err| {
+    println!("error when updating expense {}", err);
+
+    AppError::internal("error when updating expense in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&expense))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(())
+}
+
+fn set_income_cleared_date(current: &AuthenticatedUser, id: i32, cleared_date: Option<i64>) -> Result<(), AppError> {
+  let mut income = Income::get_by_id(id).map_err(|err| {
+    println!("error when fetching income {}", err);
+
+    AppError::internal("error when searching income from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such income"))?;
+
+  let before = income.clone();
+  income.cleared_date = cleared_date;
+
+  income.update().map_err(|err| {
+    println!("error when updating income {}", err);
+
+    AppError::internal("error when updating income in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&income))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(())
+}
+
+/// Returns the account chosen in an expense or income form, `None` when the
+/// field is left empty.
+pub fn validate_account_id(errors: &mut FormErrors, field: &'static str, value: &str) -> Result<Option<i32>, AppError> {
+  if value.trim().is_empty() {
+    return Ok(None);
+  }
+
+  let account = match value.trim().parse::<i32>() {
+    Ok(id) => Account::get_by_id(id).map_err(|err| {
+      println!("error when fetching account {}", err);
+
+      AppError::internal("error when searching account from database")
+    })?,
+    Err(_) => None
+  };
+
+  match account {
+    Some(account) => Ok(Some(account.id)),
+    None => {
+      errors.add(field, String::from("no such account"));
+
+      Ok(None)
+    }
+  }
+}
+
+fn validate_kind(errors: &mut FormErrors, field: &'static str, value: &str) {
+  if !account::KINDS.contains(&value) {
+    errors.add(field, format!("the kind must be one of {}", account::KINDS.join(", ")));
+  }
+}
+
+fn get_account(id: i32) -> Result<Account, AppError> {
+  Account::get_by_id(id).map_err(|err| {
+    println!("error when fetching account {}", err);
+
+    AppError::internal("error when searching account from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such account"))
+}
+
+fn get_statement(id: i32) -> Result<Statement, AppError> {
+  Statement::get_by_id(id).map_err(|err| {
+    println!("error when fetching statement {}", err);
+
+    AppError::internal("error when searching statement from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such statement"))
+}
\ No newline at end of file
@@ -1,9 +1,16 @@
-use crate::models::invitation::Invitation;
-use actix_web::{http, web, HttpResponse, Result};
+use crate::models::invitation::{Invitation, InvitationError};
+use crate::models::audit_entry;
+use actix_web::{http, web, HttpMessage, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
-use crate::models::user::{User, set_user_token};
-use crate::utils::crypto::{verify_passwords};
-use uuid::Uuid;
+use crate::models::user::{User, set_user_password};
+use crate::models::session::{Session, remove_all_by_user_id_except};
+use crate::models::password_reset::{PasswordReset, PasswordResetError};
+use crate::models::two_factor::{TwoFactor, TwoFactorChallenge, use_recovery_code};
+use crate::constants;
+use crate::utils::cookies;
+use crate::utils::crypto::{hash_password, needs_rehash, verify_passwords};
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::errors::AppError;
 
 #[derive(Serialize, Deserialize)]
 pub struct SignupBody {
@@ -13,56 +20,55 @@ pub struct SignupBody {
   pub invitation_hash: String,
 }
 
-pub async fn signup(req: web::Form<SignupBody>) -> Result<HttpResponse> {
+pub async fn signup(req: web::Form<SignupBody>) -> Result<HttpResponse, AppError> {
   if req.password.len() == 0 || req.handle.len() == 0 || req.invitation_hash.len() == 0 {
-    return Ok(
-      HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("handle, password and invitation hash are required"),
-    );
+    return Err(AppError::bad_request("handle, password and invitation hash are required"));
   }
 
   if req.password != req.passwordconfirm {
-    return Ok(
-      HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("passwords do not match"),
-    );
+    return Err(AppError::bad_request("passwords do not match"));
   }
 
   let invitation = Invitation::get_by_hash(&req.invitation_hash)
     .map_err(|err| {
       println!("error when fetching invitation by hash {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: could not fetch invitation from db")
+      AppError::internal("could not fetch invitation from db")
     })
     .and_then(|invitation| match invitation {
       Some(inv) => Ok(inv),
       None => 
-        Err(HttpResponse::Unauthorized()
-          .content_type("text/plain")
-          .body("no such invitation"))
+        Err(AppError::unauthorized("no such invitation"))
     })?;
 
     if invitation.handle != req.handle {
-      return Ok(
-        HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("no such invitation")
-      );
+      return Err(AppError::unauthorized("no such invitation"));
     }
     
-    invitation.consume(req.password.clone())
-    .map_err(|err| {
-      println!("error on invitation.consume {}", err);
+    let user = invitation.consume(req.password.clone())
+    .map_err(|err| match err {
+      InvitationError::Expired => {
+        AppError::unauthorized("this invitation expired, ask an administrator to extend it")
+      },
+      InvitationError::HandleTaken => {
+        AppError::conflict("this handle is already taken")
+      },
+      InvitationError::Internal(err) => {
+        println!("error on invitation.consume {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: could not use the invitation")
+        AppError::internal("could not use the invitation")
+      }
     })?;
 
+    // the new user is the one who used the invitation
+    audit_entry::record(Some(&user), None, Some(&user))
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
+    audit_entry::record(Some(&user), Some(&invitation), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
   Ok(
     HttpResponse::Found()
       .header(http::header::LOCATION, "/signin")
@@ -77,54 +83,313 @@ pub struct SigninBody {
   pub password: String
 }
 
-pub async fn signin(req: web::Form<SigninBody>) -> Result<HttpResponse> {
-  let user = User::get_by_handle(&req.handle)
+pub async fn signin(req: HttpRequest, form: web::Form<SigninBody>) -> Result<HttpResponse, AppError> {
+  let user = User::get_by_handle(&form.handle)
     .map_err(|err| {
       println!("error when fetching user by handle {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: could not fetch user from db")
+      AppError::internal("could not fetch user from db")
     })?;
 
   if let Some(user) = user {
-    if !verify_passwords(&req.handle, &req.password, &user.password) {
-      return Ok(
-        HttpResponse::Unauthorized()
-          .content_type("text/plain")
-          .body("user & password do no match")
-      );
+    if !verify_passwords(&form.handle, &form.password, &user.password) {
+      return Err(AppError::unauthorized("user & password do no match"));
     }
 
-    let token = Uuid::new_v4().to_string();
+    // upgrade the hash to the current scheme while the password is known
+    if needs_rehash(&user.password) {
+      let hashed_password = hash_password(&form.password)
+        .map_err(|err| {
+          println!("error when hashing password {}", err);
+
+          AppError::internal("error when hashing password")
+        })?;
+
+      set_user_password(user.id, &hashed_password)
+        .map_err(|err| {
+          println!("error when updating user password {}, {}", form.handle, err);
 
-    set_user_token(&req.handle, &token)
+          AppError::internal("error when updating user password")
+        })?;
+    }
+
+    let two_factor = TwoFactor::get_enabled_by_user_id(user.id)
       .map_err(|err| {
-        println!("error when updating user token {}, {}", req.handle, err);
+        println!("error when fetching two-factor of {}, {}", form.handle, err);
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating user token")
+        AppError::internal("could not fetch two-factor from db")
       })?;
 
-    let cookie = actix_web::cookie::Cookie::build("token", token)
-      .path("/")
-      // .secure(true)
-      .finish();
-
-    Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, "/")
-        .header(http::header::SET_COOKIE, cookie.to_string())
-        .finish()
-        .into_body()
-    )
+    if two_factor.is_some() {
+      return start_two_factor_challenge(user.id);
+    }
+
+    start_session(&req, user.id)
   }
   else {
-    Ok(
-      HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("user & password do no match")
-    )
+    Err(AppError::unauthorized("user & password do no match"))
+  }
+}
+
+/// Signs the device in: creates a session for the user and sets its `token`
+/// cookie.
+pub fn start_session(req: &HttpRequest, user_id: i32) -> Result<HttpResponse, AppError> {
+  let user_agent = req.headers()
+    .get(http::header::USER_AGENT)
+    .and_then(|header| header.to_str().ok())
+    .unwrap_or("unknown device");
+
+  let session = Session::new(user_id, user_agent);
+
+  session.insert()
+    .map_err(|err| {
+      println!("error when creating session for {}, {}", user_id, err);
+
+      AppError::internal("error when creating session")
+    })?;
+
+  let cookie = cookies::session(session.token);
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/")
+      .header(http::header::SET_COOKIE, cookie.to_string())
+      .finish()
+      .into_body()
+  )
+}
+
+/// The password was right but the user enabled two-factor authentication,
+/// the device gets a `two_factor` cookie and is sent to the page asking for
+/// the code.
+fn start_two_factor_challenge(user_id: i32) -> Result<HttpResponse, AppError> {
+  let challenge = TwoFactorChallenge::new(user_id);
+
+  challenge.insert()
+    .map_err(|err| {
+      println!("error when creating two-factor challenge for {}, {}", user_id, err);
+
+      AppError::internal("error when creating two-factor challenge")
+    })?;
+
+  let cookie = cookies::build("two_factor", challenge.token)
+    .max_age(constants::TWO_FACTOR_CHALLENGE_EXPIRE_DURATION_SECONDS)
+    .finish();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/signin/two-factor")
+      .header(http::header::SET_COOKIE, cookie.to_string())
+      .finish()
+      .into_body()
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorSigninBody {
+  pub code: String
+}
+
+/// Second step of the sign in, accepts either a code from the authenticator
+/// app or one of the recovery codes.
+pub async fn signin_two_factor(req: HttpRequest, form: web::Form<TwoFactorSigninBody>) -> Result<HttpResponse, AppError> {
+  let some_challenge = match req.cookie("two_factor") {
+    Some(cookie) => TwoFactorChallenge::get_by_token(cookie.value())
+      .map_err(|err| {
+        println!("error when fetching two-factor challenge {}", err);
+
+        AppError::internal("could not fetch two-factor challenge from db")
+      })?,
+    None => None
+  };
+
+  let mut challenge = match some_challenge {
+    Some(challenge) if !challenge.is_expired() => challenge,
+    _ => {
+      return Err(AppError::unauthorized("this sign in expired, sign in again"));
+    }
+  };
+
+  let mut two_factor = TwoFactor::get_enabled_by_user_id(challenge.user_id)
+    .map_err(|err| {
+      println!("error when fetching two-factor {}", err);
+
+      AppError::internal("could not fetch two-factor from db")
+    })?;
+
+  let accepted = match two_factor.as_mut() {
+    Some(two_factor) => {
+      two_factor.verify_code(&form.code)
+        .and_then(|valid| if valid {
+          Ok(true)
+        } else {
+          use_recovery_code(challenge.user_id, &form.code)
+        })
+        .map_err(|err| {
+          println!("error when verifying two-factor code {}", err);
+
+          AppError::internal("could not verify the code")
+        })?
+    },
+    // two-factor authentication was disabled in the meantime
+    None => true
+  };
+
+  if !accepted {
+    challenge.add_failed_attempt()
+      .map_err(|err| {
+        println!("error when counting two-factor attempt {}", err);
+
+        AppError::internal("could not verify the code")
+      })?;
+
+    return Err(AppError::unauthorized("wrong code"));
+  }
+
+  challenge.remove()
+    .map_err(|err| {
+      println!("error when removing two-factor challenge {}", err);
+
+      AppError::internal("error when removing two-factor challenge")
+    })?;
+
+  start_session(&req, challenge.user_id)
+}
+
+/// Ends the session of the device and removes its `token` cookie.
+pub async fn signout(current: Option<AuthenticatedUser>) -> Result<HttpResponse, AppError> {
+  if let Some(AuthenticatedUser { session, .. }) = current {
+    session.remove()
+      .map_err(|err| {
+        println!("error when removing session {}", err);
+
+        AppError::internal("error when removing session")
+      })?;
+  }
+
+  let cookie = cookies::build("token", String::new())
+    .max_age(0)
+    .finish();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/signin")
+      .header(http::header::SET_COOKIE, cookie.to_string())
+      .finish()
+      .into_body()
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangePasswordBody {
+  pub current_password: String,
+  pub password: String,
+  pub passwordconfirm: String
+}
+
+/// Changes the password of the signed in user, who stays signed in on this
+/// device only.
+pub async fn change_password(current: AuthenticatedUser, form: web::Form<ChangePasswordBody>) -> Result<HttpResponse, AppError> {
+  let AuthenticatedUser { user, session } = current;
+
+  if !verify_passwords(&user.handle, &form.current_password, &user.password) {
+    return Err(AppError::unauthorized("the current password is wrong"));
+  }
+
+  if form.password.is_empty() {
+    return Err(AppError::bad_request("the new password is required"));
+  }
+
+  if form.password != form.passwordconfirm {
+    return Err(AppError::bad_request("passwords do not match"));
+  }
+
+  let hashed_password = hash_password(&form.password)
+    .map_err(|err| {
+      println!("error when hashing password {}", err);
+
+      AppError::internal("error when hashing password")
+    })?;
+
+  set_user_password(user.id, &hashed_password)
+    .map_err(|err| {
+      println!("error when updating user password {}, {}", user.handle, err);
+
+      AppError::internal("error when updating user password")
+    })?;
+
+  remove_all_by_user_id_except(user.id, session.id)
+    .map_err(|err| {
+      println!("error when removing other sessions of {}, {}", user.handle, err);
+
+      AppError::internal("error when signing out other devices")
+    })?;
+
+  audit_entry::record_action(Some(&user), "password_changed", &user)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/settings")
+      .finish()
+      .into_body()
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResetPasswordBody {
+  pub hash: String,
+  pub password: String,
+  pub passwordconfirm: String
+}
+
+pub async fn reset_password(form: web::Form<ResetPasswordBody>) -> Result<HttpResponse, AppError> {
+  if form.password.is_empty() {
+    return Err(AppError::bad_request("the new password is required"));
+  }
+
+  if form.password != form.passwordconfirm {
+    return Err(AppError::bad_request("passwords do not match"));
   }
-}
\ No newline at end of file
+
+  let reset = PasswordReset::get_by_hash(&form.hash)
+    .map_err(|err| {
+      println!("error when fetching password reset by hash {}", err);
+
+      AppError::internal("could not fetch password reset from db")
+    })?
+    .ok_or_else(|| {
+      AppError::unauthorized("no such password reset")
+    })?;
+
+  reset.consume(&form.password)
+    .map_err(|err| match err {
+      PasswordResetError::Expired => {
+        AppError::unauthorized("this reset link expired, ask an administrator for a new one")
+      },
+      PasswordResetError::Internal(err) => {
+        println!("error on reset.consume {}", err);
+
+        AppError::internal("could not reset the password")
+      }
+    })?;
+
+  // the user is not signed in, the entry is made in their name
+  match User::get_by_id(reset.user_id) {
+    Ok(Some(user)) => {
+      audit_entry::record_action(Some(&user), "password_reset", &user)
+        .map_err(|err| println!("error when recording audit entry {}", err))
+        .ok();
+    },
+    Ok(None) => {},
+    Err(err) => println!("error when fetching user by id {}", err)
+  };
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/signin")
+      .finish()
+      .into_body()
+  )
+}
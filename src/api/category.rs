@@ -0,0 +1,168 @@
+use crate::models::category::Category;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateCategoryBody {
+  pub name: String,
+
+  /// the planned amount, left empty when there is none
+  pub target: String,
+  pub sheet_id: i32
+}
+
+pub async fn create_category(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateCategoryBody>) -> Result<HttpResponse, AppError> {
+  let sheet = Sheet::get_by_id(form.sheet_id).map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let target = validation::optional_amount(&mut errors, "target", &form.target);
+
+  if !errors.is_empty() {
+    let page = pages::new_sheet_category::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut category = Category::new(&name, sheet.id);
+  category.target = target;
+
+  category.insert().map_err(|err| {
+    println!("error when creating category {}", err);
+
+    AppError::internal("error when inserting category into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&category))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateCategoryByIdBody {
+  pub name: String,
+  pub target: String,
+  pub id: i32
+}
+
+pub async fn update_category_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateCategoryByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut category = Category::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching category {}", err);
+
+    AppError::internal("error when searching category from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such category"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let target = validation::optional_amount(&mut errors, "target", &form.target);
+
+  if !errors.is_empty() {
+    let page = pages::edit_category::render_form(&req, &form, category.sheet_id, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = category.clone();
+  category.name = name;
+  category.target = target;
+
+  category.update().map_err(|err| {
+    println!("error when updating category {}", err);
+
+    AppError::internal("error when updating category in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&category))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", category.sheet_id))
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteCategoryByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_category_by_id(current: AuthenticatedUser, form: web::Form<DeleteCategoryByIdBody>) -> Result<HttpResponse, AppError> {
+  let category = Category::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching category {}", err);
+
+    AppError::internal("error when searching category from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such category"))?;
+
+  category.remove().map_err(|err| {
+    println!("error when removing category {}", err);
+
+    AppError::internal("error when removing category from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&category), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", category.sheet_id))
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+/// Returns the category chosen in an expense form, `None` when the field is
+/// left empty. It must belong to the sheet of the expense.
+pub fn validate_category_id(errors: &mut FormErrors, field: &'static str, value: &str, sheet_id: i32) -> Result<Option<i32>, AppError> {
+  if value.trim().is_empty() {
+    return Ok(None);
+  }
+
+  let id = match value.trim().parse::<i32>() {
+    Ok(id) => id,
+    Err(_) => {
+      errors.add(field, String::from("no such category"));
+
+      return Ok(None);
+    }
+  };
+
+  let category = Category::get_by_id(id).map_err(|err| {
+    println!("error when fetching category {}", err);
+
+    AppError::internal("error when searching category from database")
+  })?;
+
+  match category {
+    Some(category) if category.sheet_id == sheet_id => Ok(Some(category.id)),
+    _ => {
+      errors.add(field, String::from("no such category"));
+
+      Ok(None)
+    }
+  }
+}
\ No newline at end of file
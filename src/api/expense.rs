@@ -1,189 +1,180 @@
+use crate::api::category::validate_category_id;
+use crate::api::account::validate_account_id;
+use crate::api::goal::validate_goal_id;
+use crate::api::transfer::ensure_not_in_transfer;
 use crate::models::expense::Expense;
-use crate::models::user::{UserRole};
 use crate::models::sheet::Sheet;
-use crate::utils::req_auth::request_authentication;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
 
 use serde::{Deserialize, Serialize};
-use actix_web::{web, HttpRequest, HttpResponse, Result, http};
-use chrono::prelude::*;
-#[derive(Serialize, Deserialize)]
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateExpenseBody {
   pub name: String,
-  pub amount: i32,
+  pub amount: String,
   pub date: String,
-  pub sheet_id: i32
-}
+  pub sheet_id: i32,
 
-pub async fn create_expense(req: HttpRequest, form: web::Form<CreateExpenseBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
+  /// the id of the category, left empty when there is none
+  #[serde(default)]
+  pub category_id: String,
 
-  if let Ok(date) = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d") {
-    let expense = Expense::new(&form.name, form.amount, form.sheet_id, date.and_hms(0, 0, 0).timestamp());
+  /// the id of the goal, left empty when there is none
+  #[serde(default)]
+  pub goal_id: String,
 
-    expense.insert()
-    .map_err(|err| {
-      println!("error when creating expense {}", err);
-  
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when inserting expense into database")
-    })?;
+  /// the id of the account, left empty when there is none
+  #[serde(default)]
+  pub account_id: String
+}
 
-    let some_sheet = Sheet::get_by_id(expense.sheet_id)
-    .map_err(|err| {
-      println!("error when fetching parent sheet {}", err);
+pub async fn create_expense(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateExpenseBody>) -> Result<HttpResponse, AppError> {
+  let mut sheet = Sheet::get_by_id(form.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
-    })?;
-    
-    if let Some(mut sheet) = some_sheet {
-      sheet.remove_from_cached_value(expense.amount)
-      .map_err(|err| {
-        println!("error when updating parent sheet cached value {}", err);
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
-      })?;
-    }
-    else {
-      println!("no such sheet");
-    }
-  
-    Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
-        .content_type("text/plain")
-        .body("created")
-    )
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let category_id = validate_category_id(&mut errors, "category_id", &form.category_id, sheet.id)?;
+  let goal_id = validate_goal_id(&mut errors, "goal_id", &form.goal_id)?;
+  let account_id = validate_account_id(&mut errors, "account_id", &form.account_id)?;
+
+  if !errors.is_empty() {
+    let page = pages::new_sheet_expense::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
   }
-  else {
-    println!("error when parsing expense date");
-  
-    return Ok(
-      HttpResponse::InternalServerError()
+
+  let mut expense = Expense::new(&name, amount, sheet.id, date);
+  expense.category_id = category_id;
+  expense.goal_id = goal_id;
+  expense.account_id = account_id;
+
+  expense.insert()
+  .map_err(|err| {
+    println!("error when creating expense {}", err);
+
+    AppError::internal("error when inserting expense into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&expense))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  sheet.remove_from_cached_value(expense.amount)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
+
+    AppError::internal("error when updating parent sheet in database")
+  })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
       .content_type("text/plain")
-      .body("Internal server error: error when parsing expense date")
-    );
-  }
+      .body("created")
+  )
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdateExpenseByIdBody {
   pub name: String,
-  pub amount: i32,
+  pub amount: String,
   pub date: String,
-  pub id: i32
+  pub id: i32,
+  #[serde(default)]
+  pub category_id: String,
+  #[serde(default)]
+  pub goal_id: String,
+  #[serde(default)]
+  pub account_id: String
 }
 
-pub async fn update_expense_by_id(req: HttpRequest, form: web::Form<UpdateExpenseByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
-  let some_expense = Expense::get_by_id(form.id).map_err(|err| {
+pub async fn update_expense_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateExpenseByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut expense = Expense::get_by_id(form.id).map_err(|err| {
     println!("error when fetching expense {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching expense from database")
-  })?;
+    AppError::internal("error when searching expense from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such expense"))?;
 
-  if let Some(mut expense) = some_expense {
-    let before_update_amount = expense.amount;
+  ensure_not_in_transfer("expense", expense.id)?;
 
-    expense.amount = form.amount;
-    expense.name = form.name.clone();
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let category_id = validate_category_id(&mut errors, "category_id", &form.category_id, expense.sheet_id)?;
+  let goal_id = validate_goal_id(&mut errors, "goal_id", &form.goal_id)?;
+  let account_id = validate_account_id(&mut errors, "account_id", &form.account_id)?;
 
-    if let Ok(date) = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d") {
-      expense.date = date.and_hms(0, 0, 0).timestamp();
-    }  
+  if !errors.is_empty() {
+    let page = pages::edit_expense::render_form(&req, &form, expense.sheet_id, &errors);
 
-    expense.update().map_err(|err| {
-      println!("error when updating expense {}", err);
+    return Err(AppError::invalid_form(errors, page));
+  }
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when renaming sheet from database")
-    })?;
+  let before = expense.clone();
+  let before_update_amount = expense.amount;
 
-    let some_sheet = Sheet::get_by_id(expense.sheet_id)
-    .map_err(|err| {
-      println!("error when fetching parent sheet {}", err);
+  expense.name = name;
+  expense.amount = amount;
+  expense.date = date;
+  expense.category_id = category_id;
+  expense.goal_id = goal_id;
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
-    })?;
-    
-    if let Some(mut sheet) = some_sheet {
-      let difference_with_update = expense.amount - before_update_amount;
+  // the statement no longer matches a changed amount or account
+  if expense.amount != before.amount || account_id != before.account_id {
+    expense.cleared_date = None;
+  }
 
-      sheet.remove_from_cached_value(difference_with_update)
-      .map_err(|err| {
-        println!("error when updating parent sheet cached value {}", err);
+  expense.account_id = account_id;
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
-      })?;
-    }
-    else {
-      println!("no such sheet");
-    }
+  expense.update().map_err(|err| {
+    println!("error when updating expense {}", err);
+
+    AppError::internal("error when updating expense in database")
+  })?;
 
-    return Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, format!("/sheet/{}", expense.sheet_id))
-        .content_type("text/plain")
-        .body("update")
-    );
+  audit_entry::record(Some(&current.user), Some(&before), Some(&expense))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  let some_sheet = Sheet::get_by_id(expense.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?;
+  
+  if let Some(mut sheet) = some_sheet {
+    let difference_with_update = expense.amount - before_update_amount;
+
+    sheet.remove_from_cached_value(difference_with_update)
+    .map_err(|err| {
+      println!("error when updating parent sheet cached value {}", err);
+
+      AppError::internal("error when updating parent sheet in database")
+    })?;
   }
 
-  return Ok(
+  Ok(
     HttpResponse::Found()
-      .header(http::header::LOCATION, format!("/expenses/{}", form.id))
+      .header(http::header::LOCATION, format!("/sheet/{}", expense.sheet_id))
       .content_type("text/plain")
       .body("update")
-  );
+  )
 }
 
 #[derive(Serialize, Deserialize)]
@@ -192,52 +183,31 @@ pub struct DeleteExpenseByIdBody {
   pub sheet_id: i32
 }
 
-pub async fn delete_expense_by_id(req: HttpRequest, form: web::Form<DeleteExpenseByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
+pub async fn delete_expense_by_id(current: AuthenticatedUser, form: web::Form<DeleteExpenseByIdBody>) -> Result<HttpResponse, AppError> {
   let some_expense = Expense::get_by_id(form.id).map_err(|err| {
     println!("error when fetching expense {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching expense from database")
+    AppError::internal("error when searching expense from database")
   })?;
 
   if let Some(expense) = some_expense {
+    ensure_not_in_transfer("expense", expense.id)?;
+
     expense.remove().map_err(|err| {
       println!("error when removing expense {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when removing expense from database")
+      AppError::internal("error when removing expense from database")
     })?;
 
+    audit_entry::record(Some(&current.user), Some(&expense), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
     let some_sheet = Sheet::get_by_id(expense.sheet_id)
     .map_err(|err| {
       println!("error when fetching parent sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
+      AppError::internal("error when fetching parent sheet from database")
     })?;
     
     if let Some(mut sheet) = some_sheet {
@@ -245,9 +215,7 @@ pub async fn delete_expense_by_id(req: HttpRequest, form: web::Form<DeleteExpens
       .map_err(|err| {
         println!("error when updating parent sheet cached value {}", err);
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
+        AppError::internal("error when updating parent sheet in database")
       })?;
     }
   }
@@ -258,4 +226,50 @@ pub async fn delete_expense_by_id(req: HttpRequest, form: web::Form<DeleteExpens
       .content_type("text/plain")
       .body("created")
   )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RestoreExpenseByIdBody {
+  pub id: i32
+}
+
+pub async fn restore_expense_by_id(current: AuthenticatedUser, form: web::Form<RestoreExpenseByIdBody>) -> Result<HttpResponse, AppError> {
+  let expense = Expense::get_deleted_by_id(form.id).map_err(|err| {
+    println!("error when fetching deleted expense {}", err);
+
+    AppError::internal("error when searching expense from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such expense in the trash"))?;
+
+  let mut sheet = Sheet::get_by_id(expense.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::conflict("the sheet of this expense is in the trash, restore it first"))?;
+
+  expense.restore().map_err(|err| {
+    println!("error when restoring expense {}", err);
+
+    AppError::internal("error when restoring expense from database")
+  })?;
+
+  sheet.remove_from_cached_value(expense.amount)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
+
+    AppError::internal("error when updating parent sheet in database")
+  })?;
+
+  audit_entry::record_restore(Some(&current.user), &expense)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("restored")
+  )
 }
\ No newline at end of file
@@ -0,0 +1,40 @@
+use actix_web::{http, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::utils::req_auth::AdminUser;
+use crate::models::failed_attempt;
+use crate::errors::AppError;
+
+#[derive(Serialize, Deserialize)]
+pub struct ClearFailedAttemptsBody {
+  pub handle: Option<String>,
+  pub ip: Option<String>
+}
+
+/// Forgets the failed attempts of a handle or an ip, lifting their backoff
+/// or lockout.
+pub async fn clear_failed_attempts(_admin: AdminUser, form: web::Form<ClearFailedAttemptsBody>) -> Result<HttpResponse, AppError> {
+  if let Some(handle) = &form.handle {
+    failed_attempt::remove_all_by_handle(handle)
+      .map_err(|err| {
+        println!("error when removing failed attempts {}", err);
+
+        AppError::internal("error when removing failed attempts from database")
+      })?;
+  }
+
+  if let Some(ip) = &form.ip {
+    failed_attempt::remove_all_by_ip(ip)
+      .map_err(|err| {
+        println!("error when removing failed attempts {}", err);
+
+        AppError::internal("error when removing failed attempts from database")
+      })?;
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/failed-attempts")
+      .content_type("text/plain")
+      .body("cleared")
+  )
+}
@@ -0,0 +1,196 @@
+use crate::models::goal::Goal;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateGoalBody {
+  pub name: String,
+  pub target: String,
+
+  /// left empty when there is no deadline
+  pub deadline: String,
+
+  /// the id of the dedicated sheet, left empty when there is none
+  #[serde(default)]
+  pub sheet_id: String
+}
+
+pub async fn create_goal(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateGoalBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let target = validation::amount(&mut errors, "target", &form.target);
+  let deadline = validation::optional_date(&mut errors, "deadline", &form.deadline);
+  let sheet_id = validate_sheet_id(&mut errors, "sheet_id", &form.sheet_id)?;
+
+  if !errors.is_empty() {
+    let page = pages::new_goal::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut goal = Goal::new(&name, target);
+  goal.deadline = deadline;
+  goal.sheet_id = sheet_id;
+  goal.user_id = Some(current.user.id);
+
+  goal.insert().map_err(|err| {
+    println!("error when creating goal {}", err);
+
+    AppError::internal("error when inserting goal into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&goal))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/goal/{}", goal.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateGoalByIdBody {
+  pub name: String,
+  pub target: String,
+  pub deadline: String,
+  #[serde(default)]
+  pub sheet_id: String,
+  pub id: i32
+}
+
+pub async fn update_goal_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateGoalByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut goal = Goal::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching goal {}", err);
+
+    AppError::internal("error when searching goal from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such goal"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let target = validation::amount(&mut errors, "target", &form.target);
+  let deadline = validation::optional_date(&mut errors, "deadline", &form.deadline);
+  let sheet_id = validate_sheet_id(&mut errors, "sheet_id", &form.sheet_id)?;
+
+  if !errors.is_empty() {
+    let page = pages::edit_goal::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = goal.clone();
+  goal.name = name;
+  goal.target = target;
+  goal.deadline = deadline;
+  goal.sheet_id = sheet_id;
+
+  goal.update().map_err(|err| {
+    println!("error when updating goal {}", err);
+
+    AppError::internal("error when updating goal in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&goal))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/goal/{}", goal.id))
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteGoalByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_goal_by_id(current: AuthenticatedUser, form: web::Form<DeleteGoalByIdBody>) -> Result<HttpResponse, AppError> {
+  let goal = Goal::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching goal {}", err);
+
+    AppError::internal("error when searching goal from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such goal"))?;
+
+  goal.remove().map_err(|err| {
+    println!("error when removing goal {}", err);
+
+    AppError::internal("error when removing goal from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&goal), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/goals")
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+/// Returns the goal chosen in an expense or income form, `None` when the
+/// field is left empty.
+pub fn validate_goal_id(errors: &mut FormErrors, field: &'static str, value: &str) -> Result<Option<i32>, AppError> {
+  if value.trim().is_empty() {
+    return Ok(None);
+  }
+
+  let goal = match value.trim().parse::<i32>() {
+    Ok(id) => Goal::get_by_id(id).map_err(|err| {
+      println!("error when fetching goal {}", err);
+
+      AppError::internal("error when searching goal from database")
+    })?,
+    Err(_) => None
+  };
+
+  match goal {
+    Some(goal) => Ok(Some(goal.id)),
+    None => {
+      errors.add(field, String::from("no such goal"));
+
+      Ok(None)
+    }
+  }
+}
+
+/// Returns the dedicated sheet chosen in a goal form, `None` when the field
+/// is left empty.
+fn validate_sheet_id(errors: &mut FormErrors, field: &'static str, value: &str) -> Result<Option<i32>, AppError> {
+  if value.trim().is_empty() {
+    return Ok(None);
+  }
+
+  let sheet = match value.trim().parse::<i32>() {
+    Ok(id) => Sheet::get_by_id(id).map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when searching sheet from database")
+    })?,
+    Err(_) => None
+  };
+
+  match sheet {
+    Some(sheet) => Ok(Some(sheet.id)),
+    None => {
+      errors.add(field, String::from("no such sheet"));
+
+      Ok(None)
+    }
+  }
+}
\ No newline at end of file
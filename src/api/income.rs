@@ -1,186 +1,169 @@
+use crate::api::account::validate_account_id;
+use crate::api::goal::validate_goal_id;
+use crate::api::transfer::ensure_not_in_transfer;
 use crate::models::income::Income;
-use crate::models::user::{UserRole};
 use crate::models::sheet::Sheet;
-use crate::utils::req_auth::request_authentication;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
 
 use serde::{Deserialize, Serialize};
-use actix_web::{web, HttpRequest, HttpResponse, Result, http};
-use chrono::prelude::*;
-#[derive(Serialize, Deserialize)]
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateIncomeBody {
   pub name: String,
-  pub amount: i32,
+  pub amount: String,
   pub date: String,
-  pub sheet_id: i32
-}
+  pub sheet_id: i32,
 
-pub async fn create_income(req: HttpRequest, form: web::Form<CreateIncomeBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
+  /// the id of the goal, left empty when there is none
+  #[serde(default)]
+  pub goal_id: String,
 
-  if let Ok(date) = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d") {
-    let income = Income::new(&form.name, form.amount, form.sheet_id, date.and_hms(0, 0, 0).timestamp());
+  /// the id of the account, left empty when there is none
+  #[serde(default)]
+  pub account_id: String
+}
 
-    income.insert()
-    .map_err(|err| {
-      println!("error when creating income {}", err);
-  
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when inserting income into database")
-    })?;
+pub async fn create_income(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateIncomeBody>) -> Result<HttpResponse, AppError> {
+  let mut sheet = Sheet::get_by_id(form.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
 
-    let some_sheet = Sheet::get_by_id(income.sheet_id)
-    .map_err(|err| {
-      println!("error when fetching parent sheet {}", err);
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
-    })?;
-    
-    if let Some(mut sheet) = some_sheet {
-      sheet.add_to_cached_value(income.amount)
-      .map_err(|err| {
-        println!("error when updating parent sheet cached value {}", err);
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let goal_id = validate_goal_id(&mut errors, "goal_id", &form.goal_id)?;
+  let account_id = validate_account_id(&mut errors, "account_id", &form.account_id)?;
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
-      })?;
-    }
-    else {
-      println!("no such sheet");
-    }
-  
-    Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
-        .content_type("text/plain")
-        .body("created")
-    )
+  if !errors.is_empty() {
+    let page = pages::new_sheet_income::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
   }
-  else {
-    println!("error when parsing income date");
-  
-    return Ok(
-      HttpResponse::InternalServerError()
+
+  let mut income = Income::new(&name, amount, sheet.id, date);
+  income.goal_id = goal_id;
+  income.account_id = account_id;
+
+  income.insert()
+  .map_err(|err| {
+    println!("error when creating income {}", err);
+
+    AppError::internal("error when inserting income into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&income))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  sheet.add_to_cached_value(income.amount)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
+
+    AppError::internal("error when updating parent sheet in database")
+  })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
       .content_type("text/plain")
-      .body("Internal server error: error when parsing income date")
-    );
-  }
+      .body("created")
+  )
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdateincomeByIdBody {
   pub name: String,
-  pub amount: i32,
+  pub amount: String,
   pub date: String,
-  pub id: i32
+  pub id: i32,
+  #[serde(default)]
+  pub goal_id: String,
+  #[serde(default)]
+  pub account_id: String
 }
 
-pub async fn update_income_by_id(req: HttpRequest, form: web::Form<UpdateincomeByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
-  let some_income = Income::get_by_id(form.id).map_err(|err| {
+pub async fn update_income_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateincomeByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut income = Income::get_by_id(form.id).map_err(|err| {
     println!("error when fetching income {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching income from database")
-  })?;
+    AppError::internal("error when searching income from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such income"))?;
 
-  if let Some(mut income) = some_income {
-    let before_change_amount = income.amount;
+  ensure_not_in_transfer("income", income.id)?;
 
-    income.amount = form.amount;
-    income.name = form.name.clone();
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let goal_id = validate_goal_id(&mut errors, "goal_id", &form.goal_id)?;
+  let account_id = validate_account_id(&mut errors, "account_id", &form.account_id)?;
 
-    if let Ok(date) = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d") {
-      income.date = date.and_hms(0, 0, 0).timestamp();
-    }  
+  if !errors.is_empty() {
+    let page = pages::edit_income::render_form(&req, &form, income.sheet_id, &errors);
 
-    income.update().map_err(|err| {
-      println!("error when updating income {}", err);
+    return Err(AppError::invalid_form(errors, page));
+  }
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when renaming sheet from database")
-    })?;
+  let before = income.clone();
+  let before_update_amount = income.amount;
 
-    let some_sheet = Sheet::get_by_id(income.sheet_id)
-    .map_err(|err| {
-      println!("error when fetching parent sheet {}", err);
+  income.name = name;
+  income.amount = amount;
+  income.date = date;
+  income.goal_id = goal_id;
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
-    })?;
-    
-    if let Some(mut sheet) = some_sheet {
-      let difference_with_update = income.amount - before_change_amount;
-      
-      sheet.add_to_cached_value(difference_with_update)
-      .map_err(|err| {
-        println!("error when updating parent sheet cached value {}", err);
+  // the statement no longer matches a changed amount or account
+  if income.amount != before.amount || account_id != before.account_id {
+    income.cleared_date = None;
+  }
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
-      })?;
-    }
+  income.account_id = account_id;
+
+  income.update().map_err(|err| {
+    println!("error when updating income {}", err);
+
+    AppError::internal("error when updating income in database")
+  })?;
 
-    return Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, format!("/sheet/{}", income.sheet_id))
-        .content_type("text/plain")
-        .body("update")
-    );
+  audit_entry::record(Some(&current.user), Some(&before), Some(&income))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  let some_sheet = Sheet::get_by_id(income.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?;
+  
+  if let Some(mut sheet) = some_sheet {
+    let difference_with_update = income.amount - before_update_amount;
+
+    sheet.add_to_cached_value(difference_with_update)
+    .map_err(|err| {
+      println!("error when updating parent sheet cached value {}", err);
+
+      AppError::internal("error when updating parent sheet in database")
+    })?;
   }
 
-  return Ok(
+  Ok(
     HttpResponse::Found()
-      .header(http::header::LOCATION, format!("/incomes/{}", form.id))
+      .header(http::header::LOCATION, format!("/sheet/{}", income.sheet_id))
       .content_type("text/plain")
       .body("update")
-  );
+  )
 }
 
 #[derive(Serialize, Deserialize)]
@@ -189,52 +172,31 @@ pub struct DeleteincomeByIdBody {
   pub sheet_id: i32
 }
 
-pub async fn delete_income_by_id(req: HttpRequest, form: web::Form<DeleteincomeByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
+pub async fn delete_income_by_id(current: AuthenticatedUser, form: web::Form<DeleteincomeByIdBody>) -> Result<HttpResponse, AppError> {
   let some_income = Income::get_by_id(form.id).map_err(|err| {
     println!("error when fetching income {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching income from database")
+    AppError::internal("error when searching income from database")
   })?;
 
   if let Some(income) = some_income {
+    ensure_not_in_transfer("income", income.id)?;
+
     income.remove().map_err(|err| {
       println!("error when removing income {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when removing income from database")
+      AppError::internal("error when removing income from database")
     })?;
 
+    audit_entry::record(Some(&current.user), Some(&income), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
     let some_sheet = Sheet::get_by_id(income.sheet_id)
     .map_err(|err| {
       println!("error when fetching parent sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
+      AppError::internal("error when fetching parent sheet from database")
     })?;
     
     if let Some(mut sheet) = some_sheet {
@@ -242,9 +204,7 @@ pub async fn delete_income_by_id(req: HttpRequest, form: web::Form<DeleteincomeB
       .map_err(|err| {
         println!("error when updating parent sheet cached value {}", err);
 
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet in database")
+        AppError::internal("error when updating parent sheet in database")
       })?;
     }
   }
@@ -255,4 +215,50 @@ pub async fn delete_income_by_id(req: HttpRequest, form: web::Form<DeleteincomeB
       .content_type("text/plain")
       .body("created")
   )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RestoreIncomeByIdBody {
+  pub id: i32
+}
+
+pub async fn restore_income_by_id(current: AuthenticatedUser, form: web::Form<RestoreIncomeByIdBody>) -> Result<HttpResponse, AppError> {
+  let income = Income::get_deleted_by_id(form.id).map_err(|err| {
+    println!("error when fetching deleted income {}", err);
+
+    AppError::internal("error when searching income from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such income in the trash"))?;
+
+  let mut sheet = Sheet::get_by_id(income.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::conflict("the sheet of this income is in the trash, restore it first"))?;
+
+  income.restore().map_err(|err| {
+    println!("error when restoring income {}", err);
+
+    AppError::internal("error when restoring income from database")
+  })?;
+
+  sheet.add_to_cached_value(income.amount)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
+
+    AppError::internal("error when updating parent sheet in database")
+  })?;
+
+  audit_entry::record_restore(Some(&current.user), &income)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("restored")
+  )
 }
\ No newline at end of file
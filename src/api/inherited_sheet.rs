@@ -1,159 +1,137 @@
 use crate::models::inherited_sheet::InheritedSheet;
-use crate::models::user::{UserRole};
 use crate::models::sheet::Sheet;
-use crate::utils::req_auth::request_authentication;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
 
 use serde::{Deserialize, Serialize};
-use actix_web::{web, HttpRequest, HttpResponse, Result, http};
-use chrono::prelude::*;
-#[derive(Serialize, Deserialize)]
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateInheritedSheetBody {
   pub date: String,
   pub sheet_id: i32,
-  pub inherited_sheet_id: i32
+  pub inherited_sheet_id: Option<i32>
 }
 
-pub async fn create_inherited_sheet(req: HttpRequest, form: web::Form<CreateInheritedSheetBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
+pub async fn create_inherited_sheet(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateInheritedSheetBody>) -> Result<HttpResponse, AppError> {
+  let mut parent_sheet = Sheet::get_by_id(form.sheet_id).map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let date = validation::date(&mut errors, "date", &form.date);
+
+  let child_sheet = match form.inherited_sheet_id {
+    Some(id) if id == form.sheet_id => {
+      errors.add("inherited_sheet_id", String::from("a sheet cannot import itself"));
+
+      None
+    },
+    Some(id) => {
+      let child_sheet = Sheet::get_by_id(id).map_err(|err| {
+        println!("error when fetching child sheet {}", err);
+
+        AppError::internal("error when fetching child sheet from database")
+      })?;
+
+      if child_sheet.is_none() {
+        errors.add("inherited_sheet_id", String::from("no such sheet"));
       }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
+      else if InheritedSheet::imports(id, form.sheet_id).map_err(|err| {
+        println!("error when walking the imports of the child sheet {}", err);
+
+        AppError::internal("error when checking the imports of the sheet")
+      })? {
+        errors.add("inherited_sheet_id", String::from("this sheet already imports the current sheet"));
+      }
+
+      child_sheet
+    },
+    None => {
+      errors.add("inherited_sheet_id", String::from("choose the sheet to import"));
+
+      None
     }
   };
 
-  if let Ok(date) = NaiveDate::parse_from_str(&form.date, "%Y-%m-%d") {
-    let inherited_sheet = InheritedSheet::new(form.sheet_id, form.inherited_sheet_id, date.and_hms(0, 0, 0).timestamp());
+  let child_sheet = match child_sheet {
+    Some(child_sheet) if errors.is_empty() => child_sheet,
+    _ => {
+      let page = pages::new_sheet_inherited_sheet::render_form(&req, &form, &errors);
 
-    inherited_sheet.insert()
-    .map_err(|err| {
-      println!("error when creating inherited_sheet {}", err);
-  
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when inserting inherited_sheet into database")
-    })?;
+      return Err(AppError::invalid_form(errors, page));
+    }
+  };
 
-    let parent_sheet = Sheet::get_by_id(inherited_sheet.parent_sheet_id).map_err(|err| {
-      println!("error when fetching parent sheet {}", err);
+  let inherited_sheet = InheritedSheet::new(parent_sheet.id, child_sheet.id, date);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
-    })?;
+  inherited_sheet.insert()
+  .map_err(|err| {
+    println!("error when creating inherited_sheet {}", err);
 
-    let child_sheet = Sheet::get_by_id(inherited_sheet.inherited_sheet_id).map_err(|err| {
-      println!("error when fetching child sheet {}", err);
+    AppError::internal("error when inserting inherited_sheet into database")
+  })?;
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching child sheet from database")
-    })?;
+  audit_entry::record(Some(&current.user), None, Some(&inherited_sheet))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
 
-    match (parent_sheet, child_sheet) {
-      (Some(mut parent_sheet), Some(child_sheet)) => {
-        parent_sheet.add_to_cached_value(child_sheet.cached_value)
-        .map_err(|err| {
-          println!("error when updating parent sheet cached value {}", err);
+  parent_sheet.add_to_cached_value(child_sheet.cached_value)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
 
-          HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet cached value in database")
-        })?;
-      },
-      _ => {}
-    };
-  
-    Ok(
-      HttpResponse::Found()
-        .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
-        .content_type("text/plain")
-        .body("created")
-    )
-  }
-  else {
-    println!("error when parsing inherited_sheet date");
-  
-    return Ok(
-      HttpResponse::InternalServerError()
+    AppError::internal("error when updating parent sheet cached value in database")
+  })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
       .content_type("text/plain")
-      .body("Internal server error: error when parsing inherited_sheet date")
-    );
-  }
+      .body("created")
+  )
 }
+
 #[derive(Serialize, Deserialize)]
 pub struct DeleteInheritedSheetByIdBody {
   pub sheet_id: i32,
   pub inherited_sheet_id: i32
 }
 
-pub async fn delete_inherited_sheet_by_id(req: HttpRequest, form: web::Form<DeleteInheritedSheetByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
+pub async fn delete_inherited_sheet_by_id(current: AuthenticatedUser, form: web::Form<DeleteInheritedSheetByIdBody>) -> Result<HttpResponse, AppError> {
   let some_inherited_sheet = InheritedSheet::get_by_parent_and_inherited_id(form.sheet_id, form.inherited_sheet_id)
   .map_err(|err| {
     println!("error when fetching inherited sheet {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching inherited sheet from database")
+    AppError::internal("error when searching inherited sheet from database")
   })?;
 
   if let Some(inherited_sheet) = some_inherited_sheet {
     inherited_sheet.remove().map_err(|err| {
       println!("error when removing inherited sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when removing inherited sheet from database")
+      AppError::internal("error when removing inherited sheet from database")
     })?;
 
+    audit_entry::record(Some(&current.user), Some(&inherited_sheet), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
     let parent_sheet = Sheet::get_by_id(inherited_sheet.parent_sheet_id).map_err(|err| {
       println!("error when fetching parent sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching parent sheet from database")
+      AppError::internal("error when fetching parent sheet from database")
     })?;
 
     let child_sheet = Sheet::get_by_id(inherited_sheet.inherited_sheet_id).map_err(|err| {
       println!("error when fetching child sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when fetching child sheet from database")
+      AppError::internal("error when fetching child sheet from database")
     })?;
 
     match (parent_sheet, child_sheet) {
@@ -162,9 +140,7 @@ pub async fn delete_inherited_sheet_by_id(req: HttpRequest, form: web::Form<Dele
         .map_err(|err| {
           println!("error when updating parent sheet cached value {}", err);
 
-          HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body("Internal server error: error when updating parent sheet cached value in database")
+          AppError::internal("error when updating parent sheet cached value in database")
         })?;
       },
       _ => {}
@@ -177,4 +153,75 @@ pub async fn delete_inherited_sheet_by_id(req: HttpRequest, form: web::Form<Dele
       .content_type("text/plain")
       .body("created")
   )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RestoreInheritedSheetBody {
+  pub sheet_id: i32,
+  pub inherited_sheet_id: i32,
+
+  /// tells apart the imports of the same sheet that were deleted
+  pub deleted_date: i64
+}
+
+pub async fn restore_inherited_sheet(current: AuthenticatedUser, form: web::Form<RestoreInheritedSheetBody>) -> Result<HttpResponse, AppError> {
+  let inherited_sheet = InheritedSheet::get_deleted(form.sheet_id, form.inherited_sheet_id, form.deleted_date)
+  .map_err(|err| {
+    println!("error when fetching deleted inherited sheet {}", err);
+
+    AppError::internal("error when searching inherited sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such inherited sheet in the trash"))?;
+
+  let existing_inherited_sheet = InheritedSheet::get_by_parent_and_inherited_id(form.sheet_id, form.inherited_sheet_id)
+  .map_err(|err| {
+    println!("error when fetching inherited sheet {}", err);
+
+    AppError::internal("error when searching inherited sheet from database")
+  })?;
+
+  if existing_inherited_sheet.is_some() {
+    return Err(AppError::conflict("the sheet is already imported again"));
+  }
+
+  let parent_sheet = Sheet::get_by_id(inherited_sheet.parent_sheet_id).map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?;
+
+  let child_sheet = Sheet::get_by_id(inherited_sheet.inherited_sheet_id).map_err(|err| {
+    println!("error when fetching child sheet {}", err);
+
+    AppError::internal("error when fetching child sheet from database")
+  })?;
+
+  let (mut parent_sheet, child_sheet) = match (parent_sheet, child_sheet) {
+    (Some(parent_sheet), Some(child_sheet)) => (parent_sheet, child_sheet),
+    _ => return Err(AppError::conflict("one of the sheets is in the trash, restore it first"))
+  };
+
+  inherited_sheet.restore().map_err(|err| {
+    println!("error when restoring inherited sheet {}", err);
+
+    AppError::internal("error when restoring inherited sheet from database")
+  })?;
+
+  parent_sheet.add_to_cached_value(child_sheet.cached_value)
+  .map_err(|err| {
+    println!("error when updating parent sheet cached value {}", err);
+
+    AppError::internal("error when updating parent sheet cached value in database")
+  })?;
+
+  audit_entry::record_restore(Some(&current.user), &inherited_sheet)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", form.sheet_id))
+      .content_type("text/plain")
+      .body("restored")
+  )
 }
\ No newline at end of file
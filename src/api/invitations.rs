@@ -1,70 +1,164 @@
-use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::{http, web, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
-use crate::models::user::UserRole;
-use crate::utils::req_auth::{request_authentication};
-use crate::models::invitation::{Invitation, update_invitation_expiration};
+use crate::models::user::{User, UserRole};
+use crate::utils::req_auth::AdminUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::models::invitation::{Invitation, update_invitation_expiration, update_invitation_role};
+use crate::models::audit_entry;
+use crate::errors::AppError;
+use crate::pages;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateInvitationBody {
   pub handle: String,
   pub role: i32,
 }
 
-pub async fn create_invitation(req: HttpRequest, form: web::Form<CreateInvitationBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Admin)
+pub async fn create_invitation(req: HttpRequest, admin: AdminUser, form: web::Form<CreateInvitationBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let handle = validation::name(&mut errors, "handle", &form.handle);
+  let role = UserRole::from_number(form.role);
+
+  if let UserRole::None = role {
+    errors.add("role", String::from("unknown role"));
+  }
+
+  if !errors.is_empty() {
+    let page = pages::admin_invitations::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let existing_user = User::get_by_handle(&handle)
     .map_err(|err| {
-      println!("error");
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(err)
+      println!("error when fetching user by handle {}", err);
+
+      AppError::internal("error when checking if the handle is taken")
     })?;
 
-  if !auth_result.has_access() {
-    return Ok(
-      HttpResponse::NotFound()
-        .content_type("text/plain")
-        .body("HTTP 404: Not found")
-    ); 
+  if existing_user.is_some() {
+    return Err(AppError::conflict("a user with this handle already exists"));
   }
 
-  let invitation = if let Some(inv) = Invitation::get_by_handle(&form.handle)
-    .map_err(|_err| {
-      println!("error when checking if invitation already exists");
+  let invitation = if let Some(inv) = Invitation::get_by_handle(&handle)
+    .map_err(|err| {
+      println!("error when checking if invitation already exists {}", err);
 
-      HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when checking if invitation already exists")
+      AppError::internal("error when checking if invitation already exists")
     })? {
     
-    update_invitation_expiration(&form.handle)
-      .map_err(|_err| {
-        println!("error when updating already existing invitation");
+    update_invitation_expiration(&handle)
+      .and_then(|_| update_invitation_role(&handle, role))
+      .map_err(|err| {
+        println!("error when updating already existing invitation {}", err);
 
-        HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when updating already existing invitation")
+        AppError::internal("error when updating already existing invitation")
       })?;
 
+    record_extension(&admin.user, &inv);
+
     inv
   }
   else {
-    let invitation = Invitation::new(&form.handle, UserRole::from_number(form.role));
+    let mut invitation = Invitation::new(&handle, role);
 
-    Invitation::insert(&invitation)
-    .map_err(|_err| {
-      println!("error when inserting new invitation");
+    Invitation::insert(&mut invitation)
+    .map_err(|err| {
+      println!("error when inserting new invitation {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: invitation insertion failed")
+      AppError::internal("invitation insertion failed")
     })?;
 
+    audit_entry::record(Some(&admin.user), None, Some(&invitation))
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+
     invitation
   };
 
   Ok(
     HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/invitations")
       .content_type("text/plain")
       .body(format!("created: {}", invitation.to_url()))
   )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InvitationByIdBody {
+  pub id: i32
+}
+
+pub async fn extend_invitation_by_id(admin: AdminUser, form: web::Form<InvitationByIdBody>) -> Result<HttpResponse, AppError> {
+  let some_invitation = Invitation::get_by_id(form.id)
+    .map_err(|err| {
+      println!("error when fetching invitation {}", err);
+
+      AppError::internal("error when searching invitation from database")
+    })?;
+
+  if let Some(invitation) = some_invitation {
+    update_invitation_expiration(&invitation.handle)
+      .map_err(|err| {
+        println!("error when extending invitation {}", err);
+
+        AppError::internal("error when extending invitation")
+      })?;
+
+    record_extension(&admin.user, &invitation);
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/invitations")
+      .content_type("text/plain")
+      .body("extended")
+  )
+}
+
+pub async fn delete_invitation_by_id(admin: AdminUser, form: web::Form<InvitationByIdBody>) -> Result<HttpResponse, AppError> {
+  let some_invitation = Invitation::get_by_id(form.id)
+    .map_err(|err| {
+      println!("error when fetching invitation {}", err);
+
+      AppError::internal("error when searching invitation from database")
+    })?;
+
+  if let Some(invitation) = some_invitation {
+    Invitation::remove(&invitation)
+      .map_err(|err| {
+        println!("error when removing invitation {}", err);
+
+        AppError::internal("error when removing invitation from database")
+      })?;
+
+    audit_entry::record(Some(&admin.user), Some(&invitation), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/invitations")
+      .content_type("text/plain")
+      .body("revoked")
+  )
+}
+
+/// Logs the new expiration date and role of an invitation, `before` is the invitation
+/// as it was fetched before the update.
+fn record_extension(admin: &User, before: &Invitation) {
+  let after = match Invitation::get_by_id(before.id) {
+    Ok(Some(after)) => after,
+    Ok(None) => return,
+    Err(err) => {
+      println!("error when fetching invitation {}", err);
+
+      return;
+    }
+  };
+
+  audit_entry::record(Some(admin), Some(before), Some(&after))
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
 }
\ No newline at end of file
@@ -4,4 +4,16 @@ pub mod invitations;
 pub mod users;
 pub mod expense;
 pub mod income;
-pub mod inherited_sheet;
\ No newline at end of file
+pub mod inherited_sheet;
+pub mod sessions;
+pub mod password_resets;
+pub mod two_factor;
+pub mod failed_attempts;
+pub mod sheet_template;
+pub mod category;
+pub mod goal;
+pub mod recurring_entry;
+pub mod account;
+pub mod transfer;
+pub mod split;
+pub mod settlement;
\ No newline at end of file
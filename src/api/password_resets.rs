@@ -0,0 +1,68 @@
+use actix_web::{http, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use crate::models::user::User;
+use crate::utils::req_auth::AdminUser;
+use crate::models::password_reset::PasswordReset;
+use crate::errors::AppError;
+
+#[derive(Serialize, Deserialize)]
+pub struct CreatePasswordResetBody {
+  pub user_id: i32
+}
+
+pub async fn create_password_reset(_admin: AdminUser, form: web::Form<CreatePasswordResetBody>) -> Result<HttpResponse, AppError> {
+  let some_user = User::get_by_id(form.user_id)
+    .map_err(|err| {
+      println!("error when fetching user {}", err);
+
+      AppError::internal("error when searching user from database")
+    })?;
+
+  if some_user.is_none() {
+    return Err(AppError::not_found("no such user"));
+  }
+
+  PasswordReset::new(form.user_id).insert()
+    .map_err(|err| {
+      println!("error when inserting password reset {}", err);
+
+      AppError::internal("error when inserting password reset")
+    })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/password-resets")
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PasswordResetByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_password_reset_by_id(_admin: AdminUser, form: web::Form<PasswordResetByIdBody>) -> Result<HttpResponse, AppError> {
+  let some_reset = PasswordReset::get_by_id(form.id)
+    .map_err(|err| {
+      println!("error when fetching password reset {}", err);
+
+      AppError::internal("error when searching password reset from database")
+    })?;
+
+  if let Some(reset) = some_reset {
+    reset.remove()
+      .map_err(|err| {
+        println!("error when removing password reset {}", err);
+
+        AppError::internal("error when removing password reset from database")
+      })?;
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/password-resets")
+      .content_type("text/plain")
+      .body("revoked")
+  )
+}
@@ -0,0 +1,119 @@
+use crate::models::recurring_entry::RecurringEntry;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry;
+use crate::utils::date::Period;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateRecurringEntryBody {
+  pub sheet_id: i32,
+
+  /// either `expense` or `income`
+  pub kind: String,
+  pub name: String,
+  pub amount: String,
+  pub start_date: String,
+  pub period: String,
+
+  /// left empty when the entry does not end
+  pub end_date: String
+}
+
+pub async fn create_recurring_entry(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateRecurringEntryBody>) -> Result<HttpResponse, AppError> {
+  let sheet = Sheet::get_by_id(form.sheet_id).map_err(|err| {
+    println!("error when fetching parent sheet {}", err);
+
+    AppError::internal("error when fetching parent sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let start_date = validation::date(&mut errors, "start_date", &form.start_date);
+  let period = validation::period(&mut errors, "period", &form.period);
+  let end_date = validation::optional_date(&mut errors, "end_date", &form.end_date);
+
+  if form.kind != "expense" && form.kind != "income" {
+    errors.add("kind", String::from("the entry must be an expense or an income"));
+  }
+
+  if period == Period::None {
+    errors.add("period", String::from("choose how often the entry comes back"));
+  }
+
+  if end_date.is_some_and(|end_date| end_date < start_date) {
+    errors.add("end_date", String::from("the end date cannot be before the start date"));
+  }
+
+  if !errors.is_empty() {
+    let page = pages::new_recurring_entry::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut entry = RecurringEntry {
+    id: 0,
+    sheet_id: sheet.id,
+    kind: form.kind.clone(),
+    name,
+    amount,
+    start_date,
+    period,
+    end_date
+  };
+
+  entry.insert().map_err(|err| {
+    println!("error when creating recurring entry {}", err);
+
+    AppError::internal("error when inserting recurring entry into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&entry))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}/forecast", sheet.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteRecurringEntryByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_recurring_entry_by_id(current: AuthenticatedUser, form: web::Form<DeleteRecurringEntryByIdBody>) -> Result<HttpResponse, AppError> {
+  let entry = RecurringEntry::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching recurring entry {}", err);
+
+    AppError::internal("error when searching recurring entry from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such recurring entry"))?;
+
+  entry.remove().map_err(|err| {
+    println!("error when removing recurring entry {}", err);
+
+    AppError::internal("error when removing recurring entry from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&entry), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}/forecast", entry.sheet_id))
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+use crate::models::session::Session;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::errors::AppError;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpResponse, http};
+
+#[derive(Serialize, Deserialize)]
+pub struct RevokeSessionByIdBody {
+  pub id: i32
+}
+
+pub async fn revoke_session_by_id(current: AuthenticatedUser, form: web::Form<RevokeSessionByIdBody>) -> Result<HttpResponse, AppError> {
+  let current_session = current.session;
+
+  let some_session = Session::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching session {}", err);
+
+    AppError::internal("error when searching session from database")
+  })?;
+
+  // a user can only revoke their own sessions
+  if let Some(session) = some_session.filter(|session| session.user_id == current_session.user_id) {
+    session.remove().map_err(|err| {
+      println!("error when removing session {}", err);
+
+      AppError::internal("error when removing session from database")
+    })?;
+
+    if session.id == current_session.id {
+      return Ok(
+        HttpResponse::Found()
+          .header(http::header::LOCATION, "/signin")
+          .content_type("text/plain")
+          .body("revoked")
+      );
+    }
+  }
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/sessions")
+      .content_type("text/plain")
+      .body("revoked")
+  )
+}
@@ -0,0 +1,105 @@
+use crate::api::transfer::{add_to_sheet, validate_sheet_ids};
+use crate::models::settlement::Settlement;
+use crate::models::transfer::Transfer;
+use crate::models::sheet::Sheet;
+use crate::models::user::User;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateSettlementBody {
+  /// the sheet whose split expenses are settled
+  pub sheet_id: i32,
+  pub from_user_id: String,
+  pub to_user_id: String,
+  pub amount: String,
+  pub date: String,
+
+  /// the sheets of the transfer moving the money
+  pub from_sheet_id: String,
+  pub to_sheet_id: String
+}
+
+pub async fn create_settlement(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateSettlementBody>) -> Result<HttpResponse, AppError> {
+  let sheet = Sheet::get_by_id(form.sheet_id)
+  .map_err(|err| {
+    println!("error when fetching sheet {}", err);
+
+    AppError::internal("error when fetching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let from_user = validate_user_id(&mut errors, "from_user_id", &form.from_user_id)?;
+  let to_user = validate_user_id(&mut errors, "to_user_id", &form.to_user_id)?;
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let (from_sheet_id, to_sheet_id) = validate_sheet_ids(&mut errors, &form.from_sheet_id, &form.to_sheet_id)?;
+
+  if let (Some(from_user), Some(to_user)) = (&from_user, &to_user) {
+    if from_user.id == to_user.id {
+      errors.add("to_user_id", String::from("the payment must go to another user"));
+    }
+  }
+
+  let (from_user, to_user) = match (from_user, to_user) {
+    (Some(from_user), Some(to_user)) if errors.is_empty() => (from_user, to_user),
+    _ => {
+      let page = pages::new_settlement::render_form(&req, &form, &errors);
+
+      return Err(AppError::invalid_form(errors, page));
+    }
+  };
+
+  let mut transfer = Transfer::new(&format!("{} pays {}", from_user.handle, to_user.handle), amount, date, from_sheet_id, to_sheet_id);
+  transfer.user_id = Some(current.user.id);
+
+  let mut settlement = Settlement::new(sheet.id, from_user.id, to_user.id);
+
+  settlement.insert(&mut transfer).map_err(|err| {
+    println!("error when creating settlement {}", err);
+
+    AppError::internal("error when inserting settlement into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&transfer))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  audit_entry::record(Some(&current.user), None, Some(&settlement))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  add_to_sheet(transfer.from_sheet_id, -transfer.amount)?;
+  add_to_sheet(transfer.to_sheet_id, transfer.amount)?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}/settle-up", sheet.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+fn validate_user_id(errors: &mut FormErrors, field: &'static str, value: &str) -> Result<Option<User>, AppError> {
+  let user = match value.trim().parse::<i32>() {
+    Ok(id) => User::get_by_id(id).map_err(|err| {
+      println!("error when fetching user {}", err);
+
+      AppError::internal("error when searching user from database")
+    })?,
+    Err(_) => None
+  };
+
+  if user.is_none() {
+    errors.add(field, String::from("no such user"));
+  }
+
+  Ok(user)
+}
\ No newline at end of file
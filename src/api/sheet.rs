@@ -1,56 +1,44 @@
-use crate::models::sheet::Sheet;
-use crate::models::user::{UserRole};
-use crate::utils::req_auth::request_authentication;
+use crate::api::sheet_template::CreateSheetFromTemplateBody;
+use crate::models::sheet::{Sheet, SheetRows};
+use crate::models::user::User;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
 
 use serde::{Deserialize, Serialize};
-use actix_web::{web, HttpRequest, HttpResponse, Result, http};
+use actix_web::{web, HttpRequest, HttpResponse, http};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct CreateSheetBody {
   pub name: String,
 }
 
-pub async fn create_sheet(req: HttpRequest, form: web::Form<CreateSheetBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
-  if form.name.len() == 0 {
-    return Ok(
-      HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("sheet name is required")
-    );
+pub async fn create_sheet(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateSheetBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+
+  if !errors.is_empty() {
+    let page = pages::new_sheet::render_form(&req, current.user.id, &form, &CreateSheetFromTemplateBody::default(), &errors);
+
+    return Err(AppError::invalid_form(errors, page));
   }
 
-  let sheet = Sheet::new(&form.name);
+  let mut sheet = Sheet::new(&name);
+  sheet.user_id = Some(current.user.id);
   
   sheet.insert()
   .map_err(|err| {
     println!("error when creating sheet {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when inserting sheet into database")
+    AppError::internal("error when inserting sheet into database")
   })?;
 
+  audit_entry::record(Some(&current.user), None, Some(&sheet))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
   Ok(
     HttpResponse::Found()
       .header(http::header::LOCATION, "/sheets")
@@ -65,56 +53,36 @@ pub struct RenameSheetByIdBody {
   pub id: i32
 }
 
-pub async fn rename_sheet_by_id(req: HttpRequest, form: web::Form<RenameSheetByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
-  if form.name.len() == 0 {
-    return Ok(
-      HttpResponse::Unauthorized()
-        .content_type("text/plain")
-        .body("sheet name is required")
-    );
-  }
-
-  let some_sheet = Sheet::get_by_id(form.id).map_err(|err| {
+pub async fn rename_sheet_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<RenameSheetByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut sheet = Sheet::get_by_id(form.id).map_err(|err| {
     println!("error when fetching sheet {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching sheet from database")
-  })?;
+    AppError::internal("error when searching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
 
-  if let Some(mut sheet) = some_sheet {
-    sheet.name = form.name.clone();
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
 
-    sheet.update().map_err(|err| {
-      println!("error when removing sheet {}", err);
+  if !errors.is_empty() {
+    let page = pages::rename_sheet::render_form(&req, &form, &errors);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when renaming sheet from database")
-    })?;
+    return Err(AppError::invalid_form(errors, page));
   }
 
+  let before = sheet.clone();
+  sheet.name = name;
+
+  sheet.update().map_err(|err| {
+    println!("error when renaming sheet {}", err);
+
+    AppError::internal("error when renaming sheet from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&sheet))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
   Ok(
     HttpResponse::Found()
       .header(http::header::LOCATION, "/sheets")
@@ -123,49 +91,73 @@ pub async fn rename_sheet_by_id(req: HttpRequest, form: web::Form<RenameSheetByI
   )
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SetSheetBudgetByIdBody {
+  /// left empty to remove the budget
+  pub budget: String,
+  pub id: i32
+}
+
+pub async fn set_sheet_budget_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<SetSheetBudgetByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut sheet = Sheet::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching sheet {}", err);
+
+    AppError::internal("error when searching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let budget = validation::optional_amount(&mut errors, "budget", &form.budget);
+
+  if !errors.is_empty() {
+    let page = pages::sheet_budget::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = sheet.clone();
+  sheet.budget = budget;
+
+  sheet.update().map_err(|err| {
+    println!("error when setting sheet budget {}", err);
+
+    AppError::internal("error when updating sheet in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&sheet))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DeleteSheetByIdBody {
   pub id: i32,
 }
 
-pub async fn delete_sheet_by_id(req: HttpRequest, form: web::Form<DeleteSheetByIdBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return Ok(
-          HttpResponse::NotFound()
-            .content_type("text/plain")
-            .body("HTTP 404: Not found"),
-        );
-      }
-    }
-    Err(e) => {
-      return Ok(
-        HttpResponse::InternalServerError()
-          .content_type("text/plain")
-          .body(e),
-      )
-    }
-  };
-
+pub async fn delete_sheet_by_id(current: AuthenticatedUser, form: web::Form<DeleteSheetByIdBody>) -> Result<HttpResponse, AppError> {
   let some_sheet = Sheet::get_by_id(form.id).map_err(|err| {
     println!("error when fetching sheet {}", err);
 
-    HttpResponse::InternalServerError()
-      .content_type("text/plain")
-      .body("Internal server error: error when searching sheet from database")
+    AppError::internal("error when searching sheet from database")
   })?;
 
   if let Some(sheet) = some_sheet {
     sheet.remove().map_err(|err| {
       println!("error when removing sheet {}", err);
 
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: error when removing sheet from database")
+      AppError::internal("error when removing sheet from database")
     })?;
+
+    audit_entry::record(Some(&current.user), Some(&sheet), None)
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
   }
 
   Ok(
@@ -174,4 +166,106 @@ pub async fn delete_sheet_by_id(req: HttpRequest, form: web::Form<DeleteSheetByI
       .content_type("text/plain")
       .body("created")
   )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RestoreSheetByIdBody {
+  pub id: i32,
+}
+
+pub async fn restore_sheet_by_id(current: AuthenticatedUser, form: web::Form<RestoreSheetByIdBody>) -> Result<HttpResponse, AppError> {
+  let sheet = Sheet::get_deleted_by_id(form.id).map_err(|err| {
+    println!("error when fetching deleted sheet {}", err);
+
+    AppError::internal("error when searching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet in the trash"))?;
+
+  sheet.restore().map_err(|err| {
+    println!("error when restoring sheet {}", err);
+
+    AppError::internal("error when restoring sheet from database")
+  })?;
+
+  audit_entry::record_restore(Some(&current.user), &sheet)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("restored")
+  )
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DuplicateSheetByIdBody {
+  pub id: i32,
+  pub name: String,
+
+  /// the period the dates of the copied rows are moved forward by
+  pub shift: String
+}
+
+pub async fn duplicate_sheet_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<DuplicateSheetByIdBody>) -> Result<HttpResponse, AppError> {
+  let source = Sheet::get_by_id(form.id).map_err(|err| {
+    println!("error when fetching sheet {}", err);
+
+    AppError::internal("error when searching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let shift = validation::period(&mut errors, "shift", &form.shift);
+
+  if !errors.is_empty() {
+    let page = pages::duplicate_sheet::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut rows = SheetRows::get_by_sheet_id(source.id).map_err(|err| {
+    println!("error when fetching sheet rows {}", err);
+
+    AppError::internal("error when fetching sheet rows from database")
+  })?;
+
+  rows.shift_dates(shift);
+
+  let mut sheet = Sheet::new(&name);
+  sheet.user_id = Some(current.user.id);
+  sheet.budget = source.budget;
+
+  sheet.insert_with_rows(&mut rows).map_err(|err| {
+    println!("error when duplicating sheet {}", err);
+
+    AppError::internal("error when inserting sheet copy into database")
+  })?;
+
+  record_rows(&current.user, &sheet, &rows);
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("duplicated")
+  )
+}
+
+/// Records the creation of a sheet made with `Sheet::insert_with_rows` and of
+/// every row it received.
+pub fn record_rows(user: &User, sheet: &Sheet, rows: &SheetRows) {
+  let results = std::iter::once(audit_entry::record(Some(user), None, Some(sheet)))
+    .chain(rows.categories.iter().map(|category| audit_entry::record(Some(user), None, Some(category))))
+    .chain(rows.expenses.iter().map(|expense| audit_entry::record(Some(user), None, Some(expense))))
+    .chain(rows.incomes.iter().map(|income| audit_entry::record(Some(user), None, Some(income))))
+    .chain(rows.inherited_sheets.iter().map(|inherited_sheet| audit_entry::record(Some(user), None, Some(inherited_sheet))));
+
+  for result in results {
+    result
+    .map_err(|err| println!("error when recording audit entry {}", err))
+    .ok();
+  }
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+use crate::api::sheet::{CreateSheetBody, record_rows};
+use crate::models::sheet::{Sheet, SheetRows};
+use crate::models::sheet_template::SheetTemplate;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateSheetTemplateBody {
+  pub sheet_id: i32,
+  pub name: String
+}
+
+pub async fn create_sheet_template(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateSheetTemplateBody>) -> Result<HttpResponse, AppError> {
+  let sheet = Sheet::get_by_id(form.sheet_id).map_err(|err| {
+    println!("error when fetching sheet {}", err);
+
+    AppError::internal("error when searching sheet from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+
+  if !errors.is_empty() {
+    let page = pages::new_sheet_template::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let rows = SheetRows::get_by_sheet_id(sheet.id).map_err(|err| {
+    println!("error when fetching sheet rows {}", err);
+
+    AppError::internal("error when fetching sheet rows from database")
+  })?;
+
+  let mut template = SheetTemplate::from_sheet(&name, current.user.id, &sheet, &rows);
+
+  template.insert().map_err(|err| {
+    println!("error when creating sheet template {}", err);
+
+    AppError::internal("error when inserting sheet template into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&template))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/new-sheet")
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateSheetFromTemplateBody {
+  pub template_id: Option<i32>,
+  pub sheet_name: String,
+
+  /// the date of the earliest row of the template in the new sheet
+  pub start_date: String
+}
+
+pub async fn create_sheet_from_template(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateSheetFromTemplateBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "sheet_name", &form.sheet_name);
+  let start_date = validation::date(&mut errors, "start_date", &form.start_date);
+
+  let template = match form.template_id {
+    Some(id) => {
+      let template = get_own_template(id, current.user.id)?;
+
+      if template.is_none() {
+        errors.add("template_id", String::from("no such template"));
+      }
+
+      template
+    },
+    None => {
+      errors.add("template_id", String::from("choose the template to start from"));
+
+      None
+    }
+  };
+
+  let template = match template {
+    Some(template) if errors.is_empty() => template,
+    _ => {
+      let page = pages::new_sheet::render_form(&req, current.user.id, &CreateSheetBody::default(), &form, &errors);
+
+      return Err(AppError::invalid_form(errors, page));
+    }
+  };
+
+  let mut rows = template.to_rows(start_date);
+
+  let mut sheet = Sheet::new(&name);
+  sheet.user_id = Some(current.user.id);
+  sheet.budget = template.budget;
+
+  sheet.insert_with_rows(&mut rows).map_err(|err| {
+    println!("error when creating sheet from template {}", err);
+
+    AppError::internal("error when inserting sheet into database")
+  })?;
+
+  record_rows(&current.user, &sheet, &rows);
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", sheet.id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteSheetTemplateByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_sheet_template_by_id(current: AuthenticatedUser, form: web::Form<DeleteSheetTemplateByIdBody>) -> Result<HttpResponse, AppError> {
+  let template = get_own_template(form.id, current.user.id)?
+    .ok_or_else(|| AppError::not_found("no such template"))?;
+
+  template.remove().map_err(|err| {
+    println!("error when removing sheet template {}", err);
+
+    AppError::internal("error when removing sheet template from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&template), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/new-sheet")
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+/// The templates are private, those of the other users are not found.
+fn get_own_template(id: i32, user_id: i32) -> Result<Option<SheetTemplate>, AppError> {
+  let template = SheetTemplate::get_by_id(id).map_err(|err| {
+    println!("error when fetching sheet template {}", err);
+
+    AppError::internal("error when searching sheet template from database")
+  })?;
+
+  Ok(template.filter(|template| template.user_id == user_id))
+}
\ No newline at end of file
@@ -0,0 +1,147 @@
+use crate::api::transfer::ensure_not_in_transfer;
+use crate::models::expense::Expense;
+use crate::models::split::{self, Share, Split};
+use crate::models::user::User;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+use std::collections::HashMap;
+
+/// Splits the expense `expense_id` paid by `paid_by` with the users whose
+/// `share-<user id>` field holds a value, read according to `method`. The
+/// split replaces the previous one of the expense.
+pub async fn save_split(req: HttpRequest, current: AuthenticatedUser, form: web::Form<HashMap<String, String>>) -> Result<HttpResponse, AppError> {
+  let expense_id = form
+    .get("expense_id")
+    .and_then(|id| id.parse::<i32>().ok())
+    .ok_or_else(|| AppError::not_found("no such expense"))?;
+
+  let expense = Expense::get_by_id(expense_id).map_err(|err| {
+    println!("error when fetching expense {}", err);
+
+    AppError::internal("error when searching expense from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such expense"))?;
+
+  ensure_not_in_transfer("expense", expense.id)?;
+
+  let users = User::get_all().map_err(|err| {
+    println!("error when fetching users {}", err);
+
+    AppError::internal("error when fetching users from database")
+  })?;
+
+  let field = |name: &str| form.get(name).map(|value| value.as_str()).unwrap_or("");
+
+  let mut errors = FormErrors::new();
+  let method = field("method");
+
+  let paid_by = match users.iter().find(|user| user.id.to_string() == field("paid_by")) {
+    Some(user) => user.id,
+    None => {
+      errors.add("paid_by", String::from("no such user"));
+
+      0
+    }
+  };
+
+  if !split::METHODS.contains(&method) {
+    errors.add("method", format!("the method must be one of {}", split::METHODS.join(", ")));
+  }
+
+  let mut shares = Vec::new();
+
+  for user in &users {
+    let value = field(&format!("share-{}", user.id));
+
+    if let Some(value) = validation::optional_amount(&mut errors, "shares", value) {
+      shares.push(Share {
+        user_id: user.id,
+        value: if method == "equal" { 1 } else { value }
+      });
+    }
+  }
+
+  let total: i32 = shares.iter().map(|share| share.value).sum();
+
+  if shares.is_empty() {
+    errors.add("shares", String::from("choose at least one user to split the expense with"));
+  }
+  else if method == "exact" && total != expense.amount {
+    errors.add("shares", format!("the exact amounts add up to {} instead of {}", total, expense.amount));
+  }
+
+  if !errors.is_empty() {
+    let page = pages::expense_split::render_form(&req, &expense, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = get_split(expense.id)?;
+
+  let split = Split {
+    expense_id: expense.id,
+    sheet_id: expense.sheet_id,
+    amount: expense.amount,
+    paid_by,
+    method: method.to_owned(),
+    shares
+  };
+
+  split.save().map_err(|err| {
+    println!("error when saving split {}", err);
+
+    AppError::internal("error when saving split into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), before.as_ref(), Some(&split))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", expense.sheet_id))
+      .content_type("text/plain")
+      .body("saved")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteSplitByIdBody {
+  pub expense_id: i32
+}
+
+pub async fn delete_split_by_id(current: AuthenticatedUser, form: web::Form<DeleteSplitByIdBody>) -> Result<HttpResponse, AppError> {
+  let split = get_split(form.expense_id)?
+    .ok_or_else(|| AppError::not_found("no such split"))?;
+
+  split.remove().map_err(|err| {
+    println!("error when removing split {}", err);
+
+    AppError::internal("error when removing split from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&split), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", split.sheet_id))
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+fn get_split(expense_id: i32) -> Result<Option<Split>, AppError> {
+  Split::get_by_expense_id(expense_id).map_err(|err| {
+    println!("error when fetching split {}", err);
+
+    AppError::internal("error when searching split from database")
+  })
+}
\ No newline at end of file
@@ -0,0 +1,223 @@
+use crate::models::transfer::Transfer;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::{self, FormErrors};
+use crate::errors::AppError;
+use crate::pages;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpRequest, HttpResponse, http};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateTransferBody {
+  pub name: String,
+  pub amount: String,
+  pub date: String,
+  pub from_sheet_id: String,
+  pub to_sheet_id: String
+}
+
+pub async fn create_transfer(req: HttpRequest, current: AuthenticatedUser, form: web::Form<CreateTransferBody>) -> Result<HttpResponse, AppError> {
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let (from_sheet_id, to_sheet_id) = validate_sheet_ids(&mut errors, &form.from_sheet_id, &form.to_sheet_id)?;
+
+  if !errors.is_empty() {
+    let page = pages::new_transfer::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let mut transfer = Transfer::new(&name, amount, date, from_sheet_id, to_sheet_id);
+  transfer.user_id = Some(current.user.id);
+
+  transfer.insert().map_err(|err| {
+    println!("error when creating transfer {}", err);
+
+    AppError::internal("error when inserting transfer into database")
+  })?;
+
+  audit_entry::record(Some(&current.user), None, Some(&transfer))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  add_to_sheet(transfer.from_sheet_id, -transfer.amount)?;
+  add_to_sheet(transfer.to_sheet_id, transfer.amount)?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", transfer.from_sheet_id))
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateTransferByIdBody {
+  pub name: String,
+  pub amount: String,
+  pub date: String,
+  pub from_sheet_id: String,
+  pub to_sheet_id: String,
+  pub id: i32
+}
+
+pub async fn update_transfer_by_id(req: HttpRequest, current: AuthenticatedUser, form: web::Form<UpdateTransferByIdBody>) -> Result<HttpResponse, AppError> {
+  let mut transfer = get_transfer(form.id)?;
+
+  let mut errors = FormErrors::new();
+  let name = validation::name(&mut errors, "name", &form.name);
+  let amount = validation::amount(&mut errors, "amount", &form.amount);
+  let date = validation::date(&mut errors, "date", &form.date);
+  let (from_sheet_id, to_sheet_id) = validate_sheet_ids(&mut errors, &form.from_sheet_id, &form.to_sheet_id)?;
+
+  if !errors.is_empty() {
+    let page = pages::edit_transfer::render_form(&req, &form, &errors);
+
+    return Err(AppError::invalid_form(errors, page));
+  }
+
+  let before = transfer.clone();
+  transfer.name = name;
+  transfer.amount = amount;
+  transfer.date = date;
+  transfer.from_sheet_id = from_sheet_id;
+  transfer.to_sheet_id = to_sheet_id;
+
+  transfer.update().map_err(|err| {
+    println!("error when updating transfer {}", err);
+
+    AppError::internal("error when updating transfer in database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&before), Some(&transfer))
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  // the sheets may have changed too, the old amount is taken back from the
+  // old ones before the new amount is given to the new ones
+  add_to_sheet(before.from_sheet_id, before.amount)?;
+  add_to_sheet(before.to_sheet_id, -before.amount)?;
+  add_to_sheet(transfer.from_sheet_id, -transfer.amount)?;
+  add_to_sheet(transfer.to_sheet_id, transfer.amount)?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", transfer.from_sheet_id))
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeleteTransferByIdBody {
+  pub id: i32
+}
+
+pub async fn delete_transfer_by_id(current: AuthenticatedUser, form: web::Form<DeleteTransferByIdBody>) -> Result<HttpResponse, AppError> {
+  let transfer = get_transfer(form.id)?;
+
+  transfer.remove().map_err(|err| {
+    println!("error when removing transfer {}", err);
+
+    AppError::internal("error when removing transfer from database")
+  })?;
+
+  audit_entry::record(Some(&current.user), Some(&transfer), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  add_to_sheet(transfer.from_sheet_id, transfer.amount)?;
+  add_to_sheet(transfer.to_sheet_id, -transfer.amount)?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, format!("/sheet/{}", transfer.from_sheet_id))
+      .content_type("text/plain")
+      .body("deleted")
+  )
+}
+
+/// Refuses to change the expense or income on its own when it belongs to a
+/// transfer, as the other sheet would no longer be balanced.
+pub fn ensure_not_in_transfer(kind: &str, entry_id: i32) -> Result<(), AppError> {
+  let transfer_id = Transfer::get_id_by_entry(kind, entry_id).map_err(|err| {
+    println!("error when fetching transfer {}", err);
+
+    AppError::internal("error when searching transfer from database")
+  })?;
+
+  match transfer_id {
+    Some(_) => Err(AppError::conflict(&format!("this {} is part of a transfer, change the transfer instead", kind))),
+    None => Ok(())
+  }
+}
+
+/// Adds the change to the cached value of the sheet and of the sheets
+/// importing it. The sheet is fetched right before as the previous change
+/// may have reached it through an import, nothing is done when it is in the
+/// trash.
+pub fn add_to_sheet(sheet_id: i32, change: i32) -> Result<(), AppError> {
+  let some_sheet = Sheet::get_by_id(sheet_id)
+  .map_err(|err| {
+    println!("error when fetching sheet {}", err);
+
+    AppError::internal("error when fetching sheet from database")
+  })?;
+
+  if let Some(mut sheet) = some_sheet {
+    sheet.add_to_cached_value(change)
+    .map_err(|err| {
+      println!("error when updating sheet cached value {}", err);
+
+      AppError::internal("error when updating sheet in database")
+    })?;
+  }
+
+  Ok(())
+}
+
+/// Returns the ids of the sheets the money leaves and goes to, both must
+/// exist and be different.
+pub fn validate_sheet_ids(errors: &mut FormErrors, from: &str, to: &str) -> Result<(i32, i32), AppError> {
+  let from_sheet_id = validate_sheet_id(errors, "from_sheet_id", from)?;
+  let to_sheet_id = validate_sheet_id(errors, "to_sheet_id", to)?;
+
+  if from_sheet_id != 0 && from_sheet_id == to_sheet_id {
+    errors.add("to_sheet_id", String::from("the money must go to another sheet"));
+  }
+
+  Ok((from_sheet_id, to_sheet_id))
+}
+
+fn validate_sheet_id(errors: &mut FormErrors, field: &'static str, value: &str) -> Result<i32, AppError> {
+  let sheet = match value.trim().parse::<i32>() {
+    Ok(id) => Sheet::get_by_id(id).map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when searching sheet from database")
+    })?,
+    Err(_) => None
+  };
+
+  match sheet {
+    Some(sheet) => Ok(sheet.id),
+    None => {
+      errors.add(field, String::from("no such sheet"));
+
+      Ok(0)
+    }
+  }
+}
+
+fn get_transfer(id: i32) -> Result<Transfer, AppError> {
+  Transfer::get_by_id(id).map_err(|err| {
+    println!("error when fetching transfer {}", err);
+
+    AppError::internal("error when searching transfer from database")
+  })?
+  .ok_or_else(|| AppError::not_found("no such transfer"))
+}
\ No newline at end of file
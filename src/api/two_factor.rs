@@ -0,0 +1,176 @@
+use crate::constants;
+use crate::models::two_factor::{self, TwoFactor, replace_recovery_codes};
+use crate::models::user::User;
+use crate::pages;
+use crate::utils::crypto::{generate_recovery_codes, verify_passwords};
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::totp;
+use crate::errors::AppError;
+
+use serde::{Deserialize, Serialize};
+use actix_web::{web, HttpResponse, http};
+
+/// Generates a new secret for the user and sends them to the page showing
+/// its QR code. Two-factor authentication is not enabled until a first code
+/// is confirmed.
+pub async fn setup(current: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let user = current.user;
+
+  let existing = TwoFactor::get_enabled_by_user_id(user.id)
+    .map_err(|err| {
+      println!("error when fetching two-factor {}", err);
+
+      AppError::internal("could not fetch two-factor from db")
+    })?;
+
+  if existing.is_some() {
+    return Err(AppError::conflict("two-factor authentication is already enabled"));
+  }
+
+  let secret = totp::generate_secret()
+    .map_err(|err| {
+      println!("error when generating two-factor secret {}", err);
+
+      AppError::internal("could not generate a secret")
+    })?;
+
+  TwoFactor::new(user.id, secret).insert()
+    .map_err(|err| {
+      println!("error when inserting two-factor {}", err);
+
+      AppError::internal("could not save the secret")
+    })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/settings/two-factor")
+      .content_type("text/plain")
+      .body("created")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnableTwoFactorBody {
+  pub code: String
+}
+
+/// Enables two-factor authentication once the user proved their app works,
+/// then shows their recovery codes.
+pub async fn enable(current: AuthenticatedUser, form: web::Form<EnableTwoFactorBody>) -> Result<HttpResponse, AppError> {
+  let user = current.user;
+
+  let some_two_factor = TwoFactor::get_by_user_id(user.id)
+    .map_err(|err| {
+      println!("error when fetching two-factor {}", err);
+
+      AppError::internal("could not fetch two-factor from db")
+    })?;
+
+  let mut two_factor = match some_two_factor {
+    Some(two_factor) if !two_factor.enabled => two_factor,
+    _ => {
+      return Ok(
+        HttpResponse::Found()
+          .header(http::header::LOCATION, "/settings")
+          .content_type("text/plain")
+          .body("nothing to enable")
+      );
+    }
+  };
+
+  let valid = two_factor.verify_code(&form.code)
+    .map_err(|err| {
+      println!("error when verifying two-factor code {}", err);
+
+      AppError::internal("could not verify the code")
+    })?;
+
+  if !valid {
+    return Err(AppError::bad_request("wrong code, check the clock of your device"));
+  }
+
+  two_factor.enable()
+    .map_err(|err| {
+      println!("error when enabling two-factor {}", err);
+
+      AppError::internal("could not enable two-factor authentication")
+    })?;
+
+  new_recovery_codes(user.id)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorPasswordBody {
+  pub current_password: String
+}
+
+fn verify_current_password(user: &User, form: &TwoFactorPasswordBody) -> Result<(), AppError> {
+  if !verify_passwords(&user.handle, &form.current_password, &user.password) {
+    return Err(AppError::unauthorized("the current password is wrong"));
+  }
+
+  Ok(())
+}
+
+pub async fn disable(current: AuthenticatedUser, form: web::Form<TwoFactorPasswordBody>) -> Result<HttpResponse, AppError> {
+  let user = current.user;
+
+  verify_current_password(&user, &form)?;
+
+  two_factor::remove_all_by_user_id(user.id)
+    .map_err(|err| {
+      println!("error when removing two-factor {}", err);
+
+      AppError::internal("could not disable two-factor authentication")
+    })?;
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/settings")
+      .content_type("text/plain")
+      .body("disabled")
+  )
+}
+
+/// Replaces the recovery codes, the previous ones stop working.
+pub async fn regenerate_recovery_codes(current: AuthenticatedUser, form: web::Form<TwoFactorPasswordBody>) -> Result<HttpResponse, AppError> {
+  let user = current.user;
+
+  verify_current_password(&user, &form)?;
+
+  let some_two_factor = TwoFactor::get_enabled_by_user_id(user.id)
+    .map_err(|err| {
+      println!("error when fetching two-factor {}", err);
+
+      AppError::internal("could not fetch two-factor from db")
+    })?;
+
+  if some_two_factor.is_none() {
+    return Ok(
+      HttpResponse::Found()
+        .header(http::header::LOCATION, "/settings")
+        .content_type("text/plain")
+        .body("two-factor authentication is not enabled")
+    );
+  }
+
+  new_recovery_codes(user.id)
+}
+
+fn new_recovery_codes(user_id: i32) -> Result<HttpResponse, AppError> {
+  let codes = generate_recovery_codes(constants::RECOVERY_CODES_COUNT)
+    .map_err(|err| {
+      println!("error when generating recovery codes {}", err);
+
+      AppError::internal("could not generate recovery codes")
+    })?;
+
+  replace_recovery_codes(user_id, &codes)
+    .map_err(|err| {
+      println!("error when saving recovery codes {}", err);
+
+      AppError::internal("could not save recovery codes")
+    })?;
+
+  Ok(pages::settings_two_factor::render_recovery_codes(&codes))
+}
@@ -1,43 +1,213 @@
-use actix_web::{http, web, HttpResponse, Result, HttpRequest};
+use actix_web::{http, web, HttpResponse};
 use serde::{Deserialize, Serialize};
-use crate::models::user::{UserRole, delete_user_by_id};
-use crate::utils::req_auth::request_authentication;
+use crate::models::user::{User, UserRole, delete_user_by_id, is_in_shared_expenses, set_user_role};
+use crate::models::sheet::{Sheet, transfer_ownership};
+use crate::models::two_factor;
+use crate::models::audit_entry;
+use crate::utils::req_auth::AdminUser;
+use crate::errors::AppError;
 
 #[derive(Serialize, Deserialize)]
 pub struct DeleteUserBody {
-  pub id: i32
+  pub id: i32,
+
+  /// what to do with the sheets of the user, either `transfer` or `delete`
+  pub sheets_action: String,
+
+  /// the user who receives the sheets when they are transferred
+  pub transfer_to: Option<i32>
 }
 
-pub async fn delete_user(req: HttpRequest, form: web::Form<DeleteUserBody>) -> Result<HttpResponse> {
-  let auth_result = request_authentication(&req, UserRole::Admin)
-    .map_err(|err| {
-      println!("error");
-      HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(err)
-    })?;
-
-  if !auth_result.has_access() {
-    return Ok(
-      HttpResponse::NotFound()
-        .content_type("text/plain")
-        .body("HTTP 404: Not found")
-    ); 
+pub async fn delete_user(admin: AdminUser, form: web::Form<DeleteUserBody>) -> Result<HttpResponse, AppError> {
+  if admin.user.id == form.id {
+    return Err(AppError::forbidden("you cannot delete your own account"));
+  }
+
+  let in_shared_expenses = is_in_shared_expenses(form.id)
+  .map_err(|err| {
+    println!("error when checking the splits of the user {}", err);
+
+    AppError::internal("error when checking the splits of the user")
+  })?;
+
+  if in_shared_expenses {
+    return Err(AppError::conflict("the user is part of splits or settlements, remove them before deleting the user"));
   }
 
+  match (form.sheets_action.as_str(), form.transfer_to) {
+    ("transfer", Some(new_owner_id)) => {
+      if new_owner_id == form.id {
+        return Err(AppError::bad_request("the sheets must be transferred to another user"));
+      }
+
+      let new_owner = User::get_by_id(new_owner_id)
+      .map_err(|err| {
+        println!("error when fetching user by id {}", err);
+
+        AppError::internal("error when fetching the new owner of the sheets")
+      })?;
+
+      if new_owner.is_none() {
+        return Err(AppError::bad_request("no such user to transfer the sheets to"));
+      }
+
+      let sheets = Sheet::get_all_by_user_id(form.id)
+      .map_err(|err| {
+        println!("error when fetching sheets by user id {}", err);
+
+        AppError::internal("error when fetching the sheets of the user")
+      })?;
+
+      transfer_ownership(form.id, new_owner_id)
+      .map_err(|err| {
+        println!("error when transferring sheets {}", err);
+
+        AppError::internal("error when transferring the sheets of the user")
+      })?;
+
+      for sheet in sheets {
+        let mut transferred = sheet.clone();
+        transferred.user_id = Some(new_owner_id);
+
+        audit_entry::record(Some(&admin.user), Some(&sheet), Some(&transferred))
+        .map_err(|err| println!("error when recording audit entry {}", err))
+        .ok();
+      }
+    },
+    ("delete", _) => {
+      let sheets = Sheet::get_all_by_user_id(form.id)
+      .map_err(|err| {
+        println!("error when fetching sheets by user id {}", err);
+
+        AppError::internal("error when fetching the sheets of the user")
+      })?;
+
+      for sheet in sheets {
+        sheet.remove()
+        .map_err(|err| {
+          println!("error when removing sheet {}", err);
+
+          AppError::internal("error when removing the sheets of the user")
+        })?;
+
+        audit_entry::record(Some(&admin.user), Some(&sheet), None)
+        .map_err(|err| println!("error when recording audit entry {}", err))
+        .ok();
+      }
+    },
+    _ => {
+      return Err(AppError::bad_request("the sheets of the user must either be transferred or deleted"));
+    }
+  };
+
+  let user = User::get_by_id(form.id)
+  .map_err(|err| {
+    println!("error when fetching user by id {}", err);
+
+    AppError::internal("error when fetching the user")
+  })?;
+
   delete_user_by_id(form.id)
   .map_err(|err| {
     println!("error when deleting user by id {}", err);
 
-    HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body("Internal server error: an error occured when deleting the user")
+    AppError::internal("an error occured when deleting the user")
   })?;
 
+  audit_entry::record(Some(&admin.user), user.as_ref(), None)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
   Ok(
     HttpResponse::Found()
-      .header(http::header::LOCATION, "/private/users")
+      .header(http::header::LOCATION, "/admin/users")
       .content_type("text/plain")
       .body("deleted")
   )
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SetUserRoleBody {
+  pub id: i32,
+  pub role: i32
+}
+
+pub async fn set_role(admin: AdminUser, form: web::Form<SetUserRoleBody>) -> Result<HttpResponse, AppError> {
+  if admin.user.id == form.id {
+    return Err(AppError::forbidden("you cannot change your own role"));
+  }
+
+  let role = UserRole::from_number(form.role);
+
+  if let UserRole::None = role {
+    return Err(AppError::bad_request("unknown role"));
+  }
+
+  let before = User::get_by_id(form.id)
+  .map_err(|err| {
+    println!("error when fetching user by id {}", err);
+
+    AppError::internal("error when fetching the user")
+  })?
+  .ok_or_else(|| AppError::not_found("no such user"))?;
+
+  set_user_role(form.id, role)
+  .map_err(|err| {
+    println!("error when updating user role {}", err);
+
+    AppError::internal("error when updating the role of the user")
+  })?;
+
+  let after = User::get_by_id(form.id)
+  .map_err(|err| {
+    println!("error when fetching user by id {}", err);
+
+    AppError::internal("error when fetching the user")
+  })?;
+
+  audit_entry::record(Some(&admin.user), Some(&before), after.as_ref())
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/users")
+      .content_type("text/plain")
+      .body("updated")
+  )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResetTwoFactorBody {
+  pub id: i32
+}
+
+/// Disables the two-factor authentication of a user who lost both their
+/// device and their recovery codes.
+pub async fn reset_two_factor(admin: AdminUser, form: web::Form<ResetTwoFactorBody>) -> Result<HttpResponse, AppError> {
+  let user = User::get_by_id(form.id)
+  .map_err(|err| {
+    println!("error when fetching user by id {}", err);
+
+    AppError::internal("error when fetching the user")
+  })?
+  .ok_or_else(|| AppError::not_found("no such user"))?;
+
+  two_factor::remove_all_by_user_id(user.id)
+  .map_err(|err| {
+    println!("error when removing two-factor {}", err);
+
+    AppError::internal("error when resetting the two-factor authentication of the user")
+  })?;
+
+  audit_entry::record_action(Some(&admin.user), "two_factor_reset", &user)
+  .map_err(|err| println!("error when recording audit entry {}", err))
+  .ok();
+
+  Ok(
+    HttpResponse::Found()
+      .header(http::header::LOCATION, "/admin/users")
+      .content_type("text/plain")
+      .body("reset")
+  )
+}
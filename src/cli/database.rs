@@ -0,0 +1,91 @@
+use crate::models::open_connection;
+use crate::models::integrity;
+use crate::models::sheet::Sheet;
+use crate::models::user::get_duplicated_handles;
+use rusqlite::params;
+
+/// Copies the whole database into a new file at `path`. The copy is made by
+/// sqlite itself, so it is consistent even if the server is running.
+pub fn backup(path: &str) -> Result<(), String> {
+  if std::path::Path::new(path).exists() {
+    return Err(format!("{} already exists, refusing to overwrite it", path));
+  }
+
+  let conn = open_connection()
+    .map_err(|err| format!("error when opening the database {}", err))?;
+
+  conn.execute("vacuum into ?1", params![path])
+    .map_err(|err| format!("error when backing up the database {}", err))?;
+
+  println!("database saved into {}", path);
+
+  Ok(())
+}
+
+/// Lists the orphans of the database, the rows pointing at rows that do not
+/// exist. With `repair` they are deleted or unlinked like the foreign keys
+/// would have done, then the cached values of the sheets are recomputed.
+/// Handles shared by several users are listed too, they are never repaired
+/// since only an administrator knows which user to keep.
+pub fn check_integrity(repair: bool) -> Result<(), String> {
+  let handles = get_duplicated_handles()
+    .map_err(|err| format!("error when checking the handles {}", err))?;
+
+  for handle in &handles {
+    println!("users: several users share the handle {}", handle);
+  }
+
+  let orphans = integrity::find_orphans()
+    .map_err(|err| format!("error when checking the foreign keys {}", err))?;
+
+  if orphans.is_empty() {
+    println!("no orphan found");
+
+    return duplicated_handles_result(&handles);
+  }
+
+  for orphan in &orphans {
+    let parent_id = orphan.parent_id.map(|id| id.to_string()).unwrap_or_default();
+
+    println!(
+      "{} row {}: {} {} does not exist in {}",
+      orphan.table, orphan.rowid, orphan.column, parent_id, orphan.parent_table
+    );
+  }
+
+  if !repair {
+    return Err(format!(
+      "found {} orphans, run `calco check-integrity --repair` to delete or unlink them",
+      orphans.len()
+    ));
+  }
+
+  let mut count = 0;
+
+  for orphan in &orphans {
+    let changed = orphan.repair()
+      .map_err(|err| format!("error when repairing {} row {} {}", orphan.table, orphan.rowid, err))?;
+
+    if changed {
+      count += 1;
+    }
+  }
+
+  Sheet::recompute_all_cached_values()
+    .map_err(|err| format!("error when recomputing the cached values {}", err))?;
+
+  println!("repaired {} rows", count);
+
+  duplicated_handles_result(&handles)
+}
+
+fn duplicated_handles_result(handles: &[String]) -> Result<(), String> {
+  if handles.is_empty() {
+    return Ok(());
+  }
+
+  Err(format!(
+    "found {} duplicated handles, delete the extra users with `calco delete-user --handle <handle>`",
+    handles.len()
+  ))
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+pub mod users;
+pub mod sheets;
+pub mod database;
+
+pub const USAGE: &str = "usage: calco [--config <path>] [--db <path>] <command> [options]
+
+commands:
+  serve [--port <port>] [--bind <address>] [--admin-handle <handle>]
+                                              start the web server (default command)
+  create-invitation --handle <handle> --role <guest|admin>
+  list-users
+  delete-user --handle <handle>
+  recompute-cache                             recompute the cached value of every sheet
+  backup <path>                               copy the database into <path>
+  check-integrity [--repair]                  list the rows pointing at missing rows, and
+                                              delete or unlink them with --repair
+  import-csv --sheet <id|name> <file>         import expenses & incomes from a csv file
+  export --sheet <id|name> [--output <file>]  export expenses & incomes as csv";
+
+pub enum Command {
+  Serve {
+    port: Option<u16>,
+    bind: Option<String>,
+
+    /// the handle the first admin invitation is made for
+    admin_handle: Option<String>
+  },
+  CreateInvitation {
+    handle: String,
+    role: String
+  },
+  ListUsers,
+  DeleteUser {
+    handle: String
+  },
+  RecomputeCache,
+  Backup {
+    path: String
+  },
+  CheckIntegrity {
+    repair: bool
+  },
+  ImportCsv {
+    sheet: String,
+    path: String
+  },
+  Export {
+    sheet: String,
+    output: Option<String>
+  },
+  Help
+}
+
+pub struct Arguments {
+  pub config: Option<String>,
+  pub database: Option<String>,
+  pub command: Command
+}
+
+/// Parses the arguments passed to the binary, without the binary name.
+///
+/// For compatibility with older versions a lone port number, `calco 5000`,
+/// is understood as `calco serve --port 5000`.
+pub fn parse_arguments(args: Vec<String>) -> Result<Arguments, String> {
+  let mut command_name: Option<String> = None;
+  let mut options: HashMap<String, String> = HashMap::new();
+  let mut positionals: Vec<String> = Vec::new();
+
+  let mut args = args.into_iter().peekable();
+
+  while let Some(arg) = args.next() {
+    if arg.starts_with("--") {
+      let name = arg.trim_start_matches("--").to_owned();
+      let value = match args.peek() {
+        Some(next) if !next.starts_with("--") => args.next().unwrap(),
+        _ => String::from("true")
+      };
+
+      options.insert(name, value);
+    }
+    else if command_name.is_none() {
+      command_name = Some(arg);
+    }
+    else {
+      positionals.push(arg);
+    }
+  }
+
+  let config = options.remove("config");
+  let database = options.remove("db");
+
+  if options.remove("help").is_some() {
+    return Ok(Arguments { config, database, command: Command::Help });
+  }
+
+  let command = match command_name.as_deref() {
+    None | Some("serve") => Command::Serve {
+      port: parse_port(options.remove("port"))?,
+      bind: options.remove("bind"),
+      admin_handle: options.remove("admin-handle")
+    },
+    Some(port) if port.parse::<u16>().is_ok() => Command::Serve {
+      port: port.parse::<u16>().ok(),
+      bind: None,
+      admin_handle: None
+    },
+    Some("create-invitation") => Command::CreateInvitation {
+      handle: required_option(&mut options, "handle")?,
+      role: options.remove("role").unwrap_or_else(|| String::from("guest"))
+    },
+    Some("list-users") => Command::ListUsers,
+    Some("delete-user") => Command::DeleteUser {
+      handle: required_option(&mut options, "handle")?
+    },
+    Some("recompute-cache") => Command::RecomputeCache,
+    Some("backup") => Command::Backup {
+      path: required_positional(&mut positionals, "backup path")?
+    },
+    Some("check-integrity") => Command::CheckIntegrity {
+      repair: options.remove("repair").is_some()
+    },
+    Some("import-csv") => Command::ImportCsv {
+      sheet: required_option(&mut options, "sheet")?,
+      path: required_positional(&mut positionals, "csv file")?
+    },
+    Some("export") => Command::Export {
+      sheet: required_option(&mut options, "sheet")?,
+      output: options.remove("output")
+    },
+    Some("help") => Command::Help,
+    Some(unknown) => return Err(format!("unknown command {}", unknown))
+  };
+
+  if let Some(name) = options.keys().next() {
+    return Err(format!("unknown option --{}", name));
+  }
+
+  if let Some(arg) = positionals.first() {
+    return Err(format!("unexpected argument {}", arg));
+  }
+
+  Ok(Arguments { config, database, command })
+}
+
+/// Runs every command but `serve`, which needs the async runtime and is
+/// handled by `main`.
+pub fn run(command: Command) -> Result<(), String> {
+  match command {
+    Command::CreateInvitation { handle, role } => users::create_invitation(&handle, &role),
+    Command::ListUsers => users::list_users(),
+    Command::DeleteUser { handle } => users::delete_user(&handle),
+    Command::RecomputeCache => sheets::recompute_cache(),
+    Command::Backup { path } => database::backup(&path),
+    Command::CheckIntegrity { repair } => database::check_integrity(repair),
+    Command::ImportCsv { sheet, path } => sheets::import_csv(&sheet, &path),
+    Command::Export { sheet, output } => sheets::export(&sheet, output.as_deref()),
+    Command::Help | Command::Serve { .. } => {
+      println!("{}", USAGE);
+
+      Ok(())
+    }
+  }
+}
+
+fn parse_port(port: Option<String>) -> Result<Option<u16>, String> {
+  match port {
+    Some(port) => port
+      .parse::<u16>()
+      .map(Some)
+      .map_err(|_err| format!("invalid port {}", port)),
+    None => Ok(None)
+  }
+}
+
+fn required_option(options: &mut HashMap<String, String>, name: &str) -> Result<String, String> {
+  options
+    .remove(name)
+    .ok_or_else(|| format!("missing option --{}", name))
+}
+
+fn required_positional(positionals: &mut Vec<String>, name: &str) -> Result<String, String> {
+  if positionals.is_empty() {
+    return Err(format!("missing argument: {}", name));
+  }
+
+  Ok(positionals.remove(0))
+}
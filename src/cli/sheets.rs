@@ -0,0 +1,230 @@
+use crate::models::open_connection;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry;
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::category::Category;
+use crate::utils::date::format_date;
+
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::io::Write;
+
+const CSV_HEADERS: [&str; 4] = ["type", "name", "amount", "date"];
+
+/// Written after `CSV_HEADERS` by `export`, the planned and actual amounts
+/// are those of the category of the expense. Only the category is read back
+/// by `import_csv`, the other two are derived.
+const CSV_BUDGET_HEADERS: [&str; 3] = ["category", "planned", "actual"];
+
+pub fn recompute_cache() -> Result<(), String> {
+  let count = Sheet::recompute_all_cached_values()
+    .map_err(|err| format!("error when recomputing cached values {}", err))?;
+
+  println!("recomputed the cached value of {} sheets", count);
+
+  Ok(())
+}
+
+/// Imports the rows of a csv file into a sheet. The file must start with the
+/// `type,name,amount,date` headers, where `type` is either `expense` or
+/// `income` and `date` uses the `YYYY-MM-DD` format. They can be followed by
+/// the headers written by `export`, the expenses are then put in the category
+/// named in the `category` column, which is created when the sheet has none
+/// by that name.
+pub fn import_csv(sheet_key: &str, path: &str) -> Result<(), String> {
+  let mut sheet = find_sheet(sheet_key)?;
+
+  let mut reader = csv::Reader::from_path(path)
+    .map_err(|err| format!("error when opening {} {}", path, err))?;
+
+  let headers = reader.headers()
+    .map_err(|err| format!("error when reading csv headers {}", err))?;
+
+  let headers = headers.iter().map(|header| header.trim()).collect::<Vec<&str>>();
+  let known_headers = CSV_HEADERS.iter().chain(CSV_BUDGET_HEADERS.iter()).cloned().collect::<Vec<&str>>();
+
+  if headers.len() < CSV_HEADERS.len() || !known_headers.starts_with(&headers) {
+    return Err(format!(
+      "expected the csv headers to be {} optionally followed by {}",
+      CSV_HEADERS.join(","),
+      CSV_BUDGET_HEADERS.join(",")
+    ));
+  }
+
+  let has_category = headers.len() > CSV_HEADERS.len();
+  let mut categories: HashMap<String, Category> = Category::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| format!("error when fetching categories {}", err))?
+    .into_iter()
+    .map(|category| (category.name.clone(), category))
+    .collect();
+
+  // every record is read before anything is written, a bad line leaves the
+  // sheet untouched
+  let mut expenses: Vec<(Expense, String)> = Vec::new();
+  let mut incomes: Vec<Income> = Vec::new();
+
+  for (index, record) in reader.records().enumerate() {
+    // the headers are on the first line
+    let line = index + 2;
+    let record = record
+      .map_err(|err| format!("line {}: error when reading record {}", line, err))?;
+
+    let name = record[1].trim();
+    let amount = record[2].trim().parse::<i32>()
+      .map_err(|_err| format!("line {}: invalid amount {}", line, &record[2]))?;
+    let date = NaiveDate::parse_from_str(record[3].trim(), "%Y-%m-%d")
+      .map_err(|_err| format!("line {}: invalid date {}", line, &record[3]))?
+      .and_hms(0, 0, 0)
+      .timestamp();
+
+    match record[0].trim() {
+      "expense" => {
+        let category_name = if has_category { record[4].trim() } else { "" };
+
+        expenses.push((Expense::new(name, amount, sheet.id, date), category_name.to_owned()));
+      },
+      "income" => incomes.push(Income::new(name, amount, sheet.id, date)),
+      kind => return Err(format!("line {}: unknown type {}, expected expense or income", line, kind))
+    };
+  }
+
+  let mut conn = open_connection()
+    .map_err(|err| format!("error when opening the database {}", err))?;
+  let transaction = conn.transaction()
+    .map_err(|err| format!("error when starting a transaction {}", err))?;
+
+  let mut new_categories = Vec::new();
+
+  for (expense, category_name) in expenses.iter_mut() {
+    if category_name.is_empty() {
+      continue;
+    }
+
+    if !categories.contains_key(category_name.as_str()) {
+      let mut category = Category::new(category_name, sheet.id);
+
+      category.insert_in(&transaction)
+        .map_err(|err| format!("error when inserting category {}", err))?;
+
+      new_categories.push(category.clone());
+      categories.insert(category.name.clone(), category);
+    }
+
+    expense.category_id = categories.get(category_name.as_str()).map(|category| category.id);
+  }
+
+  for (expense, _) in expenses.iter_mut() {
+    expense.insert_in(&transaction)
+      .map_err(|err| format!("error when inserting expense {}", err))?;
+  }
+
+  for income in incomes.iter_mut() {
+    income.insert_in(&transaction)
+      .map_err(|err| format!("error when inserting income {}", err))?;
+  }
+
+  transaction.commit()
+    .map_err(|err| format!("error when writing the rows {}", err))?;
+
+  // the audit log is written once the rows are, from its own connection
+  for category in &new_categories {
+    audit_entry::record(None, None, Some(category))
+      .map_err(|err| format!("error when recording audit entry {}", err))?;
+  }
+
+  for (expense, _) in &expenses {
+    audit_entry::record(None, None, Some(expense))
+      .map_err(|err| format!("error when recording audit entry {}", err))?;
+  }
+
+  for income in &incomes {
+    audit_entry::record(None, None, Some(income))
+      .map_err(|err| format!("error when recording audit entry {}", err))?;
+  }
+
+  let change = incomes.iter().map(|income| income.amount).sum::<i32>()
+    - expenses.iter().map(|(expense, _)| expense.amount).sum::<i32>();
+
+  sheet.add_to_cached_value(change)
+    .map_err(|err| format!("error when updating sheet cached value {}", err))?;
+
+  println!("imported {} rows into sheet {}", expenses.len() + incomes.len(), sheet.name);
+
+  Ok(())
+}
+
+/// Writes the expenses and incomes of a sheet as csv, in the format expected
+/// by `import_csv`. The rows are written to stdout when no `output` is given.
+pub fn export(sheet_key: &str, output: Option<&str>) -> Result<(), String> {
+  let sheet = find_sheet(sheet_key)?;
+
+  let categories: HashMap<i32, (Category, i32)> = Category::get_all_with_spent_by_sheet_id(sheet.id)
+    .map_err(|err| format!("error when fetching categories {}", err))?
+    .into_iter()
+    .map(|(category, spent)| (category.id, (category, spent)))
+    .collect();
+
+  let expenses = Expense::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| format!("error when fetching expenses {}", err))?;
+
+  let incomes = Income::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| format!("error when fetching incomes {}", err))?;
+
+  let destination: Box<dyn Write> = match output {
+    Some(path) => Box::new(
+      std::fs::File::create(path)
+        .map_err(|err| format!("error when creating {} {}", path, err))?
+    ),
+    None => Box::new(std::io::stdout())
+  };
+
+  let mut writer = csv::Writer::from_writer(destination);
+  let mut write_row = |row: &[String]| {
+    writer.write_record(row)
+      .map_err(|err| format!("error when writing csv row {}", err))
+  };
+
+  write_row(&CSV_HEADERS.iter().chain(CSV_BUDGET_HEADERS.iter()).map(|header| header.to_string()).collect::<Vec<String>>())?;
+
+  for expense in expenses {
+    let category = expense.category_id.and_then(|id| categories.get(&id));
+
+    write_row(&[
+      String::from("expense"),
+      expense.name,
+      expense.amount.to_string(),
+      format_date(expense.date),
+      category.map(|(category, _)| category.name.clone()).unwrap_or_default(),
+      category.and_then(|(category, _)| category.target).map(|target| target.to_string()).unwrap_or_default(),
+      category.map(|(_, spent)| spent.to_string()).unwrap_or_default()
+    ])?;
+  }
+
+  for income in incomes {
+    write_row(&[
+      String::from("income"),
+      income.name,
+      income.amount.to_string(),
+      format_date(income.date),
+      String::new(),
+      String::new(),
+      String::new()
+    ])?;
+  }
+
+  writer.flush()
+    .map_err(|err| format!("error when writing csv {}", err))
+}
+
+/// Finds a sheet by id, or by name when `key` is not a number.
+fn find_sheet(key: &str) -> Result<Sheet, String> {
+  let sheet = match key.parse::<i32>() {
+    Ok(id) => Sheet::get_by_id(id),
+    Err(_) => Sheet::get_by_name(key)
+  };
+
+  sheet
+    .map_err(|err| format!("error when fetching sheet {}", err))?
+    .ok_or_else(|| format!("no sheet with the id or name {}", key))
+}
@@ -0,0 +1,86 @@
+use crate::models::audit_entry;
+use crate::models::invitation::{Invitation, update_invitation_expiration, update_invitation_role};
+use crate::models::user::{User, UserRole, delete_user_by_id, is_in_shared_expenses};
+
+pub fn create_invitation(handle: &str, role: &str) -> Result<(), String> {
+  let role = UserRole::from_name(role)
+    .ok_or_else(|| format!("unknown role {}, expected guest or admin", role))?;
+
+  if User::get_by_handle(handle)
+    .map_err(|err| format!("error when fetching user by handle {}", err))?
+    .is_some() {
+    return Err(format!("a user with the handle {} already exists", handle));
+  }
+
+  let existing_invitation = Invitation::get_by_handle(handle)
+    .map_err(|err| format!("error when checking if invitation already exists {}", err))?;
+
+  let invitation = match existing_invitation {
+    Some(invitation) => {
+      update_invitation_expiration(handle)
+        .map_err(|err| format!("error when updating already existing invitation {}", err))?;
+
+      update_invitation_role(handle, role)
+        .map_err(|err| format!("error when updating already existing invitation {}", err))?;
+
+      if let Some(extended) = Invitation::get_by_id(invitation.id)
+        .map_err(|err| format!("error when fetching invitation {}", err))? {
+        audit_entry::record(None, Some(&invitation), Some(&extended))
+          .map_err(|err| format!("error when recording audit entry {}", err))?;
+      }
+
+      println!("an invitation already existed for {}, its role and expiration date were updated", handle);
+
+      invitation
+    },
+    None => {
+      let mut invitation = Invitation::new(handle, role);
+
+      Invitation::insert(&mut invitation)
+        .map_err(|err| format!("error when inserting new invitation {}", err))?;
+
+      audit_entry::record(None, None, Some(&invitation))
+        .map_err(|err| format!("error when recording audit entry {}", err))?;
+
+      invitation
+    }
+  };
+
+  println!("{}", invitation.to_url());
+
+  Ok(())
+}
+
+pub fn list_users() -> Result<(), String> {
+  let users = User::get_all()
+    .map_err(|err| format!("error when fetching users {}", err))?;
+
+  println!("id\thandle\trole");
+
+  for user in users {
+    println!("{}\t{}\t{}", user.id, user.handle, user.role.to_name());
+  }
+
+  Ok(())
+}
+
+pub fn delete_user(handle: &str) -> Result<(), String> {
+  let user = User::get_by_handle(handle)
+    .map_err(|err| format!("error when fetching user by handle {}", err))?
+    .ok_or_else(|| format!("no user with the handle {}", handle))?;
+
+  if is_in_shared_expenses(user.id)
+    .map_err(|err| format!("error when checking the splits of the user {}", err))? {
+    return Err(format!("{} is part of splits or settlements, remove them before deleting the user", handle));
+  }
+
+  delete_user_by_id(user.id)
+    .map_err(|err| format!("error when deleting user by id {}", err))?;
+
+  audit_entry::record(None, Some(&user), None)
+    .map_err(|err| format!("error when recording audit entry {}", err))?;
+
+  println!("deleted user {}", handle);
+
+  Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::components::field_error;
+use crate::models::account::Account;
+use crate::utils::validation::FormErrors;
+use maud::{html, Markup};
+
+/// The field choosing the account of an expense or an income, nothing is
+/// shown when there is no account.
+pub fn account_select(selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    @match Account::get_all() {
+      Ok(accounts) => {
+        @if !accounts.is_empty() {
+          div {
+            label for="account_id" { "Account" }
+            select id="account_id" name="account_id" {
+              option value="" { "none" }
+
+              @for account in &accounts {
+                @if account.id.to_string() == selected {
+                  option value=(account.id) selected { (account.name) }
+                } @else {
+                  option value=(account.id) { (account.name) }
+                }
+              }
+            }
+            (field_error(errors, "account_id"))
+          }
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the accounts: " (e)
+      }
+    }
+  }
+}
\ No newline at end of file
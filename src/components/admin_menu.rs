@@ -0,0 +1,24 @@
+use maud::{html, Markup};
+
+const ADMIN_PAGES: [(&str, &str); 5] = [
+  ("/admin/users", "users"),
+  ("/admin/invitations", "invitations"),
+  ("/admin/password-resets", "password resets"),
+  ("/admin/failed-attempts", "failed attempts"),
+  ("/admin/history", "history")
+];
+
+/// The navigation between the pages of the admin area.
+pub fn admin_menu(active: &str) -> Markup {
+  html! {
+    div.admin-menu {
+      @for (href, name) in &ADMIN_PAGES {
+        @if *name == active {
+          a class="active" href=(href) { (name) }
+        } @else {
+          a href=(href) { (name) }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,58 @@
+use crate::models::audit_entry::{AuditEntry, AuditFilter};
+use maud::{html, Markup};
+
+const ENTITIES: [&str; 15] = ["sheet", "category", "goal", "account", "statement", "expense", "income", "transfer", "split", "settlement", "recurring_entry", "inherited_sheet", "sheet_template", "user", "invitation"];
+const ACTIONS: [&str; 7] = ["create", "update", "delete", "restore", "password_changed", "password_reset", "two_factor_reset"];
+
+/// The form filtering the entries of a history page, submitted to `action`
+/// as query parameters.
+pub fn audit_filter_form(action: &str, filter: &AuditFilter) -> Markup {
+  let entity = filter.entity.as_deref().unwrap_or("");
+  let kind = filter.action.as_deref().unwrap_or("");
+
+  html! {
+    form class="history-filters" method="get" action=(action) {
+      select name="entity" {
+        option value="" { "everything" }
+        @for name in &ENTITIES {
+          @if *name == entity {
+            option value=(name) selected { (name) }
+          } @else {
+            option value=(name) { (name) }
+          }
+        }
+      }
+
+      select name="action" {
+        option value="" { "any change" }
+        @for name in &ACTIONS {
+          @if *name == kind {
+            option value=(name) selected { (name) }
+          } @else {
+            option value=(name) { (name) }
+          }
+        }
+      }
+
+      input type="text" name="actor" placeholder="handle" value=(filter.actor.as_deref().unwrap_or(""));
+      input type="submit" value="filter";
+    }
+  }
+}
+
+/// The entries of the audit log, latest first.
+pub fn audit_log(entries: &[AuditEntry]) -> Markup {
+  html! {
+    div class="history-list" {
+      @if entries.is_empty() {
+        p { "no change recorded" }
+      }
+
+      @for entry in entries {
+        div.history-entry.element {
+          (entry)
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,31 @@
+use crate::config;
+use maud::{html, Markup};
+
+/// What was spent against the planned amount, if there is one. The spending
+/// above it is highlighted.
+pub fn budget_progress(label: &str, spent: i32, planned: Option<i32>) -> Markup {
+  let over = planned.filter(|planned| spent > *planned).map(|planned| spent - planned);
+
+  html! {
+    div class=(if over.is_some() { "budget-progress over" } else { "budget-progress" }) {
+      div class="row" {
+        span class="name" { (label) }
+        span class="amount" {
+          (spent)
+          @if let Some(planned) = planned {
+            " / " (planned)
+          }
+          span { (config::get().default_currency) }
+        }
+      }
+
+      @if let Some(planned) = planned {
+        progress value=(spent.min(planned)) max=(planned) {}
+      }
+
+      @if let Some(over) = over {
+        span class="overspent" { "over by " (over) }
+      }
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use crate::components::field_error;
+use crate::models::category::Category;
+use crate::utils::validation::FormErrors;
+use maud::{html, Markup};
+
+/// The field choosing the category of an expense among those of the sheet,
+/// nothing is shown when the sheet has none.
+pub fn category_select(sheet_id: i32, selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    @match Category::get_all_by_sheet_id(sheet_id) {
+      Ok(categories) => {
+        @if !categories.is_empty() {
+          div {
+            label for="category_id" { "Category" }
+            select id="category_id" name="category_id" {
+              option value="" { "none" }
+
+              @for category in &categories {
+                @if category.id.to_string() == selected {
+                  option value=(category.id) selected { (category.name) }
+                } @else {
+                  option value=(category.id) { (category.name) }
+                }
+              }
+            }
+            (field_error(errors, "category_id"))
+          }
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the categories: " (e)
+      }
+    }
+  }
+}
\ No newline at end of file
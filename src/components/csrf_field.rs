@@ -0,0 +1,15 @@
+use crate::middlewares::CsrfToken;
+use actix_web::HttpRequest;
+use maud::{html, Markup};
+
+/// The hidden field every POST form needs, see `middlewares::CsrfProtection`.
+pub fn csrf_field(req: &HttpRequest) -> Markup {
+  let token = req.extensions()
+    .get::<CsrfToken>()
+    .map(|token| token.0.clone())
+    .unwrap_or_default();
+
+  html! {
+    input type="hidden" name="csrf_token" value=(token);
+  }
+}
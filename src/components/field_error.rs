@@ -0,0 +1,12 @@
+use crate::utils::validation::FormErrors;
+use maud::{html, Markup};
+
+/// The message explaining why the given field of the form was refused, if it
+/// was.
+pub fn field_error(errors: &FormErrors, field: &str) -> Markup {
+  html! {
+    @if let Some(message) = errors.get(field) {
+      span class="field-error" { (message) }
+    }
+  }
+}
@@ -0,0 +1,50 @@
+use crate::models::forecast::Forecast;
+use crate::utils::date;
+use maud::{html, Markup};
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 200.0;
+const PADDING: f64 = 20.0;
+
+/// The projected balance as an inline SVG line chart, the area below zero is
+/// highlighted when the balance gets there.
+pub fn forecast_chart(forecast: &Forecast) -> Markup {
+  // zero is always in the range so the line can be compared to it
+  let lowest = forecast.lowest().min(0) as f64;
+  let highest = forecast.highest().max(0) as f64;
+  let range = (highest - lowest).max(1.0);
+  let last_day = (forecast.balances.len() as f64 - 1.0).max(1.0);
+
+  let x = |day: usize| PADDING + day as f64 * (WIDTH - 2.0 * PADDING) / last_day;
+  let y = |balance: f64| PADDING + (highest - balance) * (HEIGHT - 2.0 * PADDING) / range;
+
+  let points = forecast.balances
+    .iter()
+    .enumerate()
+    .map(|(day, balance)| format!("{:.1},{:.1}", x(day), y(*balance as f64)))
+    .collect::<Vec<String>>()
+    .join(" ");
+
+  let zero = y(0.0);
+
+  html! {
+    svg class="forecast-chart" viewBox={"0 0 " (WIDTH) " " (HEIGHT)} xmlns="http://www.w3.org/2000/svg" role="img" {
+      title { "projected balance" }
+
+      @if lowest < 0.0 {
+        rect class="below-zero" x=(PADDING) y=(zero) width=(WIDTH - 2.0 * PADDING) height=(HEIGHT - PADDING - zero) {}
+      }
+
+      line class="zero" x1=(PADDING) y1=(zero) x2=(WIDTH - PADDING) y2=(zero) {}
+      polyline class="balance" points=(points) {}
+
+      text class="label" x=(PADDING) y=(PADDING - 6.0) { (highest) }
+      text class="label" x=(PADDING) y=(HEIGHT - 6.0) { (date::format_date(forecast.start_date)) }
+      text class="label end" x=(WIDTH - PADDING) y=(HEIGHT - 6.0) { (date::format_date(forecast.end_date())) }
+
+      @if lowest < 0.0 {
+        text class="label" x=(PADDING + 4.0) y=(HEIGHT - PADDING - 4.0) { (lowest) }
+      }
+    }
+  }
+}
\ No newline at end of file
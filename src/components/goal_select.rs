@@ -0,0 +1,36 @@
+use crate::components::field_error;
+use crate::models::goal::Goal;
+use crate::utils::validation::FormErrors;
+use maud::{html, Markup};
+
+/// The field choosing the goal an expense or an income contributes to,
+/// nothing is shown when there is no goal.
+pub fn goal_select(selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    @match Goal::get_all() {
+      Ok(goals) => {
+        @if !goals.is_empty() {
+          div {
+            label for="goal_id" { "Goal" }
+            select id="goal_id" name="goal_id" {
+              option value="" { "none" }
+
+              @for goal in &goals {
+                @if goal.id.to_string() == selected {
+                  option value=(goal.id) selected { (goal.name) }
+                } @else {
+                  option value=(goal.id) { (goal.name) }
+                }
+              }
+            }
+            (field_error(errors, "goal_id"))
+          }
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the goals: " (e)
+      }
+    }
+  }
+}
\ No newline at end of file
@@ -4,7 +4,14 @@ pub fn menu(page_title: &str) -> Markup {
   html! {
     div.menu {
       (menu_link(&html! { "sheets" }, "/sheets", page_title, "sheets"))
+      (menu_link(&html! { "reports" }, "/reports", page_title, "reports"))
+      (menu_link(&html! { "goals" }, "/goals", page_title, "goals"))
+      (menu_link(&html! { "accounts" }, "/accounts", page_title, "accounts"))
+      (menu_link(&html! { "trash" }, "/trash", page_title, "trash"))
       (menu_link(&html! { "home" }, "/", page_title, "root"))
+      (menu_link(&html! { "settings" }, "/settings", page_title, "settings"))
+      (menu_link(&html! { "sessions" }, "/sessions", page_title, "sessions"))
+      (menu_link(&html! { "sign out" }, "/signout", page_title, "signout"))
     }
   }
 }
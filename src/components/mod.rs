@@ -1,8 +1,32 @@
 mod header;
+mod account_select;
+mod admin_menu;
+mod audit_log;
+mod budget_progress;
+mod category_select;
+mod csrf_field;
+mod field_error;
+mod forecast_chart;
+mod goal_select;
 mod menu;
 mod page;
+mod report_chart;
+mod sheet_select;
+mod trash_item;
 
 pub use header::header;
 pub use menu::menu;
 pub use page::page;
-pub use page::page_without_menu;
\ No newline at end of file
+pub use page::page_without_menu;
+pub use account_select::account_select;
+pub use admin_menu::admin_menu;
+pub use audit_log::{audit_filter_form, audit_log};
+pub use budget_progress::budget_progress;
+pub use category_select::category_select;
+pub use csrf_field::csrf_field;
+pub use field_error::field_error;
+pub use forecast_chart::forecast_chart;
+pub use goal_select::goal_select;
+pub use report_chart::report_chart;
+pub use sheet_select::sheet_select;
+pub use trash_item::trash_item;
\ No newline at end of file
@@ -0,0 +1,73 @@
+use crate::models::report::{Report, SheetTotals};
+use crate::utils::date;
+use maud::{html, Markup};
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 220.0;
+const PADDING: f64 = 20.0;
+
+/// A line of the chart, named after the class drawing it.
+type Series = (&'static str, fn(&SheetTotals) -> i32);
+
+/// The total income, total expense and net of each reported sheet as an
+/// inline SVG line chart, the sheets evenly spaced from the oldest to the
+/// latest. Each point tells its sheet and amount when hovered.
+pub fn report_chart(report: &Report) -> Markup {
+  let series: [Series; 3] = [
+    ("income", |totals| totals.income),
+    ("expense", |totals| totals.expense),
+    ("net", SheetTotals::net)
+  ];
+
+  let amounts = report.sheets
+    .iter()
+    .flat_map(|totals| series.iter().map(move |(_, amount)| amount(totals)));
+
+  // zero is always in the range so the net can be compared to it
+  let lowest = amounts.clone().min().unwrap_or(0).min(0) as f64;
+  let highest = amounts.max().unwrap_or(0).max(0) as f64;
+  let range = (highest - lowest).max(1.0);
+
+  let x = |index: usize| match report.sheets.len() {
+    0 | 1 => WIDTH / 2.0,
+    count => PADDING + index as f64 * (WIDTH - 2.0 * PADDING) / (count - 1) as f64
+  };
+  let y = |amount: i32| PADDING + (highest - amount as f64) * (HEIGHT - 2.0 * PADDING) / range;
+
+  html! {
+    svg class="report-chart" viewBox={"0 0 " (WIDTH) " " (HEIGHT)} xmlns="http://www.w3.org/2000/svg" role="img" {
+      title { "income, expense and net per sheet" }
+
+      line class="zero" x1=(PADDING) y1=(y(0)) x2=(WIDTH - PADDING) y2=(y(0)) {}
+
+      @for (name, amount) in &series {
+        @let points = report.sheets
+          .iter()
+          .enumerate()
+          .map(|(index, totals)| format!("{:.1},{:.1}", x(index), y(amount(totals))))
+          .collect::<Vec<String>>()
+          .join(" ");
+
+        polyline class=(name) points=(points) {}
+
+        @for (index, totals) in report.sheets.iter().enumerate() {
+          circle class=(name) cx=(format!("{:.1}", x(index))) cy=(format!("{:.1}", y(amount(totals)))) r="3" {
+            title { (totals.sheet.name) ", " (name) ": " (amount(totals)) }
+          }
+        }
+      }
+
+      text class="label" x=(PADDING) y=(PADDING - 6.0) { (highest) }
+
+      @if lowest < 0.0 {
+        text class="label" x=(PADDING) y=(HEIGHT - 6.0) { (lowest) }
+      }
+
+      @if let (Some(first), Some(last)) = (report.sheets.first(), report.sheets.last()) {
+        text class="label end" x=(WIDTH - PADDING) y=(HEIGHT - 6.0) {
+          (date::format_date(first.date)) " to " (date::format_date(last.date))
+        }
+      }
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,33 @@
+use crate::components::field_error;
+use crate::models::sheet::Sheet;
+use crate::utils::validation::FormErrors;
+use maud::{html, Markup};
+
+/// The field choosing the sheet dedicated to a goal, it can be left to none.
+pub fn sheet_select(selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    @match Sheet::get_all() {
+      Ok(sheets) => {
+        div {
+          label for="sheet_id" { "Sheet" }
+          select id="sheet_id" name="sheet_id" {
+            option value="" { "none" }
+
+            @for sheet in &sheets {
+              @if sheet.id.to_string() == selected {
+                option value=(sheet.id) selected { (sheet.name) }
+              } @else {
+                option value=(sheet.id) { (sheet.name) }
+              }
+            }
+          }
+          (field_error(errors, "sheet_id"))
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the sheets: " (e)
+      }
+    }
+  }
+}
\ No newline at end of file
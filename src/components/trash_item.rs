@@ -0,0 +1,79 @@
+use crate::components::csrf_field;
+use crate::models::trash::TrashItem;
+use crate::utils::date::format_datetime;
+
+use actix_web::HttpRequest;
+use maud::{html, Markup};
+use std::collections::HashMap;
+
+/// A row describing a deleted item with the form restoring it, the button
+/// reads `label`. `sheet_names` gives the names of the sheets by id.
+pub fn trash_item(req: &HttpRequest, item: &TrashItem, sheet_names: &HashMap<i32, String>, label: &str) -> Markup {
+  let sheet_name = |id: i32| sheet_names.get(&id).cloned().unwrap_or_else(|| format!("#{}", id));
+
+  html! {
+    div.row {
+      @match item {
+        TrashItem::Sheet(sheet) => {
+          span.kind { "sheet" }
+          span.name { (sheet.name) }
+        },
+        TrashItem::Expense(expense) => {
+          span.kind { "expense" }
+          span.amount { (expense.amount) }
+          span.name { (expense.name) }
+        },
+        TrashItem::Income(income) => {
+          span.kind { "income" }
+          span.amount { (income.amount) }
+          span.name { (income.name) }
+        },
+        TrashItem::InheritedSheet(inherited_sheet) => {
+          span.kind { "import" }
+          span.name { (sheet_name(inherited_sheet.inherited_sheet_id)) }
+        }
+      }
+
+      @if let Some(sheet_id) = item.sheet_id() {
+        span.sheet { "from " a href={"/sheet/" (sheet_id)} { (sheet_name(sheet_id)) } }
+      }
+
+      span.date { (format_datetime(item.deleted_date())) }
+
+      div.actions {
+        @match item {
+          TrashItem::Sheet(sheet) => {
+            form method="post" action="/api/sheets/restore-by-id" {
+              (csrf_field(req))
+              input type="hidden" name="id" value=(sheet.id);
+              input.link type="submit" value=(label);
+            }
+          },
+          TrashItem::Expense(expense) => {
+            form method="post" action="/api/expenses/restore-by-id" {
+              (csrf_field(req))
+              input type="hidden" name="id" value=(expense.id);
+              input.link type="submit" value=(label);
+            }
+          },
+          TrashItem::Income(income) => {
+            form method="post" action="/api/incomes/restore-by-id" {
+              (csrf_field(req))
+              input type="hidden" name="id" value=(income.id);
+              input.link type="submit" value=(label);
+            }
+          },
+          TrashItem::InheritedSheet(inherited_sheet) => {
+            form method="post" action="/api/inherited-sheets/restore" {
+              (csrf_field(req))
+              input type="hidden" name="sheet_id" value=(inherited_sheet.parent_sheet_id);
+              input type="hidden" name="inherited_sheet_id" value=(inherited_sheet.inherited_sheet_id);
+              input type="hidden" name="deleted_date" value=(item.deleted_date());
+              input.link type="submit" value=(label);
+            }
+          }
+        }
+      }
+    }
+  }
+}
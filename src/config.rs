@@ -0,0 +1,211 @@
+use crate::constants;
+use serde::Deserialize;
+use std::net::ToSocketAddrs;
+use std::sync::OnceLock;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// The configuration of the instance. Every value starts from its default in
+/// `constants`, is then overridden by the toml configuration file and finally
+/// by the `CALCO_*` environment variables.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub database_path: String,
+  pub bind: String,
+  pub port: u16,
+  pub admin_handle: String,
+
+  /// in seconds
+  pub session_lifetime: u64,
+
+  /// in seconds
+  pub invitation_lifetime: u64,
+
+  /// in seconds, how long the deleted items can be restored
+  pub trash_retention: u64,
+
+  pub static_directory: String,
+  pub default_currency: String,
+
+  /// whether the cookies are only sent over https, to enable when the
+  /// instance is served behind https
+  pub secure_cookies: bool
+}
+
+impl Default for Config {
+  fn default() -> Config {
+    Config {
+      database_path: constants::DATABASE_PATH.to_owned(),
+      bind: constants::BIND_ADDRESS.to_owned(),
+      port: constants::PORT,
+      admin_handle: constants::ADMIN_HANDLE.to_owned(),
+      session_lifetime: constants::USER_TOKEN_EXPIRE_DURATION_SECONDS,
+      invitation_lifetime: constants::INVITATION_EXPIRE_DURATION_SECONDS,
+      trash_retention: constants::TRASH_RETENTION_SECONDS,
+      static_directory: constants::STATIC_DIRECTORY.to_owned(),
+      default_currency: constants::CURRENCY.to_owned(),
+      secure_cookies: false
+    }
+  }
+}
+
+impl Config {
+  /// Loads the configuration from the file at `path` then from the
+  /// environment. When no path is given, `CALCO_CONFIG` is used and finally
+  /// `constants::CONFIG_PATH` if such a file exists.
+  pub fn load(path: Option<&str>) -> Result<Config, Vec<String>> {
+    let env_path = std::env::var("CALCO_CONFIG").ok();
+    let explicit_path = path.map(|p| p.to_owned()).or(env_path);
+
+    let mut config = match &explicit_path {
+      Some(path) => Config::from_file(path)?,
+      None if std::path::Path::new(constants::CONFIG_PATH).exists() => {
+        Config::from_file(constants::CONFIG_PATH)?
+      },
+      None => Config::default()
+    };
+
+    config.apply_environment()?;
+
+    Ok(config)
+  }
+
+  fn from_file(path: &str) -> Result<Config, Vec<String>> {
+    let content = std::fs::read_to_string(path)
+      .map_err(|err| vec![format!("could not read configuration file {}: {}", path, err)])?;
+
+    toml::from_str(&content)
+      .map_err(|err| vec![format!("invalid configuration file {}: {}", path, err)])
+  }
+
+  fn apply_environment(&mut self) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    if let Ok(value) = std::env::var("CALCO_DATABASE_PATH") {
+      self.database_path = value;
+    }
+
+    if let Ok(value) = std::env::var("CALCO_BIND") {
+      self.bind = value;
+    }
+
+    if let Ok(value) = std::env::var("CALCO_PORT") {
+      match value.parse::<u16>() {
+        Ok(port) => self.port = port,
+        Err(_) => errors.push(format!("CALCO_PORT: {} is not a valid port", value))
+      };
+    }
+
+    if let Ok(value) = std::env::var("CALCO_ADMIN_HANDLE") {
+      self.admin_handle = value;
+    }
+
+    if let Ok(value) = std::env::var("CALCO_SESSION_LIFETIME") {
+      match value.parse::<u64>() {
+        Ok(seconds) => self.session_lifetime = seconds,
+        Err(_) => errors.push(format!("CALCO_SESSION_LIFETIME: {} is not a number of seconds", value))
+      };
+    }
+
+    if let Ok(value) = std::env::var("CALCO_INVITATION_LIFETIME") {
+      match value.parse::<u64>() {
+        Ok(seconds) => self.invitation_lifetime = seconds,
+        Err(_) => errors.push(format!("CALCO_INVITATION_LIFETIME: {} is not a number of seconds", value))
+      };
+    }
+
+    if let Ok(value) = std::env::var("CALCO_TRASH_RETENTION") {
+      match value.parse::<u64>() {
+        Ok(seconds) => self.trash_retention = seconds,
+        Err(_) => errors.push(format!("CALCO_TRASH_RETENTION: {} is not a number of seconds", value))
+      };
+    }
+
+    if let Ok(value) = std::env::var("CALCO_STATIC_DIRECTORY") {
+      self.static_directory = value;
+    }
+
+    if let Ok(value) = std::env::var("CALCO_DEFAULT_CURRENCY") {
+      self.default_currency = value;
+    }
+
+    if let Ok(value) = std::env::var("CALCO_SECURE_COOKIES") {
+      match value.parse::<bool>() {
+        Ok(secure) => self.secure_cookies = secure,
+        Err(_) => errors.push(format!("CALCO_SECURE_COOKIES: {} is not true or false", value))
+      };
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    }
+    else {
+      Err(errors)
+    }
+  }
+
+  /// Returns every problem found in the configuration, so they can all be
+  /// reported at once when the instance starts. The address and the static
+  /// files are only checked when `serving`, the other commands do not use
+  /// them.
+  pub fn validate(&self, serving: bool) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    if self.database_path.trim().is_empty() {
+      errors.push(String::from("database_path: cannot be empty"));
+    }
+
+    if serving && self.port == 0 {
+      errors.push(String::from("port: must be between 1 and 65535"));
+    }
+
+    if serving && (self.bind.as_str(), self.port).to_socket_addrs().is_err() {
+      errors.push(format!("bind: {} is not a valid address", self.bind));
+    }
+
+    if self.admin_handle.trim().is_empty() || self.admin_handle.contains(char::is_whitespace) {
+      errors.push(String::from("admin_handle: cannot be empty or contain spaces"));
+    }
+
+    if self.session_lifetime == 0 {
+      errors.push(String::from("session_lifetime: must be at least one second"));
+    }
+
+    if self.invitation_lifetime == 0 {
+      errors.push(String::from("invitation_lifetime: must be at least one second"));
+    }
+
+    if self.trash_retention == 0 {
+      errors.push(String::from("trash_retention: must be at least one second"));
+    }
+
+    if serving && !std::path::Path::new(&self.static_directory).is_dir() {
+      errors.push(format!("static_directory: {} is not a directory", self.static_directory));
+    }
+
+    if self.default_currency.trim().is_empty() {
+      errors.push(String::from("default_currency: cannot be empty"));
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    }
+    else {
+      Err(errors)
+    }
+  }
+}
+
+/// Sets the configuration used by the whole instance, it can only be set once.
+pub fn set(config: Config) {
+  if CONFIG.set(config).is_err() {
+    println!("configuration already set, ignoring the new one");
+  }
+}
+
+/// Returns the configuration of the instance, or the default configuration
+/// if none was set.
+pub fn get() -> &'static Config {
+  CONFIG.get_or_init(Config::default)
+}
@@ -1,14 +1,89 @@
 pub const DATABASE_PATH: &str = "calco-database.db";
 
+pub const BIND_ADDRESS: &str = "127.0.0.1";
+
+pub const PORT: u16 = 5000;
+
+pub const CONFIG_PATH: &str = "calco.toml";
+
+pub const STATIC_DIRECTORY: &str = "./static";
+
+pub const CURRENCY: &str = "EUR";
+
+/// Longest name accepted for sheets, expenses and incomes, in characters.
+pub const NAME_MAX_LENGTH: usize = 100;
+
+/// Amounts of the expenses and incomes must be between 1 and this value.
+pub const AMOUNT_MAX: i32 = 10_000_000;
+
+/// Number of audit entries listed on the history pages.
+pub const HISTORY_LENGTH: i64 = 200;
+
 pub static INVITATION_EXPIRE_DURATION_SECONDS: u64 = 3600;
 
 pub static USER_TOKEN_EXPIRE_DURATION_SECONDS: u64 = 3600;
 
+pub const PASSWORD_RESET_EXPIRE_DURATION_SECONDS: i64 = 3600;
+
+/// Time given to enter the second factor once the password was accepted.
+pub const TWO_FACTOR_CHALLENGE_EXPIRE_DURATION_SECONDS: i64 = 300;
+
+pub const TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+
+pub const RECOVERY_CODES_COUNT: usize = 8;
+
+/// Failed attempts older than this are not counted by the brute-force
+/// protection anymore.
+pub const BRUTE_FORCE_WINDOW_SECONDS: i64 = 3600;
+
+/// Number of failures allowed before the backoff starts.
+pub const BRUTE_FORCE_FREE_ATTEMPTS: i64 = 3;
+
+pub const BRUTE_FORCE_BASE_DELAY_SECONDS: i64 = 2;
+
+pub const BRUTE_FORCE_MAX_DELAY_SECONDS: i64 = 300;
+
+/// Number of failures for a single handle after which the account is locked.
+pub const ACCOUNT_LOCKOUT_ATTEMPTS: i64 = 10;
+
+pub const ACCOUNT_LOCKOUT_DURATION_SECONDS: i64 = 900;
+
+/// The pages do not use any javascript nor load anything from other origins.
+pub const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; script-src 'none'; object-src 'none'; base-uri 'self'; form-action 'self'; frame-ancestors 'none'";
+
+pub const FAILED_ATTEMPTS_RETENTION_SECONDS: i64 = 3600 * 24 * 30;
+
+/// sessions are renewed at most once per interval, to avoid a write on every request
+pub const SESSION_RENEWAL_INTERVAL_SECONDS: i64 = 60;
+
+/// how long expired invitations are kept before being purged
+pub const STALE_INVITATION_GRACE_SECONDS: i64 = 7 * 24 * 3600;
+
+/// the interval between two runs of the maintenance tasks
+pub const MAINTENANCE_INTERVAL_SECONDS: u64 = 3600;
+
+/// Time the deleted sheets, expenses, incomes and imports stay in the trash
+/// before being purged.
+pub const TRASH_RETENTION_SECONDS: u64 = 30 * 24 * 3600;
+
+/// Time during which the sheet pages offer to undo a deletion.
+pub const UNDO_DELAY_SECONDS: i64 = 600;
+
+/// Number of days projected by the forecast of a sheet, about a quarter.
+pub const FORECAST_DAYS: i64 = 92;
+
+pub const FORECAST_MAX_DAYS: i64 = 366;
+
 pub const ADMIN_HANDLE: &str = "thottou";
 
+/// Number of PBKDF2 iterations of newly hashed passwords, the passwords
+/// hashed with fewer iterations are rehashed when their owner signs in.
+pub const PASSWORD_HASH_ITERATIONS: u32 = 310_000;
+
+/// Only used to verify passwords hashed by the legacy scheme.
 pub const SALT_COMPONENT: [u8; 16] = [
   0x3f, 0x12, 0x9b, 0x5c,
   0x22, 0xb0, 0x12, 0x6d,
   0xe5, 0x6a, 0x09, 0x7a,
   0x97, 0xaa, 0xf9, 0x99
-];
\ No newline at end of file
+];
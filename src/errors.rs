@@ -0,0 +1,165 @@
+use crate::components;
+use crate::utils::validation::FormErrors;
+
+use actix_web::{http, HttpResponse, ResponseError};
+use actix_web::http::StatusCode;
+use maud::{html, Markup};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The errors the handlers answer with. Browsers get a page explaining what
+/// went wrong, the api clients get a `{"error": "..."}` json body instead,
+/// see the `JsonErrors` middleware.
+///
+/// The message is shown to the client as is, the underlying errors are only
+/// printed on the server.
+#[derive(Debug)]
+pub enum AppError {
+  /// the request is malformed
+  BadRequest(String),
+
+  /// some fields of the submitted form are invalid, the browsers get the
+  /// form again with the errors next to the fields
+  InvalidForm(FormErrors, String),
+
+  /// the request needs an account, or the credentials are wrong
+  Unauthorized(String),
+
+  /// the user is signed in but is not allowed to do this
+  Forbidden(String),
+
+  NotFound(String),
+
+  /// the request conflicts with the current state, e.g. a taken handle
+  Conflict(String),
+
+  /// too many attempts, the client should wait for the given seconds
+  TooManyRequests(String, i64),
+
+  Internal(String)
+}
+
+impl AppError {
+  pub fn bad_request(message: &str) -> AppError {
+    AppError::BadRequest(message.to_owned())
+  }
+
+  /// `page` is the form that was submitted, rendered again with the input
+  /// of the user and the errors.
+  pub fn invalid_form(errors: FormErrors, page: Markup) -> AppError {
+    AppError::InvalidForm(errors, page.into_string())
+  }
+
+  pub fn unauthorized(message: &str) -> AppError {
+    AppError::Unauthorized(message.to_owned())
+  }
+
+  pub fn forbidden(message: &str) -> AppError {
+    AppError::Forbidden(message.to_owned())
+  }
+
+  pub fn not_found(message: &str) -> AppError {
+    AppError::NotFound(message.to_owned())
+  }
+
+  pub fn conflict(message: &str) -> AppError {
+    AppError::Conflict(message.to_owned())
+  }
+
+  pub fn internal(message: &str) -> AppError {
+    AppError::Internal(message.to_owned())
+  }
+
+  pub fn message(&self) -> &str {
+    match self {
+      AppError::BadRequest(message) => message,
+      AppError::InvalidForm(_, _) => "some fields of the form are invalid",
+      AppError::Unauthorized(message) => message,
+      AppError::Forbidden(message) => message,
+      AppError::NotFound(message) => message,
+      AppError::Conflict(message) => message,
+      AppError::TooManyRequests(message, _) => message,
+      AppError::Internal(message) => message
+    }
+  }
+
+  pub fn to_json(&self) -> String {
+    let fields = match self {
+      AppError::InvalidForm(errors, _) => errors
+        .iter()
+        .map(|(field, message)| (*field, message.as_str()))
+        .collect(),
+      _ => BTreeMap::new()
+    };
+
+    serde_json::to_string(&ErrorBody { error: self.message(), fields })
+      .unwrap_or_else(|_| String::from("{}"))
+  }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+  error: &'a str,
+
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  fields: BTreeMap<&'a str, &'a str>
+}
+
+impl fmt::Display for AppError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      AppError::Internal(message) => write!(f, "Internal server error: {}", message),
+      error => write!(f, "{}", error.message())
+    }
+  }
+}
+
+impl ResponseError for AppError {
+  fn status_code(&self) -> StatusCode {
+    match self {
+      AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+      AppError::InvalidForm(_, _) => StatusCode::BAD_REQUEST,
+      AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+      AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+      AppError::NotFound(_) => StatusCode::NOT_FOUND,
+      AppError::Conflict(_) => StatusCode::CONFLICT,
+      AppError::TooManyRequests(_, _) => StatusCode::TOO_MANY_REQUESTS,
+      AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR
+    }
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    let status = self.status_code();
+
+    if let AppError::InvalidForm(_, page) = self {
+      return HttpResponse::build(status)
+        .content_type("text/html")
+        .body(page.clone());
+    }
+
+    let content = html! {
+      div class="form-wrapper" {
+        h1 { "HTTP " (status.as_u16()) }
+        p { (self) }
+
+        @if let AppError::Unauthorized(_) = self {
+          a href="/signin" { "sign in" }
+        } @else {
+          a href="/" { "go back home" }
+        }
+      }
+    };
+
+    let view = components::page_without_menu("error", &content);
+    let mut response = HttpResponse::build(status);
+
+    if let AppError::TooManyRequests(_, retry_after) = self {
+      response.header(http::header::RETRY_AFTER, retry_after.to_string());
+    }
+
+    response
+      .content_type("text/html")
+      .body(view.into_string())
+  }
+}
@@ -6,38 +6,169 @@
 
 extern crate chrono;
 
-use actix_web::{App, web, HttpServer};
+use actix_web::{middleware, App, web, HttpServer};
 use actix_files as fs;
 
 mod pages;
 mod components;
 mod constants;
+mod config;
 mod models;
 mod api;
 mod utils;
+mod cli;
+mod tasks;
+mod middlewares;
+mod errors;
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
+  let arguments = match cli::parse_arguments(std::env::args().skip(1).collect()) {
+    Ok(arguments) => arguments,
+    Err(e) => {
+      eprintln!("{}\n\n{}", e, cli::USAGE);
+      std::process::exit(1);
+    }
+  };
+
+  let mut configuration = match config::Config::load(arguments.config.as_deref()) {
+    Ok(configuration) => configuration,
+    Err(errors) => exit_with_configuration_errors(errors)
+  };
+
+  if let Some(path) = arguments.database {
+    configuration.database_path = path;
+  }
+
+  if let cli::Command::Serve { port, bind, admin_handle } = &arguments.command {
+    if let Some(port) = port {
+      configuration.port = *port;
+    }
+
+    if let Some(bind) = bind {
+      configuration.bind = bind.clone();
+    }
+
+    if let Some(admin_handle) = admin_handle {
+      configuration.admin_handle = admin_handle.clone();
+    }
+  }
+
+  let serving = matches!(arguments.command, cli::Command::Serve { .. });
+
+  if let Err(errors) = configuration.validate(serving) {
+    exit_with_configuration_errors(errors);
+  }
+
+  config::set(configuration);
+
   match models::create_database() {
     Ok(()) => println!("database ready"),
-    Err(e) => panic!(e)
+    Err(e) => panic!("{}", e)
   };
 
-  let port: i32 = std::env::args()
-    .nth(1)
-    .and_then(|n| n.parse::<i32>().ok())
-    .unwrap_or(5000);
+  if serving {
+    match models::user::get_duplicated_handles() {
+      Ok(handles) if handles.is_empty() => {},
+      Ok(handles) => exit_with_duplicated_handles(handles),
+      Err(e) => panic!("{}", e)
+    };
+  }
+
+  match arguments.command {
+    cli::Command::Serve { .. } => serve().await,
+    command => {
+      if let Err(e) = cli::run(command) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+      }
+
+      Ok(())
+    }
+  }
+}
+
+fn exit_with_configuration_errors(errors: Vec<String>) -> ! {
+  eprintln!("invalid configuration:");
+
+  for error in errors {
+    eprintln!("  - {}", error);
+  }
 
-  println!("starting server on port {}", port);
+  std::process::exit(1);
+}
 
-  HttpServer::new(|| {
+fn exit_with_duplicated_handles(handles: Vec<String>) -> ! {
+  eprintln!("several users share the same handle, delete the duplicates with `calco delete-user --handle <handle>`:");
+
+  for handle in handles {
+    eprintln!("  - {}", handle);
+  }
+
+  std::process::exit(1);
+}
+
+async fn serve() -> std::io::Result<()> {
+  let configuration = config::get();
+
+  match models::bootstrap_admin(&configuration.admin_handle) {
+    Ok(Some(invitation)) => println!(
+      "no admin account exists yet, create one for {} with http://{}:{}{}",
+      configuration.admin_handle,
+      configuration.bind,
+      configuration.port,
+      invitation.to_url()
+    ),
+    Ok(None) => {},
+    Err(e) => println!("could not create admin invitation {}", e)
+  };
+
+  tasks::spawn_maintenance();
+
+  println!("starting server on {}:{}", configuration.bind, configuration.port);
+
+  HttpServer::new(move || {
     App::new()
+    .wrap(middlewares::SessionCookie)
+    .wrap(middlewares::CsrfProtection)
+    .wrap(middlewares::BruteForceProtection::new(&[
+      "/api/auth/signin",
+      "/api/auth/signup",
+      "/api/auth/two-factor",
+      "/api/auth/reset-password"
+    ]))
+    .wrap(middlewares::JsonErrors)
+    // registered last so it wraps the others, the responses they build
+    // themselves get the headers too
+    .wrap(
+      middleware::DefaultHeaders::new()
+        .header("Content-Security-Policy", constants::CONTENT_SECURITY_POLICY)
+        .header("X-Frame-Options", "DENY")
+        .header("X-Content-Type-Options", "nosniff")
+        .header("Referrer-Policy", "same-origin")
+    )
+    .app_data(
+      web::FormConfig::default()
+        .error_handler(|err, _req| errors::AppError::BadRequest(format!("invalid form, {}", err)).into())
+    )
+    .default_service(web::route().to(pages::not_found::render))
+
     // home page
     .service(web::resource("/").route(web::get().to(pages::root::render)))
 
     // auth pages
     .route("/signup", web::get().to(pages::signup::render))
     .route("/signin", web::get().to(pages::signin::render))
+    .route("/signin/two-factor", web::get().to(pages::signin_two_factor::render))
+    .service(
+      web::resource("/signout")
+        .route(web::get().to(pages::signout::render))
+        .route(web::post().to(api::auth::signout))
+    )
+    .route("/reset-password", web::get().to(pages::reset_password::render))
+    .service(web::resource("/sessions").route(web::get().to(pages::sessions::render)))
+    .service(web::resource("/settings").route(web::get().to(pages::settings::render)))
+    .service(web::resource("/settings/two-factor").route(web::get().to(pages::settings_two_factor::render)))
     
     // sheets pages
     .service(web::resource("/sheets").route(web::get().to(pages::sheets::render)))
@@ -46,35 +177,112 @@ async fn main() -> std::io::Result<()> {
     .service(web::resource("/sheet/{sheet_id}").route(web::get().to(pages::sheet::render)))
     .service(web::resource("/sheet/{sheet_id}/expenses/new").route(web::get().to(pages::new_sheet_expense::render)))
     .service(web::resource("/expense/{expense_id}/edit").route(web::get().to(pages::edit_expense::render)))
+    .service(web::resource("/expense/{expense_id}/split").route(web::get().to(pages::expense_split::render)))
     .service(web::resource("/sheet/{sheet_id}/incomes/new").route(web::get().to(pages::new_sheet_income::render)))
     .service(web::resource("/income/{income_id}/edit").route(web::get().to(pages::edit_income::render)))
     .service(web::resource("/sheet/{sheet_id}/inherited-sheets/new").route(web::get().to(pages::new_sheet_inherited_sheet::render)))
+    .service(web::resource("/sheet/{sheet_id}/history").route(web::get().to(pages::sheet_history::render)))
+    .service(web::resource("/sheet/{sheet_id}/duplicate").route(web::get().to(pages::duplicate_sheet::render)))
+    .service(web::resource("/sheet/{sheet_id}/template").route(web::get().to(pages::new_sheet_template::render)))
+    .service(web::resource("/sheet/{sheet_id}/budget").route(web::get().to(pages::sheet_budget::render)))
+    .service(web::resource("/sheet/{sheet_id}/forecast").route(web::get().to(pages::sheet_forecast::render)))
+    .service(web::resource("/sheet/{sheet_id}/recurring-entries/new").route(web::get().to(pages::new_recurring_entry::render)))
+    .service(web::resource("/sheet/{sheet_id}/transfers/new").route(web::get().to(pages::new_transfer::render)))
+    .service(web::resource("/transfer/{transfer_id}/edit").route(web::get().to(pages::edit_transfer::render)))
+    .service(web::resource("/sheet/{sheet_id}/settle-up").route(web::get().to(pages::sheet_settle_up::render)))
+    .service(web::resource("/sheet/{sheet_id}/settlements/new").route(web::get().to(pages::new_settlement::render)))
+    .service(web::resource("/sheet/{sheet_id}/categories/new").route(web::get().to(pages::new_sheet_category::render)))
+    .service(web::resource("/category/{category_id}/edit").route(web::get().to(pages::edit_category::render)))
+    .service(web::resource("/reports").route(web::get().to(pages::reports::render)))
+    .service(web::resource("/goals").route(web::get().to(pages::goals::render)))
+    .service(web::resource("/goals/new").route(web::get().to(pages::new_goal::render)))
+    .service(web::resource("/goal/{goal_id}").route(web::get().to(pages::goal::render)))
+    .service(web::resource("/goal/{goal_id}/edit").route(web::get().to(pages::edit_goal::render)))
+    .service(web::resource("/accounts").route(web::get().to(pages::accounts::render)))
+    .service(web::resource("/accounts/new").route(web::get().to(pages::new_account::render)))
+    .service(web::resource("/account/{account_id}").route(web::get().to(pages::account::render)))
+    .service(web::resource("/account/{account_id}/edit").route(web::get().to(pages::edit_account::render)))
+    .service(web::resource("/account/{account_id}/statements/new").route(web::get().to(pages::new_statement::render)))
+    .service(web::resource("/account/{account_id}/reconcile").route(web::get().to(pages::account_reconcile::render)))
+    .service(web::resource("/trash").route(web::get().to(pages::trash::render)))
+
+    // admin pages
+    .service(web::resource("/admin/users").route(web::get().to(pages::admin_users::render)))
+    .service(web::resource("/admin/users/{user_id}/delete").route(web::get().to(pages::admin_delete_user::render)))
+    .service(web::resource("/admin/invitations").route(web::get().to(pages::admin_invitations::render)))
+    .service(web::resource("/admin/password-resets").route(web::get().to(pages::admin_password_resets::render)))
+    .service(web::resource("/admin/failed-attempts").route(web::get().to(pages::admin_failed_attempts::render)))
+    .service(web::resource("/admin/history").route(web::get().to(pages::admin_history::render)))
 
     // static files
-    .service(fs::Files::new("/static", "./static"))
+    .service(fs::Files::new("/static", &configuration.static_directory))
 
     // api endpoints
     .service(
       web::scope("/api")
         .route("/auth/signup", web::post().to(api::auth::signup))
         .route("/auth/signin", web::post().to(api::auth::signin))
+        .route("/auth/two-factor", web::post().to(api::auth::signin_two_factor))
+        .route("/auth/change-password", web::post().to(api::auth::change_password))
+        .route("/auth/reset-password", web::post().to(api::auth::reset_password))
+        .route("/sessions/revoke-by-id", web::post().to(api::sessions::revoke_session_by_id))
+        .route("/two-factor/setup", web::post().to(api::two_factor::setup))
+        .route("/two-factor/enable", web::post().to(api::two_factor::enable))
+        .route("/two-factor/disable", web::post().to(api::two_factor::disable))
+        .route("/two-factor/recovery-codes", web::post().to(api::two_factor::regenerate_recovery_codes))
         .route("/users/delete-by-id", web::post().to(api::users::delete_user))
+        .route("/users/role-by-id", web::post().to(api::users::set_role))
+        .route("/users/reset-two-factor-by-id", web::post().to(api::users::reset_two_factor))
         .route("/sheets", web::post().to(api::sheet::create_sheet))
         .route("/sheets/delete-by-id", web::post().to(api::sheet::delete_sheet_by_id))
         .route("/sheets/rename-by-id", web::post().to(api::sheet::rename_sheet_by_id))
+        .route("/sheets/restore-by-id", web::post().to(api::sheet::restore_sheet_by_id))
+        .route("/sheets/duplicate-by-id", web::post().to(api::sheet::duplicate_sheet_by_id))
+        .route("/sheets/budget-by-id", web::post().to(api::sheet::set_sheet_budget_by_id))
+        .route("/categories", web::post().to(api::category::create_category))
+        .route("/categories/update-by-id", web::post().to(api::category::update_category_by_id))
+        .route("/categories/delete-by-id", web::post().to(api::category::delete_category_by_id))
+        .route("/goals", web::post().to(api::goal::create_goal))
+        .route("/goals/update-by-id", web::post().to(api::goal::update_goal_by_id))
+        .route("/goals/delete-by-id", web::post().to(api::goal::delete_goal_by_id))
+        .route("/accounts", web::post().to(api::account::create_account))
+        .route("/accounts/update-by-id", web::post().to(api::account::update_account_by_id))
+        .route("/accounts/delete-by-id", web::post().to(api::account::delete_account_by_id))
+        .route("/accounts/reconcile", web::post().to(api::account::reconcile))
+        .route("/statements", web::post().to(api::account::create_statement))
+        .route("/statements/delete-by-id", web::post().to(api::account::delete_statement_by_id))
+        .route("/sheet-templates", web::post().to(api::sheet_template::create_sheet_template))
+        .route("/sheet-templates/instantiate", web::post().to(api::sheet_template::create_sheet_from_template))
+        .route("/sheet-templates/delete-by-id", web::post().to(api::sheet_template::delete_sheet_template_by_id))
+        .route("/recurring-entries", web::post().to(api::recurring_entry::create_recurring_entry))
+        .route("/recurring-entries/delete-by-id", web::post().to(api::recurring_entry::delete_recurring_entry_by_id))
         .route("/expenses", web::post().to(api::expense::create_expense))
         .route("/expenses/delete-by-id", web::post().to(api::expense::delete_expense_by_id))
         .route("/expenses/update-by-id", web::post().to(api::expense::update_expense_by_id))
+        .route("/expenses/restore-by-id", web::post().to(api::expense::restore_expense_by_id))
         .route("/incomes", web::post().to(api::income::create_income))
         .route("/incomes/delete-by-id", web::post().to(api::income::delete_income_by_id))
         .route("/incomes/update-by-id", web::post().to(api::income::update_income_by_id))
+        .route("/incomes/restore-by-id", web::post().to(api::income::restore_income_by_id))
+        .route("/transfers", web::post().to(api::transfer::create_transfer))
+        .route("/transfers/update-by-id", web::post().to(api::transfer::update_transfer_by_id))
+        .route("/transfers/delete-by-id", web::post().to(api::transfer::delete_transfer_by_id))
+        .route("/splits", web::post().to(api::split::save_split))
+        .route("/splits/delete-by-id", web::post().to(api::split::delete_split_by_id))
+        .route("/settlements", web::post().to(api::settlement::create_settlement))
         .route("/inherited-sheets", web::post().to(api::inherited_sheet::create_inherited_sheet))
         .route("/inherited-sheets/delete", web::post().to(api::inherited_sheet::delete_inherited_sheet_by_id))
+        .route("/inherited-sheets/restore", web::post().to(api::inherited_sheet::restore_inherited_sheet))
         .route("/invitations", web::post().to(api::invitations::create_invitation))
+        .route("/invitations/extend-by-id", web::post().to(api::invitations::extend_invitation_by_id))
+        .route("/invitations/delete-by-id", web::post().to(api::invitations::delete_invitation_by_id))
+        .route("/password-resets", web::post().to(api::password_resets::create_password_reset))
+        .route("/password-resets/delete-by-id", web::post().to(api::password_resets::delete_password_reset_by_id))
+        .route("/failed-attempts/clear", web::post().to(api::failed_attempts::clear_failed_attempts))
     )
 
   })
-  .bind(format!("127.0.0.1:{}", port))?
+  .bind((configuration.bind.as_str(), configuration.port))?
   .run()
   .await
 }
\ No newline at end of file
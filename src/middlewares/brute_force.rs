@@ -0,0 +1,187 @@
+use crate::errors::AppError;
+use crate::models::failed_attempt::{self, FailedAttempt};
+use crate::models::two_factor::TwoFactorChallenge;
+use crate::models::user::User;
+use crate::utils::date;
+
+use crate::middlewares::peek_body;
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http, Error, HttpMessage};
+use futures::future::{ok, Ready};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// Slows down and eventually refuses the POST requests to the given paths
+/// once too many of them were answered with a 401, counting the failures per
+/// ip and per `handle` field of the form. The second step of a sign in has no
+/// handle, its failures count against the user of the two-factor challenge.
+/// The failures of a handle are cleared once a session is created. The limits
+/// are defined by the `BRUTE_FORCE_*` and `ACCOUNT_LOCKOUT_*` constants.
+///
+/// The ip is the address of the peer, the `Forwarded` headers are ignored
+/// since any client could set them.
+pub struct BruteForceProtection {
+  paths: Rc<Vec<String>>
+}
+
+impl BruteForceProtection {
+  pub fn new(paths: &[&str]) -> BruteForceProtection {
+    BruteForceProtection {
+      paths: Rc::new(paths.iter().map(|path| path.to_string()).collect())
+    }
+  }
+}
+
+impl<S, B> Transform<S> for BruteForceProtection
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = BruteForceProtectionMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(BruteForceProtectionMiddleware {
+      service: Rc::new(RefCell::new(service)),
+      paths: self.paths.clone()
+    })
+  }
+}
+
+pub struct BruteForceProtectionMiddleware<S> {
+  service: Rc<RefCell<S>>,
+  paths: Rc<Vec<String>>
+}
+
+/// The only field of the form the middleware cares about.
+#[derive(Deserialize)]
+struct HandleField {
+  handle: Option<String>
+}
+
+impl<S, B> Service for BruteForceProtectionMiddleware<S>
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.borrow_mut().poll_ready(cx)
+  }
+
+  fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+    let service = self.service.clone();
+
+    let is_protected = req.method() == http::Method::POST
+      && self.paths.iter().any(|path| path == req.path());
+
+    if !is_protected {
+      return Box::pin(service.borrow_mut().call(req));
+    }
+
+    Box::pin(async move {
+      let body = peek_body(&mut req).await?;
+      let handle = serde_urlencoded::from_bytes::<HandleField>(&body)
+        .ok()
+        .and_then(|field| field.handle)
+        .filter(|handle| !handle.is_empty());
+
+      let handle = match handle {
+        Some(handle) => Some(handle),
+        None => challenge_handle(&req)
+          .map_err(|err| {
+            println!("error when fetching the user of the two-factor challenge {}", err);
+
+            AppError::internal("error when checking failed attempts")
+          })?
+      };
+
+      let ip = req.peer_addr()
+        .map(|address| address.ip().to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+
+      let blocked_until = ip_or_handle_blocked_until(&ip, handle.as_deref())
+        .map_err(|err| {
+          println!("error when checking failed attempts {}", err);
+
+          AppError::internal("error when checking failed attempts")
+        })?;
+
+      if let Some(until) = blocked_until {
+        let error = AppError::TooManyRequests(
+          String::from("too many failed attempts, try again later"),
+          until - date::now()
+        );
+
+        return Ok(req.error_response(error));
+      }
+
+      let path = req.path().to_owned();
+      let user_agent = req.headers()
+        .get(http::header::USER_AGENT)
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or("unknown device")
+        .to_owned();
+
+      // the borrow must end before awaiting, other requests use the service
+      let future = service.borrow_mut().call(req);
+      let response = future.await?;
+      let status = response.status();
+
+      if status == http::StatusCode::UNAUTHORIZED {
+        FailedAttempt::new(&ip, handle.as_deref(), &path, &user_agent).insert()
+          .map_err(|err| println!("error when recording failed attempt {}", err))
+          .ok();
+      }
+      else if response.response().cookies().any(|cookie| cookie.name() == "token") {
+        // a correct password alone is not enough when the second factor is
+        // still to be checked, only a new session clears the failures
+        if let Some(handle) = &handle {
+          failed_attempt::remove_all_by_handle(handle)
+            .map_err(|err| println!("error when removing failed attempts {}", err))
+            .ok();
+        }
+      }
+
+      Ok(response)
+    })
+  }
+}
+
+/// The handle of the user the `two_factor` cookie of the request belongs to.
+fn challenge_handle(req: &ServiceRequest) -> rusqlite::Result<Option<String>> {
+  let challenge = match req.cookie("two_factor") {
+    Some(cookie) => TwoFactorChallenge::get_by_token(cookie.value())?,
+    None => None
+  };
+
+  match challenge {
+    Some(challenge) => Ok(User::get_by_id(challenge.user_id)?.map(|user| user.handle)),
+    None => Ok(None)
+  }
+}
+
+fn ip_or_handle_blocked_until(ip: &str, handle: Option<&str>) -> rusqlite::Result<Option<i64>> {
+  let ip_until = failed_attempt::ip_blocked_until(ip)?;
+  let handle_until = match handle {
+    Some(handle) => failed_attempt::handle_blocked_until(handle)?,
+    None => None
+  };
+
+  Ok(ip_until.max(handle_until))
+}
@@ -0,0 +1,136 @@
+use crate::errors::AppError;
+use crate::middlewares::peek_body;
+use crate::models::session::Session;
+use crate::utils::cookies;
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http, Error, HttpMessage};
+use futures::future::{ok, Ready};
+use ring::constant_time;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use uuid::Uuid;
+
+/// The token the forms of the request must send back in their `csrf_token`
+/// field, stored in the extensions of every request.
+#[derive(Clone)]
+pub struct CsrfToken(pub String);
+
+/// Refuses the POST requests whose `csrf_token` field does not match the
+/// token of the device. Signed in devices use the token of their session,
+/// the others get a random token in a `csrf` cookie so the sign in forms are
+/// protected as well.
+pub struct CsrfProtection;
+
+impl<S, B> Transform<S> for CsrfProtection
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = CsrfProtectionMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(CsrfProtectionMiddleware {
+      service: Rc::new(RefCell::new(service))
+    })
+  }
+}
+
+pub struct CsrfProtectionMiddleware<S> {
+  service: Rc<RefCell<S>>
+}
+
+/// The only field of the form the middleware cares about.
+#[derive(Deserialize)]
+struct CsrfField {
+  csrf_token: Option<String>
+}
+
+impl<S, B> Service for CsrfProtectionMiddleware<S>
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.borrow_mut().poll_ready(cx)
+  }
+
+  fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+    let service = self.service.clone();
+
+    Box::pin(async move {
+      let (expected_token, is_new_token) = device_token(&req)
+        .map_err(|err| {
+          println!("error when fetching csrf token {}", err);
+
+          AppError::internal("error when fetching csrf token")
+        })?;
+
+      if req.method() == http::Method::POST {
+        let body = peek_body(&mut req).await?;
+        let sent_token = serde_urlencoded::from_bytes::<CsrfField>(&body)
+          .ok()
+          .and_then(|field| field.csrf_token)
+          .unwrap_or_default();
+
+        let is_valid = !is_new_token && constant_time::verify_slices_are_equal(
+          sent_token.as_bytes(),
+          expected_token.as_bytes()
+        ).is_ok();
+
+        if !is_valid {
+          let error = AppError::forbidden("invalid csrf token, reload the page and try again");
+
+          return Ok(req.error_response(error));
+        }
+      }
+
+      req.extensions_mut().insert(CsrfToken(expected_token.clone()));
+
+      let future = service.borrow_mut().call(req);
+      let mut response = future.await?;
+
+      if is_new_token {
+        let cookie = cookies::build("csrf", expected_token).finish();
+
+        if let Ok(value) = http::HeaderValue::from_str(&cookie.to_string()) {
+          response.headers_mut().append(http::header::SET_COOKIE, value);
+        }
+      }
+
+      Ok(response)
+    })
+  }
+}
+
+/// Returns the token of the device and whether it was just generated.
+fn device_token(req: &ServiceRequest) -> rusqlite::Result<(String, bool)> {
+  if let Some(cookie) = req.cookie("token") {
+    if let Some(session) = Session::get_by_token(cookie.value())? {
+      if !session.is_expired() {
+        return Ok((session.csrf_token, false));
+      }
+    }
+  }
+
+  match req.cookie("csrf") {
+    Some(cookie) if !cookie.value().is_empty() => Ok((cookie.value().to_owned(), false)),
+    _ => Ok((Uuid::new_v4().to_string(), true))
+  }
+}
@@ -0,0 +1,94 @@
+use crate::errors::AppError;
+
+use actix_web::body::{Body, ResponseBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http, Error};
+use futures::future::{ok, Ready};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// Answers with a `{"error": "..."}` json body instead of the html error page
+/// when the request ended with an `AppError` and the client wants json: it
+/// asked for it in the `Accept` header, or it called the api without asking
+/// for html like the forms of the browsers do.
+pub struct JsonErrors;
+
+impl<S, B> Transform<S> for JsonErrors
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = JsonErrorsMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(JsonErrorsMiddleware {
+      service: Rc::new(RefCell::new(service))
+    })
+  }
+}
+
+pub struct JsonErrorsMiddleware<S> {
+  service: Rc<RefCell<S>>
+}
+
+impl<S, B> Service for JsonErrorsMiddleware<S>
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.borrow_mut().poll_ready(cx)
+  }
+
+  fn call(&mut self, req: ServiceRequest) -> Self::Future {
+    let wants_json = wants_json(&req);
+    let future = self.service.borrow_mut().call(req);
+
+    Box::pin(async move {
+      let response = future.await?;
+
+      if !wants_json {
+        return Ok(response);
+      }
+
+      let json = match response.response().error().and_then(|error| error.as_error::<AppError>()) {
+        Some(error) => error.to_json(),
+        None => return Ok(response)
+      };
+
+      Ok(response.map_body(|head, _body| {
+        head.headers.insert(
+          http::header::CONTENT_TYPE,
+          http::HeaderValue::from_static("application/json")
+        );
+
+        ResponseBody::Other(Body::from(json))
+      }))
+    })
+  }
+}
+
+fn wants_json(req: &ServiceRequest) -> bool {
+  let accept = req.headers()
+    .get(http::header::ACCEPT)
+    .and_then(|header| header.to_str().ok())
+    .unwrap_or("");
+
+  accept.contains("application/json")
+    || (req.path().starts_with("/api/") && !accept.contains("text/html"))
+}
@@ -0,0 +1,31 @@
+mod brute_force;
+mod csrf;
+mod json_errors;
+mod session_cookie;
+
+pub use brute_force::BruteForceProtection;
+pub use csrf::{CsrfProtection, CsrfToken};
+pub use json_errors::JsonErrors;
+pub use session_cookie::{RenewedSession, SessionCookie};
+
+use actix_web::dev::{Payload, ServiceRequest};
+use actix_web::{web, Error, HttpMessage};
+use futures::stream::{self, StreamExt};
+
+/// Reads the whole body of the request then gives it back, so the handler
+/// can still read it.
+async fn peek_body(req: &mut ServiceRequest) -> Result<web::Bytes, Error> {
+  let mut body = web::BytesMut::new();
+  let mut payload = req.take_payload();
+
+  while let Some(chunk) = payload.next().await {
+    body.extend_from_slice(&chunk?);
+  }
+
+  let body = body.freeze();
+  let given_back = body.clone();
+
+  req.set_payload(Payload::Stream(Box::pin(stream::once(async { Ok(given_back) }))));
+
+  Ok(body)
+}
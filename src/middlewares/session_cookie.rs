@@ -0,0 +1,82 @@
+use crate::utils::cookies;
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http, Error};
+use futures::future::{ok, Ready};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+/// The token of the session renewed by the request, stored in its extensions
+/// by `req_auth::get_session`.
+#[derive(Clone)]
+pub struct RenewedSession(pub String);
+
+/// Sends the `token` cookie again with a fresh max age when the request
+/// renewed its session, so the browser keeps the cookie as long as the
+/// session lives on the server.
+pub struct SessionCookie;
+
+impl<S, B> Transform<S> for SessionCookie
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = SessionCookieMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ok(SessionCookieMiddleware {
+      service: Rc::new(RefCell::new(service))
+    })
+  }
+}
+
+pub struct SessionCookieMiddleware<S> {
+  service: Rc<RefCell<S>>
+}
+
+impl<S, B> Service for SessionCookieMiddleware<S>
+where
+  S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static
+{
+  type Request = ServiceRequest;
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.service.borrow_mut().poll_ready(cx)
+  }
+
+  fn call(&mut self, req: ServiceRequest) -> Self::Future {
+    let future = self.service.borrow_mut().call(req);
+
+    Box::pin(async move {
+      let mut response = future.await?;
+      let renewed = response.request().extensions().get::<RenewedSession>().cloned();
+
+      // the handler may have set the cookie itself, when signing out
+      let sets_token = response.response().cookies().any(|cookie| cookie.name() == "token");
+
+      if let (Some(RenewedSession(token)), false) = (renewed, sets_token) {
+        let cookie = cookies::session(token);
+
+        if let Ok(value) = http::HeaderValue::from_str(&cookie.to_string()) {
+          response.headers_mut().append(http::header::SET_COOKIE, value);
+        }
+      }
+
+      Ok(response)
+    })
+  }
+}
\ No newline at end of file
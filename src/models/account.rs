@@ -0,0 +1,356 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
+use rusqlite::{params, Result};
+use serde::Serialize;
+
+/// The kinds of account, only used to tell them apart.
+pub const KINDS: [&str; 4] = ["checking", "savings", "cash", "credit card"];
+
+/// Where the money of the expenses and incomes actually is, checked against
+/// the balances of its statements.
+#[derive(Serialize, Clone)]
+pub struct Account {
+  pub id: i32,
+  pub name: String,
+
+  /// one of `KINDS`
+  pub kind: String,
+
+  /// the balance before any of the expenses and incomes of the account,
+  /// negative for a credit card that is owed money
+  pub opening_balance: i32,
+
+  /// the user who created the account
+  pub user_id: Option<i32>,
+  pub creation_date: i64
+}
+
+/// A balance read on a statement of the bank.
+#[derive(Serialize, Clone)]
+pub struct Statement {
+  pub id: i32,
+  pub account_id: i32,
+  pub date: i64,
+  pub balance: i32
+}
+
+/// An expense or an income assigned to an account.
+pub struct AccountEntry {
+  /// either `expense` or `income`
+  pub kind: String,
+  pub id: i32,
+  pub name: String,
+  pub date: i64,
+
+  /// negative for the expenses
+  pub amount: i32,
+  pub sheet_id: i32,
+  pub sheet_name: String,
+  pub cleared_date: Option<i64>
+}
+
+impl Audited for Account {
+  const ENTITY: &'static str = "account";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    None
+  }
+}
+
+impl Audited for Statement {
+  const ENTITY: &'static str = "statement";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    None
+  }
+}
+
+impl Account {
+  pub fn new(name: &str, kind: &str, opening_balance: i32) -> Account {
+    Account {
+      id: 0,
+      name: name.to_owned(),
+      kind: kind.to_owned(),
+      opening_balance,
+      user_id: None,
+      creation_date: date::now()
+    }
+  }
+
+  /// Inserts the account and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into accounts (
+        name,
+        kind,
+        opening_balance,
+        user_id,
+        creation_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5
+      )
+    ", params![self.name, self.kind, self.opening_balance, self.user_id, self.creation_date])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  pub fn update(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      update accounts
+      set name = ?1,
+          kind = ?2,
+          opening_balance = ?3
+      where id = ?4
+      ",
+      params![self.name, self.kind, self.opening_balance, self.id],
+    )?;
+
+    Ok(())
+  }
+
+  /// Deletes the account and its statements, its expenses and incomes are
+  /// left without one.
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from accounts
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Account>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, kind, opening_balance, user_id, creation_date
+      from accounts
+      where id = ?1
+    ")?;
+
+    let mut accounts = query.query_map(params![id], |row| {
+      Ok(
+        Account {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          kind: row.get(2)?,
+          opening_balance: row.get(3)?,
+          user_id: row.get(4)?,
+          creation_date: row.get(5)?
+        }
+      )
+    })?;
+
+    accounts.nth(0).transpose()
+  }
+
+  /// Every account, sorted by name.
+  pub fn get_all() -> Result<Vec<Account>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, kind, opening_balance, user_id, creation_date
+      from accounts
+      order by name
+    ")?;
+
+    let accounts = query.query_map(params![], |row| {
+      Ok(
+        Account {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          kind: row.get(2)?,
+          opening_balance: row.get(3)?,
+          user_id: row.get(4)?,
+          creation_date: row.get(5)?
+        }
+      )
+    })?;
+
+    accounts.collect()
+  }
+
+  /// The expenses and incomes of the account, the oldest first. Those in the
+  /// trash, or whose sheet is, are left out.
+  pub fn get_entries(&self) -> Result<Vec<AccountEntry>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select 'expense', expenses.id, expenses.name, date, -amount, sheets.id, sheets.name, cleared_date
+      from expenses
+      join sheets on sheets.id = sheet_id
+      where account_id = ?1 and expenses.deleted_date is null and sheets.deleted_date is null
+      union all
+      select 'income', incomes.id, incomes.name, date, amount, sheets.id, sheets.name, cleared_date
+      from incomes
+      join sheets on sheets.id = sheet_id
+      where account_id = ?1 and incomes.deleted_date is null and sheets.deleted_date is null
+      order by 4, 1, 2
+    ")?;
+
+    let entries = query.query_map(params![self.id], |row| {
+      Ok(
+        AccountEntry {
+          kind: row.get(0)?,
+          id: row.get(1)?,
+          name: row.get(2)?,
+          date: row.get(3)?,
+          amount: row.get(4)?,
+          sheet_id: row.get(5)?,
+          sheet_name: row.get(6)?,
+          cleared_date: row.get(7)?
+        }
+      )
+    })?;
+
+    entries.collect()
+  }
+
+  /// The opening balance plus the given entries dated up to `date`, all of
+  /// them or only those already cleared.
+  pub fn balance_at(&self, entries: &[AccountEntry], date: i64, cleared_only: bool) -> i32 {
+    let total: i32 = entries
+      .iter()
+      .filter(|entry| entry.date <= date && (!cleared_only || entry.cleared_date.is_some()))
+      .map(|entry| entry.amount)
+      .sum();
+
+    self.opening_balance + total
+  }
+}
+
+impl Statement {
+  pub fn new(account_id: i32, date: i64, balance: i32) -> Statement {
+    Statement {
+      id: 0,
+      account_id,
+      date,
+      balance
+    }
+  }
+
+  /// Inserts the statement and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into statements (
+        account_id,
+        date,
+        balance
+      )
+      values (
+        ?1,
+        ?2,
+        ?3
+      )
+    ", params![self.account_id, self.date, self.balance])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from statements
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Statement>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, account_id, date, balance
+      from statements
+      where id = ?1
+    ")?;
+
+    let mut statements = query.query_map(params![id], |row| {
+      Ok(
+        Statement {
+          id: row.get(0)?,
+          account_id: row.get(1)?,
+          date: row.get(2)?,
+          balance: row.get(3)?
+        }
+      )
+    })?;
+
+    statements.nth(0).transpose()
+  }
+
+  /// The statements of the account, the latest first.
+  pub fn get_all_by_account_id(account_id: i32) -> Result<Vec<Statement>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, account_id, date, balance
+      from statements
+      where account_id = ?1
+      order by date desc, id desc
+    ")?;
+
+    let statements = query.query_map(params![account_id], |row| {
+      Ok(
+        Statement {
+          id: row.get(0)?,
+          account_id: row.get(1)?,
+          date: row.get(2)?,
+          balance: row.get(3)?
+        }
+      )
+    })?;
+
+    statements.collect()
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("accounts", "
+    id integer primary key autoincrement,
+    name text not null,
+    kind text not null,
+    opening_balance integer not null,
+    user_id integer references users (id) on delete set null,
+    creation_date integer not null
+  ")?;
+
+  create_table_with_foreign_keys("statements", "
+    id integer primary key autoincrement,
+    account_id integer not null references accounts (id) on delete cascade,
+    date integer not null,
+    balance integer not null
+  ")
+}
\ No newline at end of file
@@ -0,0 +1,263 @@
+use crate::models::open_connection;
+use crate::models::user::User;
+use crate::utils::date::{self, format_date, format_datetime};
+use rusqlite::{params, Result};
+use maud::{Render, html, Markup};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The handle recorded for the changes made by the command line or the
+/// maintenance tasks.
+pub const SYSTEM_ACTOR: &str = "calco";
+
+/// The kinds of rows whose changes are recorded in the audit log.
+pub trait Audited: Serialize {
+  /// the name of the kind in the log, e.g. `sheet`
+  const ENTITY: &'static str;
+
+  fn audit_id(&self) -> i32;
+
+  /// the sheet the row belongs to, so it shows in the history of the sheet
+  fn audit_sheet_id(&self) -> Option<i32>;
+}
+
+/// A change made to an `Audited` row. The entries are only ever inserted,
+/// they keep the values before and after the change as json along with the
+/// handle of the user who made it, so they survive the deletion of both.
+#[allow(dead_code)]
+pub struct AuditEntry {
+  pub id: i32,
+  pub actor_id: Option<i32>,
+  pub actor_handle: String,
+
+  /// either `create`, `update`, `delete`, `restore` or one of the actions
+  /// that change nothing the log keeps, like `password_changed`
+  pub action: String,
+  pub entity: String,
+  pub entity_id: i32,
+  pub sheet_id: Option<i32>,
+  pub before: Option<String>,
+  pub after: Option<String>,
+  pub date: i64
+}
+
+/// The filters of the history pages, every field is optional.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AuditFilter {
+  pub entity: Option<String>,
+  pub action: Option<String>,
+  pub actor: Option<String>
+}
+
+impl Render for AuditEntry {
+  fn render(&self) -> Markup {
+    html! {
+      span class="date" { (format_datetime(self.date)) }
+      span class="handle" { (self.actor_handle) }
+      span class="action" { (self.action) " " (self.entity) " #" (self.entity_id) }
+
+      ul class="changes" {
+        @for (field, before, after) in self.changes() {
+          li {
+            span class="field" { (field) }
+            @match (before, after) {
+              (Some(before), Some(after)) => { (before) " → " (after) },
+              (Some(before), None) => { (before) },
+              (None, Some(after)) => { (after) },
+              (None, None) => {}
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+impl AuditEntry {
+  /// The fields that differ between the values before and after the change,
+  /// every field is listed for creations and deletions.
+  pub fn changes(&self) -> Vec<(String, Option<String>, Option<String>)> {
+    let before = parse_values(&self.before);
+    let after = parse_values(&self.after);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+      .into_iter()
+      .map(|field| (
+        field,
+        before.get(field).map(|value| format_value(field, value)),
+        after.get(field).map(|value| format_value(field, value))
+      ))
+      .filter(|(_, before, after)| before != after)
+      .map(|(field, before, after)| (field.clone(), before, after))
+      .collect()
+  }
+
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into audit_entries (
+        actor_id, actor_handle, action, entity, entity_id, sheet_id, before, after, date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6,
+        ?7,
+        ?8,
+        ?9
+      )
+    ", params![
+      self.actor_id,
+      self.actor_handle,
+      self.action,
+      self.entity,
+      self.entity_id,
+      self.sheet_id,
+      self.before,
+      self.after,
+      self.date
+    ])?;
+
+    Ok(())
+  }
+
+  /// The latest entries matching the filter, limited to the history of a
+  /// sheet when `sheet_id` is given.
+  pub fn get_latest(sheet_id: Option<i32>, filter: &AuditFilter, limit: i64) -> Result<Vec<AuditEntry>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, actor_id, actor_handle, action, entity, entity_id, sheet_id, before, after, date
+      from audit_entries
+      where (?1 is null or sheet_id = ?1)
+        and (?2 is null or entity = ?2)
+        and (?3 is null or action = ?3)
+        and (?4 is null or actor_handle = ?4)
+      order by id desc
+      limit ?5
+    ")?;
+
+    let entries = query.query_map(params![
+      sheet_id,
+      non_empty(&filter.entity),
+      non_empty(&filter.action),
+      non_empty(&filter.actor),
+      limit
+    ], |row| {
+      Ok(AuditEntry {
+        id: row.get(0)?,
+        actor_id: row.get(1)?,
+        actor_handle: row.get(2)?,
+        action: row.get(3)?,
+        entity: row.get(4)?,
+        entity_id: row.get(5)?,
+        sheet_id: row.get(6)?,
+        before: row.get(7)?,
+        after: row.get(8)?,
+        date: row.get(9)?
+      })
+    })?;
+
+    entries.collect()
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    create table if not exists audit_entries (
+      id integer primary key autoincrement,
+      actor_id integer,
+      actor_handle text not null,
+      action text not null,
+      entity text not null,
+      entity_id integer not null,
+      sheet_id integer,
+      before text,
+      after text,
+      date integer not null
+    )
+  ", params![])?;
+
+  conn.execute("
+    create index if not exists audit_entries_sheet_id on audit_entries (sheet_id)
+  ", params![])?;
+
+  Ok(())
+}
+
+/// Records a change made by `actor`, or by calco itself when there is none.
+/// `before` is `None` for creations and `after` is `None` for deletions.
+pub fn record<T: Audited>(actor: Option<&User>, before: Option<&T>, after: Option<&T>) -> Result<()> {
+  let action = match (before, after) {
+    (None, Some(_)) => "create",
+    (Some(_), None) => "delete",
+    (Some(_), Some(_)) => "update",
+    (None, None) => return Ok(())
+  };
+
+  insert_entry(actor, action, before, after)
+}
+
+/// Records that `row` was taken out of the trash by `actor`.
+pub fn record_restore<T: Audited>(actor: Option<&User>, row: &T) -> Result<()> {
+  insert_entry(actor, "restore", None, Some(row))
+}
+
+/// Records an action on `row` whose changes are not kept in the log, like a
+/// new password: the row is logged as it is after the action.
+pub fn record_action<T: Audited>(actor: Option<&User>, action: &str, row: &T) -> Result<()> {
+  insert_entry(actor, action, None, Some(row))
+}
+
+fn insert_entry<T: Audited>(actor: Option<&User>, action: &str, before: Option<&T>, after: Option<&T>) -> Result<()> {
+  let row = match after.or(before) {
+    Some(row) => row,
+    None => return Ok(())
+  };
+
+  AuditEntry {
+    id: 0,
+    actor_id: actor.map(|user| user.id),
+    actor_handle: actor.map(|user| user.handle.clone()).unwrap_or_else(|| String::from(SYSTEM_ACTOR)),
+    action: action.to_owned(),
+    entity: T::ENTITY.to_owned(),
+    entity_id: row.audit_id(),
+    sheet_id: row.audit_sheet_id(),
+    before: before.and_then(|row| serde_json::to_string(row).ok()),
+    after: after.and_then(|row| serde_json::to_string(row).ok()),
+    date: date::now()
+  }
+  .insert()
+}
+
+fn parse_values(json: &Option<String>) -> Map<String, Value> {
+  match json.as_ref().map(|json| serde_json::from_str(json)) {
+    Some(Ok(Value::Object(values))) => values,
+    _ => Map::new()
+  }
+}
+
+/// The timestamps are shown as dates, the other values as they are.
+fn format_value(field: &str, value: &Value) -> String {
+  match value {
+    Value::Number(number) if field == "date" => number.as_i64().map(format_date).unwrap_or_default(),
+    Value::Number(number) if field.ends_with("_date") => number.as_i64().map(format_datetime).unwrap_or_default(),
+    Value::String(text) => text.clone(),
+    Value::Null => String::from("none"),
+    other => other.to_string()
+  }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+  value.as_deref().filter(|value| !value.is_empty())
+}
@@ -1,5 +1,5 @@
-use crate::constants::DATABASE_PATH;
-use rusqlite::{params, Connection, Result};
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use rusqlite::{params, Result};
 
 #[allow(dead_code)]
 pub struct CachedSheetValue {
@@ -18,7 +18,7 @@ impl CachedSheetValue {
 
   #[allow(dead_code)]
   pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       insert into inherited_sheets (
@@ -35,7 +35,7 @@ impl CachedSheetValue {
 
   #[allow(dead_code)]
   pub fn update(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       update cached_sheet_values
@@ -50,7 +50,7 @@ impl CachedSheetValue {
 
   #[allow(dead_code)]
   pub fn remove(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       delete from cached_sheet_values
@@ -64,7 +64,7 @@ impl CachedSheetValue {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<CachedSheetValue>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
       select sheet_id, value
@@ -85,7 +85,7 @@ impl CachedSheetValue {
 
   #[allow(dead_code)]
   pub fn get_by_sheet_id(sheet_id: i32) -> Result<Option<CachedSheetValue>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
       select sheet_id, value
@@ -107,19 +107,15 @@ impl CachedSheetValue {
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
-
-  conn.execute("
-    create table if not exists cached_sheet_values (
-      sheet_id integer not null,
-      value integer not null
-    )
-  ", params![])
-  .map(|_n| ())
+  create_table_with_foreign_keys("cached_sheet_values", "
+    sheet_id integer not null references sheets (id) on delete cascade,
+    value integer not null
+  ")
 }
 
+#[allow(dead_code)]
 pub fn remove_by_sheet_id(sheet_id: i32) -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+  let conn = open_connection()?;
 
   conn.execute("
     delete from cached_sheet_values
@@ -1,13 +1,31 @@
-use crate::constants::DATABASE_PATH;
+use crate::models::{open_connection, add_column_if_missing, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
 use rusqlite::{params, Connection, Result};
+use serde::Serialize;
 
 // Represents a source of expense in a `Sheet`
 //
 //
+#[derive(Serialize, Clone)]
 pub struct Category {
   pub id: i32,
   pub name: String,
-  pub sheet_id: i32
+  pub sheet_id: i32,
+
+  /// the amount planned for the expenses of the category, if any
+  pub target: Option<i32>
+}
+
+impl Audited for Category {
+  const ENTITY: &'static str = "category";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
 }
 
 impl Category {
@@ -16,33 +34,98 @@ impl Category {
     Category {
       id: 0,
       name: name.to_owned(),
-      sheet_id
+      sheet_id,
+      target: None
     }
   }
 
+  /// Inserts the category and sets its id.
   #[allow(dead_code)]
-  pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
 
+    self.insert_in(&conn)
+  }
+
+  /// Same as `insert` on a connection of the caller, for a category written
+  /// along with other rows.
+  pub fn insert_in(&mut self, conn: &Connection) -> Result<()> {
     conn.execute("
       insert into categories (
         name,
-        sheet_id
+        sheet_id,
+        target
       )
       values (
         ?1,
         ?2,
+        ?3
       )
-    ", params![self.name, self.sheet_id])
-    .map(|_n| ())
+    ", params![self.name, self.sheet_id, self.target])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  pub fn update(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      update categories
+      set name = ?1,
+          target = ?2
+      where id = ?3
+      ",
+      params![self.name, self.target, self.id],
+    )?;
+
+    Ok(())
+  }
+
+  /// Deletes the category, its expenses are left without one.
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from categories
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Category>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, sheet_id, target
+      from categories
+      where id = ?1
+    ")?;
+
+    let mut categories = query.query_map(params![id], |row| {
+      Ok(
+        Category {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          sheet_id: row.get(2)?,
+          target: row.get(3)?
+        }
+      )
+    })?;
+
+    categories.nth(0).transpose()
   }
 
   #[allow(dead_code)]
   pub fn get_by_name(key: &str) -> Result<Option<Category>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, sheet_id
+      select id, name, sheet_id, target
       from categories
       where name = ?1
     ")?;
@@ -52,7 +135,8 @@ impl Category {
         Category {
           id: row.get(0)?,
           name: row.get(1)?,
-          sheet_id: row.get(2)?
+          sheet_id: row.get(2)?,
+          target: row.get(3)?
         }
       )
     })?;
@@ -62,10 +146,10 @@ impl Category {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<Category>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, sheet_id
+      select id, name, sheet_id, target
       from categories
     ")?;
 
@@ -74,7 +158,8 @@ impl Category {
         Category {
           id: row.get(0)?,
           name: row.get(1)?,
-          sheet_id: row.get(2)?
+          sheet_id: row.get(2)?,
+          target: row.get(3)?
         }
       )
     })?;
@@ -82,14 +167,15 @@ impl Category {
     categories.collect()
   }
 
-  #[allow(dead_code)]
+  /// The categories of the sheet, sorted by name.
   pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Category>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, sheet_id
+      select id, name, sheet_id, target
       from categories
       where sheet_id = ?
+      order by name
     ")?;
 
     let categories = query.query_map(params![sheet_id], |row| {
@@ -97,24 +183,52 @@ impl Category {
         Category {
           id: row.get(0)?,
           name: row.get(1)?,
-          sheet_id
+          sheet_id,
+          target: row.get(3)?
         }
       )
     })?;
 
     categories.collect()
   }
+
+  /// The categories of the sheet with the sum of their expenses, sorted by
+  /// name. The expenses in the trash are not counted.
+  pub fn get_all_with_spent_by_sheet_id(sheet_id: i32) -> Result<Vec<(Category, i32)>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select categories.id, categories.name, categories.target, coalesce(sum(expenses.amount), 0)
+      from categories
+      left join expenses on expenses.category_id = categories.id and expenses.deleted_date is null
+      where categories.sheet_id = ?1
+      group by categories.id
+      order by categories.name
+    ")?;
+
+    let categories = query.query_map(params![sheet_id], |row| {
+      Ok((
+        Category {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          sheet_id,
+          target: row.get(2)?
+        },
+        row.get(3)?
+      ))
+    })?;
+
+    categories.collect()
+  }
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
-
-  conn.execute("
-    create table if not exists categories (
-      id integer primary key autoincrement,
-      name text not null,
-      sheet_id integer not null
-    )
-  ", params![])
-  .map(|_n| ())
+  create_table_with_foreign_keys("categories", "
+    id integer primary key autoincrement,
+    name text not null,
+    sheet_id integer not null references sheets (id) on delete cascade,
+    target integer
+  ")?;
+
+  add_column_if_missing("categories", "target", "integer")
 }
\ No newline at end of file
@@ -1,16 +1,48 @@
-use crate::constants::DATABASE_PATH;
+use crate::models::{open_connection, add_column_if_missing, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
 use rusqlite::{params, Connection, Result};
+use serde::Serialize;
 
 // Represents a source of expense in a `Sheet`
 //
 //
 #[allow(dead_code)]
+#[derive(Serialize, Clone)]
 pub struct Expense {
   pub id: i32,
   pub name: String,
   pub amount: i32,
   pub date: i64,
-  pub sheet_id: i32
+  pub sheet_id: i32,
+
+  /// when the expense was moved to the trash
+  #[serde(skip)]
+  pub deleted_date: Option<i64>,
+
+  /// the category of the sheet the expense counts against, if any
+  pub category_id: Option<i32>,
+
+  /// the goal the expense contributes to, if any
+  pub goal_id: Option<i32>,
+
+  /// the account the money comes from or goes to, if any
+  pub account_id: Option<i32>,
+
+  /// when the expense was matched against a statement of its account
+  pub cleared_date: Option<i64>
+}
+
+impl Audited for Expense {
+  const ENTITY: &'static str = "expense";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
 }
 
 impl Expense {
@@ -21,37 +53,78 @@ impl Expense {
       name: name.to_owned(),
       amount,
       date,
-      sheet_id
+      sheet_id,
+      deleted_date: None,
+      category_id: None,
+      goal_id: None,
+      account_id: None,
+      cleared_date: None
     }
   }
 
+  /// Inserts the expense and sets its id.
   #[allow(dead_code)]
-  pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    self.insert_in(&conn)
+  }
 
+  /// Same as `insert` on a connection of the caller, so the expense can be
+  /// part of a larger transaction.
+  pub fn insert_in(&mut self, conn: &Connection) -> Result<()> {
     conn.execute("
       insert into expenses (
         name,
         amount,
         date,
-        sheet_id
+        sheet_id,
+        category_id,
+        goal_id,
+        account_id,
+        cleared_date
       )
       values (
         ?1,
         ?2,
         ?3,
-        ?4
+        ?4,
+        ?5,
+        ?6,
+        ?7,
+        ?8
       )
-    ", params![self.name, self.amount, self.date, self.sheet_id])
-    .map(|_n| ())
+    ", params![self.name, self.amount, self.date, self.sheet_id, self.category_id, self.goal_id, self.account_id, self.cleared_date])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
   }
 
+  /// Moves the expense to the trash.
   #[allow(dead_code)]
   pub fn remove(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
+
+    conn.execute("
+      update expenses
+      set deleted_date = ?1
+      where id = ?2
+      ",
+      params![date::now(), self.id],
+    )?;
+
+    Ok(())
+  }
+
+  /// Takes the expense out of the trash, the cached value of its sheet is left
+  /// to the caller.
+  pub fn restore(&self) -> Result<()> {
+    let conn = open_connection()?;
 
     conn.execute("
-      delete from expenses
+      update expenses
+      set deleted_date = null
       where id = ?1
       ",
       params![self.id],
@@ -60,14 +133,77 @@ impl Expense {
     Ok(())
   }
 
+  /// Returns the expense only if it is in the trash.
+  pub fn get_deleted_by_id(id: i32) -> Result<Option<Expense>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, amount, date, sheet_id, deleted_date, category_id, goal_id, account_id, cleared_date
+      from expenses
+      where id = ?1 and deleted_date is not null
+    ")?;
+
+    let mut expenses = query.query_map(params![id], |row| {
+      Ok(
+        Expense {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
+        }
+      )
+    })?;
+
+    expenses.nth(0).transpose()
+  }
+
+  /// The expenses in the trash, the latest deleted first. Those of the sheets in
+  /// the trash are left out, they come back with their sheet.
+  pub fn get_all_deleted() -> Result<Vec<Expense>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select expenses.id, expenses.name, amount, date, sheet_id, expenses.deleted_date, category_id, goal_id, account_id, cleared_date
+      from expenses
+      join sheets on sheets.id = sheet_id
+      where expenses.deleted_date is not null and sheets.deleted_date is null
+      order by expenses.deleted_date desc
+    ")?;
+
+    let expenses = query.query_map(params![], |row| {
+      Ok(
+        Expense {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
+        }
+      )
+    })?;
+
+    expenses.collect()
+  }
+
   #[allow(dead_code)]
   pub fn get_by_name(key: &str) -> Result<Option<Expense>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, category_id, goal_id, account_id, cleared_date
       from expenses
-      where name = ?1
+      where name = ?1 and deleted_date is null
     ")?;
 
     let mut expenses = query.query_map(params![key], |row| {
@@ -77,7 +213,12 @@ impl Expense {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id: row.get(4)?
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
         }
       )
     })?;
@@ -87,12 +228,12 @@ impl Expense {
 
   #[allow(dead_code)]
   pub fn get_by_id(id: i32) -> Result<Option<Expense>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, category_id, goal_id, account_id, cleared_date
       from expenses
-      where id = ?1
+      where id = ?1 and deleted_date is null
     ")?;
 
     let mut expenses = query.query_map(params![id], |row| {
@@ -102,7 +243,12 @@ impl Expense {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id: row.get(4)?
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
         }
       )
     })?;
@@ -112,11 +258,12 @@ impl Expense {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<Expense>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, category_id, goal_id, account_id, cleared_date
       from expenses
+      where deleted_date is null
     ")?;
 
     let expenses = query.query_map(params![], |row| {
@@ -126,7 +273,12 @@ impl Expense {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id: row.get(4)?
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
         }
       )
     })?;
@@ -136,12 +288,12 @@ impl Expense {
 
   #[allow(dead_code)]
   pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Expense>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, category_id, goal_id, account_id, cleared_date
       from expenses
-      where sheet_id = ?1
+      where sheet_id = ?1 and deleted_date is null
     ")?;
 
     let expenses = query.query_map(params![sheet_id], |row| {
@@ -151,7 +303,12 @@ impl Expense {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id
+          sheet_id,
+          deleted_date: row.get(5)?,
+          category_id: row.get(6)?,
+          goal_id: row.get(7)?,
+          account_id: row.get(8)?,
+          cleared_date: row.get(9)?
         }
       )
     })?;
@@ -160,16 +317,20 @@ impl Expense {
   }
 
   pub fn update(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       update expenses
       set name = ?1,
           amount = ?2,
-          date = ?3
-      where id = ?4
+          date = ?3,
+          category_id = ?4,
+          goal_id = ?5,
+          account_id = ?6,
+          cleared_date = ?7
+      where id = ?8
       ",
-      params![self.name, self.amount, self.date, self.id],
+      params![self.name, self.amount, self.date, self.category_id, self.goal_id, self.account_id, self.cleared_date, self.id],
     )?;
 
     Ok(())
@@ -177,16 +338,33 @@ impl Expense {
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+  create_table_with_foreign_keys("expenses", "
+    id integer primary key autoincrement,
+    name text not null,
+    amount integer not null,
+    date datetime not null,
+    sheet_id integer not null references sheets (id) on delete cascade,
+    deleted_date integer,
+    category_id integer references categories (id) on delete set null,
+    goal_id integer references goals (id) on delete set null,
+    account_id integer references accounts (id) on delete set null,
+    cleared_date integer
+  ")?;
+
+  add_column_if_missing("expenses", "category_id", "integer references categories (id) on delete set null")?;
+  add_column_if_missing("expenses", "goal_id", "integer references goals (id) on delete set null")?;
+  add_column_if_missing("expenses", "account_id", "integer references accounts (id) on delete set null")?;
+  add_column_if_missing("expenses", "cleared_date", "integer")
+}
+
+/// Deletes for good the expenses moved to the trash before `deleted_date`.
+pub fn purge_deleted_before(deleted_date: i64) -> Result<usize> {
+  let conn = open_connection()?;
 
   conn.execute("
-    create table if not exists expenses (
-      id integer primary key autoincrement,
-      name text not null,
-      amount integer not null,
-      date datetime not null,
-      sheet_id integer not null
-    )
-  ", params![])
-  .map(|_n| ())
+    delete from expenses
+    where deleted_date < ?1
+    ",
+    params![deleted_date],
+  )
 }
\ No newline at end of file
@@ -0,0 +1,191 @@
+use crate::constants;
+use crate::models::open_connection;
+use crate::utils::date::{self, format_datetime};
+use rusqlite::{params, Result};
+use maud::{Render, html, Markup};
+
+/// A request to a protected endpoint that was answered with a 401, see
+/// `middlewares::BruteForceProtection`.
+pub struct FailedAttempt {
+  pub ip: String,
+  pub handle: Option<String>,
+  pub path: String,
+  pub user_agent: String,
+  pub date: i64
+}
+
+impl Render for FailedAttempt {
+  fn render(&self) -> Markup {
+    html! {
+      span class="date" { (format_datetime(self.date)) }
+      span class="path" { (self.path) }
+      span class="ip" { (self.ip) }
+
+      @if let Some(handle) = &self.handle {
+        span class="handle" { (handle) }
+      }
+
+      span class="user-agent" { (self.user_agent) }
+    }
+  }
+}
+
+impl FailedAttempt {
+  pub fn new(ip: &str, handle: Option<&str>, path: &str, user_agent: &str) -> FailedAttempt {
+    FailedAttempt {
+      ip: ip.to_owned(),
+      handle: handle.map(|handle| handle.to_owned()),
+      path: path.to_owned(),
+      user_agent: user_agent.to_owned(),
+      date: date::now()
+    }
+  }
+
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into failed_attempts (
+        ip, handle, path, user_agent, date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5
+      )
+    ", params![self.ip, self.handle, self.path, self.user_agent, self.date])?;
+
+    Ok(())
+  }
+
+  pub fn get_latest(limit: i64) -> Result<Vec<FailedAttempt>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select ip, handle, path, user_agent, date
+      from failed_attempts
+      order by date desc
+      limit ?1
+    ")?;
+
+    let attempts = query.query_map(params![limit], |row| {
+      Ok(FailedAttempt {
+        ip: row.get(0)?,
+        handle: row.get(1)?,
+        path: row.get(2)?,
+        user_agent: row.get(3)?,
+        date: row.get(4)?
+      })
+    })?;
+
+    attempts.collect()
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    create table if not exists failed_attempts (
+      id integer primary key autoincrement,
+      ip text not null,
+      handle text,
+      path text not null,
+      user_agent text not null,
+      date datetime not null
+    )
+  ", params![])?;
+
+  Ok(())
+}
+
+/// Returns until when requests from the ip are refused, `None` when they
+/// are not.
+pub fn ip_blocked_until(ip: &str) -> Result<Option<i64>> {
+  let conn = open_connection()?;
+
+  let (count, last_date): (i64, Option<i64>) = conn.query_row("
+    select count(*), max(date)
+    from failed_attempts
+    where ip = ?1
+      and date > ?2
+  ", params![ip, date::now() - constants::BRUTE_FORCE_WINDOW_SECONDS], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+  Ok(blocked_until(last_date, backoff_delay(count)))
+}
+
+/// Returns until when sign in attempts for the handle are refused, either
+/// because of the backoff or because the account is locked out.
+pub fn handle_blocked_until(handle: &str) -> Result<Option<i64>> {
+  let conn = open_connection()?;
+
+  let (count, last_date): (i64, Option<i64>) = conn.query_row("
+    select count(*), max(date)
+    from failed_attempts
+    where handle = ?1
+      and date > ?2
+  ", params![handle, date::now() - constants::BRUTE_FORCE_WINDOW_SECONDS], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+  let delay = if count >= constants::ACCOUNT_LOCKOUT_ATTEMPTS {
+    constants::ACCOUNT_LOCKOUT_DURATION_SECONDS
+  } else {
+    backoff_delay(count)
+  };
+
+  Ok(blocked_until(last_date, delay))
+}
+
+/// Forgets the failures of the handle, after a successful sign in or when an
+/// administrator unlocks the account.
+pub fn remove_all_by_handle(handle: &str) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from failed_attempts
+    where handle = ?1
+  ", params![handle])?;
+
+  Ok(())
+}
+
+pub fn remove_all_by_ip(ip: &str) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from failed_attempts
+    where ip = ?1
+  ", params![ip])?;
+
+  Ok(())
+}
+
+/// Removes the failures older than `constants::FAILED_ATTEMPTS_RETENTION_SECONDS`.
+pub fn remove_old_failed_attempts() -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from failed_attempts
+    where date < ?1
+  ", params![date::now() - constants::FAILED_ATTEMPTS_RETENTION_SECONDS])
+}
+
+/// The first `constants::BRUTE_FORCE_FREE_ATTEMPTS` failures cost nothing,
+/// then the delay doubles with every failure.
+fn backoff_delay(count: i64) -> i64 {
+  if count < constants::BRUTE_FORCE_FREE_ATTEMPTS {
+    return 0;
+  }
+
+  let exponent = (count - constants::BRUTE_FORCE_FREE_ATTEMPTS).min(32) as u32;
+
+  (constants::BRUTE_FORCE_BASE_DELAY_SECONDS << exponent)
+    .min(constants::BRUTE_FORCE_MAX_DELAY_SECONDS)
+}
+
+fn blocked_until(last_date: Option<i64>, delay: i64) -> Option<i64> {
+  last_date
+    .map(|last_date| last_date + delay)
+    .filter(|until| *until > date::now())
+}
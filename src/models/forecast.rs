@@ -0,0 +1,215 @@
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::recurring_entry::RecurringEntry;
+use crate::models::sheet::Sheet;
+use crate::utils::date;
+use rusqlite::Result;
+use std::collections::HashMap;
+
+const DAY_SECONDS: i64 = 24 * 3600;
+
+/// The past rows by kind and lowercased name, with the name of the latest
+/// one and the date and signed amount of each.
+type Series<'a> = HashMap<(&'a str, String), (String, Vec<(i64, i32)>)>;
+
+/// Where the amount of a forecast entry comes from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EntrySource {
+  /// an expense or an income of the sheet dated in the future
+  Planned,
+  Recurring,
+
+  /// the average interval and amount of past rows of the same name
+  Average
+}
+
+impl EntrySource {
+  pub fn name(self) -> &'static str {
+    match self {
+      EntrySource::Planned => "planned",
+      EntrySource::Recurring => "recurring",
+      EntrySource::Average => "average"
+    }
+  }
+}
+
+/// An amount expected to change the balance on a day of the forecast.
+pub struct ForecastEntry {
+  pub date: i64,
+  pub name: String,
+
+  /// negative for the expenses
+  pub amount: i32,
+  pub source: EntrySource
+}
+
+/// The balance of a sheet projected day by day, starting today.
+pub struct Forecast {
+  pub start_date: i64,
+
+  /// one balance per day, the first is today's, held at the bounds of `i32`
+  /// when it goes past them
+  pub balances: Vec<i32>,
+
+  /// the entries after today, the earliest first
+  pub entries: Vec<ForecastEntry>
+}
+
+impl Forecast {
+  /// Projects the balance of the sheet over the `days` following `now`.
+  ///
+  /// The balance starts at the value of the sheet without its rows dated
+  /// after today, those are added back on their day. The recurring entries
+  /// of the sheet are added on each occurrence. The past rows of the sheet
+  /// and of the sheets it imports are grouped by kind and name, and those
+  /// seen at least twice are expected to come back at their average interval
+  /// with their average amount, unless a recurring entry of the same name
+  /// already covers them.
+  pub fn for_sheet(sheet: &Sheet, days: i64, now: i64) -> Result<Forecast> {
+    let start_date = date::start_of_day(now);
+    let end_date = start_date + days * DAY_SECONDS;
+
+    let recurring_entries = RecurringEntry::get_all_by_sheet_id(sheet.id)?;
+
+    let mut rows = Vec::new();
+    let mut imported_rows = Vec::new();
+
+    for expense in Expense::get_all_by_sheet_id(sheet.id)? {
+      rows.push(("expense", expense.name, -expense.amount, expense.date));
+    }
+
+    for income in Income::get_all_by_sheet_id(sheet.id)? {
+      rows.push(("income", income.name, income.amount, income.date));
+    }
+
+    for imported_sheet in Sheet::get_all_sheets_by_parent_sheet_id(sheet.id)? {
+      for expense in Expense::get_all_by_sheet_id(imported_sheet.id)? {
+        imported_rows.push(("expense", expense.name, -expense.amount, expense.date));
+      }
+
+      for income in Income::get_all_by_sheet_id(imported_sheet.id)? {
+        imported_rows.push(("income", income.name, income.amount, income.date));
+      }
+    }
+
+    // summed in 64 bits, a year of large daily amounts does not fit in 32
+    let mut balance = sheet.cached_value as i64;
+    let mut entries = Vec::new();
+    let mut series = Series::new();
+
+    for (kind, name, amount, row_date) in rows {
+      if row_date > start_date {
+        balance -= amount as i64;
+
+        if row_date <= end_date {
+          entries.push(ForecastEntry { date: row_date, name, amount, source: EntrySource::Planned });
+        }
+      }
+      else {
+        add_to_series(&mut series, kind, name, row_date, amount);
+      }
+    }
+
+    for (kind, name, amount, row_date) in imported_rows {
+      if row_date <= start_date {
+        add_to_series(&mut series, kind, name, row_date, amount);
+      }
+    }
+
+    for recurring_entry in &recurring_entries {
+      series.remove(&(recurring_entry.kind.as_str(), recurring_entry.name.to_lowercase()));
+
+      for occurrence in recurring_entry.occurrences_between(start_date + DAY_SECONDS, end_date) {
+        entries.push(ForecastEntry {
+          date: occurrence,
+          name: recurring_entry.name.clone(),
+          amount: recurring_entry.signed_amount(),
+          source: EntrySource::Recurring
+        });
+      }
+    }
+
+    for (name, mut occurrences) in series.into_values() {
+      if occurrences.len() < 2 {
+        continue;
+      }
+
+      occurrences.sort_by_key(|(occurrence, _)| *occurrence);
+
+      let count = occurrences.len() as i64;
+      let first = occurrences[0].0;
+      let last = occurrences[occurrences.len() - 1].0;
+      let total: i64 = occurrences.iter().map(|(_, amount)| *amount as i64).sum();
+
+      // rounded to whole days, rows of the same day do not make a series
+      let interval = ((last - first) / (count - 1) + DAY_SECONDS / 2) / DAY_SECONDS * DAY_SECONDS;
+      let amount = (total as f64 / count as f64).round() as i32;
+
+      if interval == 0 || amount == 0 {
+        continue;
+      }
+
+      let mut occurrence = last + interval;
+
+      while occurrence <= end_date {
+        if occurrence > start_date {
+          entries.push(ForecastEntry { date: occurrence, name: name.clone(), amount, source: EntrySource::Average });
+        }
+
+        occurrence += interval;
+      }
+    }
+
+    entries.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+
+    let mut balances = Vec::with_capacity(days as usize + 1);
+    let mut entries_iter = entries.iter().peekable();
+
+    for day in 0..=days {
+      let day_end = start_date + (day + 1) * DAY_SECONDS;
+
+      while let Some(entry) = entries_iter.peek() {
+        if entry.date >= day_end {
+          break;
+        }
+
+        balance += entry.amount as i64;
+        entries_iter.next();
+      }
+
+      balances.push(balance.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+    }
+
+    Ok(Forecast { start_date, balances, entries })
+  }
+
+  pub fn end_date(&self) -> i64 {
+    self.start_date + (self.balances.len() as i64 - 1) * DAY_SECONDS
+  }
+
+  /// The first day the balance is projected below zero, with that balance.
+  pub fn first_negative(&self) -> Option<(i64, i32)> {
+    self.balances
+      .iter()
+      .position(|balance| *balance < 0)
+      .map(|day| (self.start_date + day as i64 * DAY_SECONDS, self.balances[day]))
+  }
+
+  pub fn lowest(&self) -> i32 {
+    self.balances.iter().copied().min().unwrap_or(0)
+  }
+
+  pub fn highest(&self) -> i32 {
+    self.balances.iter().copied().max().unwrap_or(0)
+  }
+}
+
+fn add_to_series<'a>(series: &mut Series<'a>, kind: &'a str, name: String, row_date: i64, amount: i32) {
+  let (series_name, occurrences) = series.entry((kind, name.to_lowercase())).or_default();
+
+  if occurrences.iter().all(|(occurrence, _)| *occurrence <= row_date) {
+    *series_name = name;
+  }
+
+  occurrences.push((row_date, amount));
+}
\ No newline at end of file
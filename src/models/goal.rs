@@ -0,0 +1,320 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
+use rusqlite::{params, Result, ToSql};
+use serde::Serialize;
+
+/// Average length of a month in seconds, to count the months between two
+/// dates.
+const MONTH_SECONDS: f64 = 30.44 * 24.0 * 3600.0;
+
+/// An amount saved toward, before an optional deadline. The money comes from
+/// the expenses and incomes linked to the goal and from its dedicated sheet,
+/// whose value includes the sheets it imports.
+#[derive(Serialize, Clone)]
+pub struct Goal {
+  pub id: i32,
+  pub name: String,
+  pub target: i32,
+  pub deadline: Option<i64>,
+
+  /// the sheet holding the savings, if any
+  pub sheet_id: Option<i32>,
+
+  /// the user who created the goal
+  pub user_id: Option<i32>,
+  pub creation_date: i64
+}
+
+/// Some money that went toward a goal.
+pub struct Contribution {
+  pub date: i64,
+  pub amount: i32,
+
+  /// what the money comes from, e.g. `expense Savings`
+  pub source: String
+}
+
+/// Where a goal stands and when it should be reached at the current pace.
+pub struct Projection {
+  pub saved: i32,
+  pub remaining: i32,
+
+  /// the sum of the contributions divided by the months since the first one
+  pub monthly_average: i32,
+
+  /// `None` when the goal is reached or the savings are not growing
+  pub completion_date: Option<i64>,
+
+  /// what should be saved every month to meet the deadline, `None` when the
+  /// goal is reached or has no deadline ahead
+  pub monthly_needed: Option<i32>
+}
+
+impl Audited for Goal {
+  const ENTITY: &'static str = "goal";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    self.sheet_id
+  }
+}
+
+impl Goal {
+  pub fn new(name: &str, target: i32) -> Goal {
+    Goal {
+      id: 0,
+      name: name.to_owned(),
+      target,
+      deadline: None,
+      sheet_id: None,
+      user_id: None,
+      creation_date: date::now()
+    }
+  }
+
+  /// Inserts the goal and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into goals (
+        name,
+        target,
+        deadline,
+        sheet_id,
+        user_id,
+        creation_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6
+      )
+    ", params![self.name, self.target, self.deadline, self.sheet_id, self.user_id, self.creation_date])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  pub fn update(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      update goals
+      set name = ?1,
+          target = ?2,
+          deadline = ?3,
+          sheet_id = ?4
+      where id = ?5
+      ",
+      params![self.name, self.target, self.deadline, self.sheet_id, self.id],
+    )?;
+
+    Ok(())
+  }
+
+  /// Deletes the goal, the expenses and incomes linked to it are left without
+  /// one.
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from goals
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Goal>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, target, deadline, sheet_id, user_id, creation_date
+      from goals
+      where id = ?1
+    ")?;
+
+    let mut goals = query.query_map(params![id], |row| {
+      Ok(
+        Goal {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          target: row.get(2)?,
+          deadline: row.get(3)?,
+          sheet_id: row.get(4)?,
+          user_id: row.get(5)?,
+          creation_date: row.get(6)?
+        }
+      )
+    })?;
+
+    goals.nth(0).transpose()
+  }
+
+  /// Every goal, the closest deadlines first and those without one last.
+  pub fn get_all() -> Result<Vec<Goal>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, target, deadline, sheet_id, user_id, creation_date
+      from goals
+      order by deadline is null, deadline, name
+    ")?;
+
+    let goals = query.query_map(params![], |row| {
+      Ok(
+        Goal {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          target: row.get(2)?,
+          deadline: row.get(3)?,
+          sheet_id: row.get(4)?,
+          user_id: row.get(5)?,
+          creation_date: row.get(6)?
+        }
+      )
+    })?;
+
+    goals.collect()
+  }
+
+  /// The money that went toward the goal, the oldest first: the linked
+  /// expenses and incomes, then the rows and imports of the dedicated sheet.
+  /// An imported sheet counts for its whole value at the date of the import.
+  /// The rows of the dedicated sheet are only counted once even when they are
+  /// linked to the goal, and nothing in the trash is counted.
+  pub fn get_contributions(&self) -> Result<Vec<Contribution>> {
+    let conn = open_connection()?;
+    let mut contributions = Vec::new();
+
+    let queries: [(&str, &[&dyn ToSql]); 5] = [
+      // the linked rows
+      ("
+        select 'expense ' || expenses.name, amount, date
+        from expenses
+        join sheets on sheets.id = sheet_id
+        where goal_id = ?1 and sheet_id is not ?2
+          and expenses.deleted_date is null and sheets.deleted_date is null
+      ", params![self.id, self.sheet_id]),
+      ("
+        select 'income ' || incomes.name, amount, date
+        from incomes
+        join sheets on sheets.id = sheet_id
+        where goal_id = ?1 and sheet_id is not ?2
+          and incomes.deleted_date is null and sheets.deleted_date is null
+      ", params![self.id, self.sheet_id]),
+      // the dedicated sheet, the expenses take money out of it
+      ("
+        select 'income ' || incomes.name, amount, date
+        from incomes
+        join sheets on sheets.id = sheet_id
+        where sheet_id = ?1
+          and incomes.deleted_date is null and sheets.deleted_date is null
+      ", params![self.sheet_id]),
+      ("
+        select 'expense ' || expenses.name, -amount, date
+        from expenses
+        join sheets on sheets.id = sheet_id
+        where sheet_id = ?1
+          and expenses.deleted_date is null and sheets.deleted_date is null
+      ", params![self.sheet_id]),
+      ("
+        select 'sheet ' || children.name, children.cached_value, inherited_sheets.date
+        from inherited_sheets
+        join sheets as children on children.id = inherited_sheet_id
+        join sheets as parents on parents.id = parent_sheet_id
+        where parent_sheet_id = ?1
+          and inherited_sheets.deleted_date is null
+          and children.deleted_date is null and parents.deleted_date is null
+      ", params![self.sheet_id])
+    ];
+
+    for (sql, parameters) in &queries {
+      let mut query = conn.prepare(sql)?;
+
+      let rows = query.query_map(*parameters, |row| {
+        Ok(
+          Contribution {
+            source: row.get(0)?,
+            amount: row.get(1)?,
+            date: row.get(2)?
+          }
+        )
+      })?;
+
+      for contribution in rows {
+        contributions.push(contribution?);
+      }
+    }
+
+    contributions.sort_by_key(|contribution| contribution.date);
+
+    Ok(contributions)
+  }
+
+  /// Projects the completion of the goal from the average monthly
+  /// contribution since the first one, or since the goal was created when
+  /// there is none. At least a month is counted so a single early
+  /// contribution does not make the pace look faster than it is. There is no
+  /// completion date when the pace is too slow to ever reach the target.
+  pub fn project(&self, contributions: &[Contribution], now: i64) -> Projection {
+    let saved: i32 = contributions.iter().map(|contribution| contribution.amount).sum();
+    let remaining = (self.target - saved).max(0);
+
+    let start = contributions.first()
+      .map(|contribution| contribution.date)
+      .unwrap_or(self.creation_date)
+      .min(now);
+
+    let elapsed_months = ((now - start) as f64 / MONTH_SECONDS).max(1.0);
+    let monthly_average = (saved as f64 / elapsed_months).round() as i32;
+
+    let completion_date = if remaining > 0 && monthly_average > 0 {
+      let months = (remaining as f64 / monthly_average as f64).ceil() as u32;
+
+      date::add_months(now, months)
+    } else {
+      None
+    };
+
+    let monthly_needed = match self.deadline {
+      Some(deadline) if remaining > 0 && deadline > now => {
+        let months_left = ((deadline - now) as f64 / MONTH_SECONDS).max(1.0);
+
+        Some((remaining as f64 / months_left).ceil() as i32)
+      },
+      _ => None
+    };
+
+    Projection {
+      saved,
+      remaining,
+      monthly_average,
+      completion_date,
+      monthly_needed
+    }
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("goals", "
+    id integer primary key autoincrement,
+    name text not null,
+    target integer not null,
+    deadline integer,
+    sheet_id integer references sheets (id) on delete set null,
+    user_id integer references users (id) on delete set null,
+    creation_date integer not null
+  ")
+}
\ No newline at end of file
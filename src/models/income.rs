@@ -1,16 +1,45 @@
-use crate::constants::DATABASE_PATH;
+use crate::models::{open_connection, add_column_if_missing, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
 use rusqlite::{params, Connection, Result};
+use serde::Serialize;
 
 // Represents a source of income in a `Sheet`
 //
 //
 #[allow(dead_code)]
+#[derive(Serialize, Clone)]
 pub struct Income {
   pub id: i32,
   pub name: String,
   pub amount: i32,
   pub date: i64,
-  pub sheet_id: i32
+  pub sheet_id: i32,
+
+  /// when the income was moved to the trash
+  #[serde(skip)]
+  pub deleted_date: Option<i64>,
+
+  /// the goal the income contributes to, if any
+  pub goal_id: Option<i32>,
+
+  /// the account the money comes from or goes to, if any
+  pub account_id: Option<i32>,
+
+  /// when the income was matched against a statement of its account
+  pub cleared_date: Option<i64>
+}
+
+impl Audited for Income {
+  const ENTITY: &'static str = "income";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
 }
 
 impl Income {
@@ -21,37 +50,75 @@ impl Income {
       name: name.to_owned(),
       amount,
       date,
-      sheet_id
+      sheet_id,
+      deleted_date: None,
+      goal_id: None,
+      account_id: None,
+      cleared_date: None
     }
   }
 
+  /// Inserts the income and sets its id.
   #[allow(dead_code)]
-  pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    self.insert_in(&conn)
+  }
 
+  /// Same as `insert` on a connection of the caller, so the income can be
+  /// part of a larger transaction.
+  pub fn insert_in(&mut self, conn: &Connection) -> Result<()> {
     conn.execute("
       insert into incomes (
         name,
         amount,
         date,
-        sheet_id
+        sheet_id,
+        goal_id,
+        account_id,
+        cleared_date
       )
       values (
         ?1,
         ?2,
         ?3,
-        ?4
+        ?4,
+        ?5,
+        ?6,
+        ?7
       )
-    ", params![self.name, self.amount, self.date, self.sheet_id])
-    .map(|_n| ())
+    ", params![self.name, self.amount, self.date, self.sheet_id, self.goal_id, self.account_id, self.cleared_date])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
   }
 
+  /// Moves the income to the trash.
   #[allow(dead_code)]
   pub fn remove(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
+
+    conn.execute("
+      update incomes
+      set deleted_date = ?1
+      where id = ?2
+      ",
+      params![date::now(), self.id],
+    )?;
+
+    Ok(())
+  }
+
+  /// Takes the income out of the trash, the cached value of its sheet is left
+  /// to the caller.
+  pub fn restore(&self) -> Result<()> {
+    let conn = open_connection()?;
 
     conn.execute("
-      delete from incomes
+      update incomes
+      set deleted_date = null
       where id = ?1
       ",
       params![self.id],
@@ -59,15 +126,76 @@ impl Income {
 
     Ok(())
   }
+
+  /// Returns the income only if it is in the trash.
+  pub fn get_deleted_by_id(id: i32) -> Result<Option<Income>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, amount, date, sheet_id, deleted_date, goal_id, account_id, cleared_date
+      from incomes
+      where id = ?1 and deleted_date is not null
+    ")?;
+
+    let mut incomes = query.query_map(params![id], |row| {
+      Ok(
+        Income {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          goal_id: row.get(6)?,
+          account_id: row.get(7)?,
+          cleared_date: row.get(8)?
+        }
+      )
+    })?;
+
+    incomes.nth(0).transpose()
+  }
+
+  /// The incomes in the trash, the latest deleted first. Those of the sheets in
+  /// the trash are left out, they come back with their sheet.
+  pub fn get_all_deleted() -> Result<Vec<Income>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select incomes.id, incomes.name, amount, date, sheet_id, incomes.deleted_date, goal_id, account_id, cleared_date
+      from incomes
+      join sheets on sheets.id = sheet_id
+      where incomes.deleted_date is not null and sheets.deleted_date is null
+      order by incomes.deleted_date desc
+    ")?;
+
+    let incomes = query.query_map(params![], |row| {
+      Ok(
+        Income {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          goal_id: row.get(6)?,
+          account_id: row.get(7)?,
+          cleared_date: row.get(8)?
+        }
+      )
+    })?;
+
+    incomes.collect()
+  }
   
   #[allow(dead_code)]
   pub fn get_by_id(id: i32) -> Result<Option<Income>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, goal_id, account_id, cleared_date
       from incomes
-      where id = ?1
+      where id = ?1 and deleted_date is null
     ")?;
 
     let mut incomes = query.query_map(params![id], |row| {
@@ -77,7 +205,11 @@ impl Income {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id: row.get(4)?
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          goal_id: row.get(6)?,
+          account_id: row.get(7)?,
+          cleared_date: row.get(8)?
         }
       )
     })?;
@@ -87,11 +219,12 @@ impl Income {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<Income>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, goal_id, account_id, cleared_date
       from incomes
+      where deleted_date is null
     ")?;
 
     let incomes = query.query_map(params![], |row| {
@@ -101,7 +234,11 @@ impl Income {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id: row.get(4)?
+          sheet_id: row.get(4)?,
+          deleted_date: row.get(5)?,
+          goal_id: row.get(6)?,
+          account_id: row.get(7)?,
+          cleared_date: row.get(8)?
         }
       )
     })?;
@@ -111,12 +248,12 @@ impl Income {
 
   #[allow(dead_code)]
   pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Income>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, amount, date, sheet_id
+      select id, name, amount, date, sheet_id, deleted_date, goal_id, account_id, cleared_date
       from incomes
-      where sheet_id = ?1
+      where sheet_id = ?1 and deleted_date is null
     ")?;
 
     let incomes = query.query_map(params![sheet_id], |row| {
@@ -126,7 +263,11 @@ impl Income {
           name: row.get(1)?,
           amount: row.get(2)?,
           date: row.get(3)?,
-          sheet_id
+          sheet_id,
+          deleted_date: row.get(5)?,
+          goal_id: row.get(6)?,
+          account_id: row.get(7)?,
+          cleared_date: row.get(8)?
         }
       )
     })?;
@@ -135,16 +276,19 @@ impl Income {
   }
 
   pub fn update(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       update incomes
       set name = ?1,
           amount = ?2,
-          date = ?3
-      where id = ?4
+          date = ?3,
+          goal_id = ?4,
+          account_id = ?5,
+          cleared_date = ?6
+      where id = ?7
       ",
-      params![self.name, self.amount, self.date, self.id],
+      params![self.name, self.amount, self.date, self.goal_id, self.account_id, self.cleared_date, self.id],
     )?;
 
     Ok(())
@@ -152,16 +296,31 @@ impl Income {
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+  create_table_with_foreign_keys("incomes", "
+    id integer primary key autoincrement,
+    name text not null,
+    amount integer not null,
+    date datetime not null,
+    sheet_id integer not null references sheets (id) on delete cascade,
+    deleted_date integer,
+    goal_id integer references goals (id) on delete set null,
+    account_id integer references accounts (id) on delete set null,
+    cleared_date integer
+  ")?;
+
+  add_column_if_missing("incomes", "goal_id", "integer references goals (id) on delete set null")?;
+  add_column_if_missing("incomes", "account_id", "integer references accounts (id) on delete set null")?;
+  add_column_if_missing("incomes", "cleared_date", "integer")
+}
+
+/// Deletes for good the incomes moved to the trash before `deleted_date`.
+pub fn purge_deleted_before(deleted_date: i64) -> Result<usize> {
+  let conn = open_connection()?;
 
   conn.execute("
-    create table if not exists incomes (
-      id integer primary key autoincrement,
-      name text not null,
-      amount integer not null,
-      date datetime not null,
-      sheet_id integer not null
-    )
-  ", params![])
-  .map(|_n| ())
+    delete from incomes
+    where deleted_date < ?1
+    ",
+    params![deleted_date],
+  )
 }
\ No newline at end of file
@@ -1,11 +1,34 @@
-use crate::constants::DATABASE_PATH;
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
 use rusqlite::{params, Connection, Result};
+use serde::Serialize;
 
 #[allow(dead_code)]
+#[derive(Serialize)]
 pub struct InheritedSheet {
   pub parent_sheet_id: i32,
   pub inherited_sheet_id: i32,
-  pub date: i64
+  pub date: i64,
+
+  /// when the import was moved to the trash, it also tells apart the
+  /// deleted imports of the same sheets
+  #[serde(skip)]
+  pub deleted_date: Option<i64>
+}
+
+/// The imports are logged under the imported sheet, in the history of the
+/// sheet that imports it.
+impl Audited for InheritedSheet {
+  const ENTITY: &'static str = "inherited_sheet";
+
+  fn audit_id(&self) -> i32 {
+    self.inherited_sheet_id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.parent_sheet_id)
+  }
 }
 
 impl InheritedSheet {
@@ -14,14 +37,21 @@ impl InheritedSheet {
     InheritedSheet {
       parent_sheet_id,
       inherited_sheet_id,
-      date
+      date,
+      deleted_date: None
     }
   }
 
   #[allow(dead_code)]
   pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
+
+    self.insert_in(&conn)
+  }
 
+  /// Same as `insert` on a connection of the caller, so the import can be
+  /// part of a larger transaction.
+  pub fn insert_in(&self, conn: &Connection) -> Result<()> {
     conn.execute("
       insert into inherited_sheets (
         parent_sheet_id,
@@ -37,27 +67,96 @@ impl InheritedSheet {
     .map(|_n| ())
   }
 
+  /// Moves the import to the trash.
   #[allow(dead_code)]
   pub fn remove(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
-      delete from inherited_sheets
-      where parent_sheet_id = ?1 and inherited_sheet_id = ?2
+      update inherited_sheets
+      set deleted_date = ?1
+      where parent_sheet_id = ?2 and inherited_sheet_id = ?3 and deleted_date is null
       ",
-      params![self.parent_sheet_id, self.inherited_sheet_id],
+      params![date::now(), self.parent_sheet_id, self.inherited_sheet_id],
     )?;
 
     Ok(())
   }
 
+  /// Takes the import out of the trash, the cached value of the parent sheet
+  /// is left to the caller.
+  pub fn restore(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      update inherited_sheets
+      set deleted_date = null
+      where parent_sheet_id = ?1 and inherited_sheet_id = ?2 and deleted_date = ?3
+      ",
+      params![self.parent_sheet_id, self.inherited_sheet_id, self.deleted_date],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_deleted(parent_id: i32, inherited_id: i32, deleted_date: i64) -> Result<Option<InheritedSheet>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select parent_sheet_id, inherited_sheet_id, date, deleted_date
+      from inherited_sheets
+      where parent_sheet_id = ?1 and inherited_sheet_id = ?2 and deleted_date = ?3
+    ")?;
+
+    let mut configs = query.query_map(params![parent_id, inherited_id, deleted_date], |row| {
+      Ok(
+        InheritedSheet {
+          parent_sheet_id: row.get(0)?,
+          inherited_sheet_id: row.get(1)?,
+          date: row.get(2)?,
+          deleted_date: row.get(3)?
+        }
+      )
+    })?;
+
+    configs.nth(0).transpose()
+  }
+
+  /// The imports in the trash, the latest deleted first. Those removed along
+  /// with a sheet are left out, they come back with the sheet.
+  pub fn get_all_deleted() -> Result<Vec<InheritedSheet>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select parent_sheet_id, inherited_sheet_id, date, deleted_date
+      from inherited_sheets
+      where deleted_date is not null
+        and parent_sheet_id in (select id from sheets where deleted_date is null)
+        and inherited_sheet_id in (select id from sheets where deleted_date is null)
+      order by deleted_date desc
+    ")?;
+
+    let inherited_sheets = query.query_map(params![], |row| {
+      Ok(
+        InheritedSheet {
+          parent_sheet_id: row.get(0)?,
+          inherited_sheet_id: row.get(1)?,
+          date: row.get(2)?,
+          deleted_date: row.get(3)?
+        }
+      )
+    })?;
+
+    inherited_sheets.collect()
+  }
+
   pub fn get_by_parent_and_inherited_id(parent_id: i32, inherited_id: i32) -> Result<Option<InheritedSheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select parent_sheet_id, inherited_sheet_id, date
+      select parent_sheet_id, inherited_sheet_id, date, deleted_date
       from inherited_sheets
-      where parent_sheet_id = ?1 and inherited_sheet_id = ?2
+      where parent_sheet_id = ?1 and inherited_sheet_id = ?2 and deleted_date is null
     ")?;
 
     let mut configs = query.query_map(params![parent_id, inherited_id], |row| {
@@ -65,7 +164,8 @@ impl InheritedSheet {
         InheritedSheet {
           parent_sheet_id: row.get(0)?,
           inherited_sheet_id: row.get(1)?,
-          date: row.get(2)?
+          date: row.get(2)?,
+          deleted_date: row.get(3)?
         }
       )
     })?;
@@ -75,11 +175,12 @@ impl InheritedSheet {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<InheritedSheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select parent_sheet_id, inherited_sheet_id, date
+      select parent_sheet_id, inherited_sheet_id, date, deleted_date
       from inherited_sheets
+      where deleted_date is null
     ")?;
 
     let inherited_sheets = query.query_map(params![], |row| {
@@ -87,7 +188,8 @@ impl InheritedSheet {
         InheritedSheet {
           parent_sheet_id: row.get(0)?,
           inherited_sheet_id: row.get(1)?,
-          date: row.get(2)?
+          date: row.get(2)?,
+          deleted_date: row.get(3)?
         }
       )
     })?;
@@ -97,12 +199,12 @@ impl InheritedSheet {
 
   #[allow(dead_code)]
   pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<InheritedSheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select parent_sheet_id, inherited_sheet_id, date
+      select parent_sheet_id, inherited_sheet_id, date, deleted_date
       from inherited_sheets
-      where parent_sheet_id = ?1
+      where parent_sheet_id = ?1 and deleted_date is null
     ")?;
 
     let inherited_sheets = query.query_map(params![sheet_id], |row| {
@@ -110,50 +212,96 @@ impl InheritedSheet {
         InheritedSheet {
           parent_sheet_id: row.get(0)?,
           inherited_sheet_id: row.get(1)?,
-          date: row.get(2)?
+          date: row.get(2)?,
+          deleted_date: row.get(3)?
         }
       )
     })?;
 
     inherited_sheets.collect()
   }
+
+  /// Returns whether the sheet imports `target_id`, directly or through the
+  /// sheets it imports. Every sheet is only walked once so the loops already
+  /// in the database end.
+  pub fn imports(sheet_id: i32, target_id: i32) -> Result<bool> {
+    use std::collections::{HashMap, HashSet};
+
+    let mut imports: HashMap<i32, Vec<i32>> = HashMap::new();
+
+    for link in InheritedSheet::get_all()? {
+      imports.entry(link.parent_sheet_id).or_default().push(link.inherited_sheet_id);
+    }
+
+    let mut visited: HashSet<i32> = HashSet::new();
+    let mut sheets_to_visit = vec![sheet_id];
+
+    while let Some(id) = sheets_to_visit.pop() {
+      if id == target_id {
+        return Ok(true);
+      }
+
+      if visited.insert(id) {
+        sheets_to_visit.extend(imports.get(&id).map(|ids| ids.as_slice()).unwrap_or(&[]));
+      }
+    }
+
+    Ok(false)
+  }
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
-
-  conn.execute("
-    create table if not exists inherited_sheets (
-      parent_sheet_id integer not null,
-      inherited_sheet_id integer not null,
-      date datetime not null
-    )
-  ", params![])
-  .map(|_n| ())
+  create_table_with_foreign_keys("inherited_sheets", "
+    parent_sheet_id integer not null references sheets (id) on delete cascade,
+    inherited_sheet_id integer not null references sheets (id) on delete cascade,
+    date datetime not null,
+    deleted_date integer
+  ")
 }
 
-pub fn remove_all_from_parent_sheet_id(parent_sheet_id: i32) -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+/// Moves to the trash the imports from and of a sheet that is being moved to
+/// the trash at `deleted_date`.
+pub fn remove_all_by_sheet_id(sheet_id: i32, deleted_date: i64) -> Result<()> {
+  let conn = open_connection()?;
 
   conn.execute("
-    delete from inherited_sheets
-    where parent_sheet_id = ?1
+    update inherited_sheets
+    set deleted_date = ?1
+    where (parent_sheet_id = ?2 or inherited_sheet_id = ?2) and deleted_date is null
     ",
-    params![parent_sheet_id],
+    params![deleted_date, sheet_id],
   )?;
 
   Ok(())
 }
 
-pub fn remove_all_from_inherited_sheet_id(inherited_sheet_id: i32) -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+/// Takes out of the trash the imports removed along with a sheet, unless the
+/// sheet on their other end is still in the trash.
+pub fn restore_all_by_sheet_id(sheet_id: i32, deleted_date: i64) -> Result<()> {
+  let conn = open_connection()?;
 
   conn.execute("
-    delete from inherited_sheets
-    where inherited_sheet_id = ?1
+    update inherited_sheets
+    set deleted_date = null
+    where (parent_sheet_id = ?1 or inherited_sheet_id = ?1)
+      and deleted_date = ?2
+      and parent_sheet_id in (select id from sheets where deleted_date is null)
+      and inherited_sheet_id in (select id from sheets where deleted_date is null)
     ",
-    params![inherited_sheet_id],
+    params![sheet_id, deleted_date],
   )?;
 
   Ok(())
+}
+
+/// Deletes for good the imports moved to the trash before `deleted_date`.
+pub fn purge_deleted_before(deleted_date: i64) -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from inherited_sheets
+    where deleted_date < ?1
+    ",
+    params![deleted_date],
+  )
 }
\ No newline at end of file
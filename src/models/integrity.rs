@@ -0,0 +1,83 @@
+use crate::models::{open_connection, foreign_keys_of};
+use rusqlite::{params, Result};
+
+/// A row pointing at a row that does not exist, left by the versions of calco
+/// that did not enforce the foreign keys.
+pub struct Orphan {
+  pub table: String,
+  pub rowid: i64,
+  pub column: String,
+  pub parent_table: String,
+
+  /// the id of the missing row
+  pub parent_id: Option<i64>,
+
+  /// what the foreign key does when the parent row is deleted, the same is
+  /// done to repair the orphan
+  pub on_delete: String
+}
+
+impl Orphan {
+  /// Deletes the orphan, or clears its reference when the foreign key sets
+  /// it to null on deletion. Returns whether a row changed, an orphan of two
+  /// foreign keys is only deleted once.
+  pub fn repair(&self) -> Result<bool> {
+    let conn = open_connection()?;
+
+    let changed = if self.on_delete.eq_ignore_ascii_case("set null") {
+      conn.execute(
+        &format!("update {} set {} = null where rowid = ?1", self.table, self.column),
+        params![self.rowid]
+      )?
+    }
+    else {
+      conn.execute(
+        &format!("delete from {} where rowid = ?1", self.table),
+        params![self.rowid]
+      )?
+    };
+
+    Ok(changed > 0)
+  }
+}
+
+/// Every orphan of the database, found with `pragma foreign_key_check`.
+pub fn find_orphans() -> Result<Vec<Orphan>> {
+  let conn = open_connection()?;
+
+  let violations = {
+    let mut query = conn.prepare("pragma foreign_key_check")?;
+
+    let violations = query.query_map(params![], |row| {
+      Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i32>(3)?))
+    })?;
+
+    violations.collect::<Result<Vec<(String, i64, i32)>>>()?
+  };
+
+  let mut orphans = Vec::new();
+
+  for (table, rowid, foreign_key_id) in violations {
+    let foreign_key = match foreign_keys_of(&conn, &table)?.into_iter().find(|key| key.id == foreign_key_id) {
+      Some(foreign_key) => foreign_key,
+      None => continue
+    };
+
+    let parent_id = conn.query_row(
+      &format!("select {} from {} where rowid = ?1", foreign_key.column, table),
+      params![rowid],
+      |row| row.get(0)
+    )?;
+
+    orphans.push(Orphan {
+      table,
+      rowid,
+      column: foreign_key.column,
+      parent_table: foreign_key.parent_table,
+      parent_id,
+      on_delete: foreign_key.on_delete
+    });
+  }
+
+  Ok(orphans)
+}
@@ -1,14 +1,23 @@
+use crate::config;
 use crate::constants;
+use crate::models::open_connection;
+use crate::models::audit_entry::{self, Audited};
 use crate::models::user::{User, UserRole};
 use crate::utils::crypto::hash_password;
-use rusqlite::{params, Connection, Result};
+use crate::utils::date::{self, format_datetime};
+use rusqlite::{params, ErrorCode, Result};
 use std::ops::Add;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 use maud::{Render, html, Markup};
+use serde::Serialize;
 
+#[derive(Serialize)]
 pub struct Invitation {
   pub id: i32,
+
+  /// the secret part of the signup url, kept out of the audit log
+  #[serde(skip)]
   pub hash: String,
   pub handle: String,
   pub expire_date: i64,
@@ -18,17 +27,30 @@ pub struct Invitation {
 impl Render for Invitation {
   fn render(&self) -> Markup {
     html! {
-      ul {
-        li class="id" { "id: " (self.id) }
-        li class="handle" { "handle: " (self.handle) }
-        li class="user_role" { "user_role: " (self.user_role) }
-        li class="hash" { "hash: " (self.hash) }
-        li class="expire_date" { "expire_date: " (self.expire_date) }
+      span class="handle" { (self.handle) }
+      span class="user_role" { (self.user_role) }
+
+      @if self.is_expired() {
+        span class="expire_date expired" { "expired on " (format_datetime(self.expire_date)) }
+      } @else {
+        span class="expire_date" { "expires on " (format_datetime(self.expire_date)) }
       }
     }
   }
 }
 
+impl Audited for Invitation {
+  const ENTITY: &'static str = "invitation";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    None
+  }
+}
+
 impl Invitation {
   pub fn new(handle: &str, user_role: UserRole) -> Invitation {
     let expire_date = new_expiration_date();
@@ -42,8 +64,9 @@ impl Invitation {
     }
   }
 
-  pub fn insert(invitation: &Invitation) -> Result<()> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+  /// Inserts the invitation and sets its id.
+  pub fn insert(invitation: &mut Invitation) -> Result<()> {
+    let conn = open_connection()?;
 
     conn.execute(
       "
@@ -64,11 +87,37 @@ impl Invitation {
         invitation.user_role as i32
       ],
     )?;
+
+    invitation.id = conn.last_insert_rowid() as i32;
+
     Ok(())
   }
 
+  pub fn get_by_id(id: i32) -> Result<Option<Invitation>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare(
+      "
+      select id, handle, hash, expire_date, user_role
+      from invitations
+      where id = ?1
+    ",
+    )?;
+    let mut invitations = query.query_map(params![id], |row| {
+      Ok(Invitation {
+        id: row.get(0)?,
+        handle: row.get(1)?,
+        hash: row.get(2)?,
+        expire_date: row.get(3)?,
+        user_role: UserRole::from_number(row.get(4)?),
+      })
+    })?;
+
+    invitations.nth(0).transpose()
+  }
+
   pub fn get_by_hash(hash: &str) -> Result<Option<Invitation>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare(
       "
@@ -92,7 +141,7 @@ impl Invitation {
 
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<Invitation>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare(
       "
@@ -115,7 +164,7 @@ impl Invitation {
   }
 
   pub fn get_by_handle(handle: &str) -> Result<Option<Invitation>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare(
       "
@@ -138,7 +187,7 @@ impl Invitation {
   }
 
   pub fn remove(invitation: &Invitation) -> Result<()> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute(
       "
@@ -150,27 +199,58 @@ impl Invitation {
     Ok(())
   }
 
+  pub fn is_expired(&self) -> bool {
+    self.expire_date < date::now()
+  }
+
   pub fn to_url(self: &Invitation) -> String {
     format!("/signup?hash={}&handle={}", self.hash, self.handle)
   }
 
-  pub fn consume(self: &Invitation, password: String) -> std::result::Result<(), String> {
-    let hashed_password = hash_password(&self.handle, &password)
-      .map_err(|err| format!("error when hashing password {}", err))?;
+  /// Creates the account the invitation was made for, then removes the
+  /// invitation so it cannot be used a second time. Returns the new user.
+  pub fn consume(self: &Invitation, password: String) -> std::result::Result<User, InvitationError> {
+    if self.is_expired() {
+      return Err(InvitationError::Expired);
+    }
+
+    let existing_user = User::get_by_handle(&self.handle)
+      .map_err(|err| InvitationError::Internal(format!("error when fetching user by handle {}", err)))?;
 
-    let user = User::new(self.handle.clone(), hashed_password, self.user_role);
+    if existing_user.is_some() {
+      return Err(InvitationError::HandleTaken);
+    }
 
-    user.insert().map_err(|err| format!("error when inserting user {}", err))?;
-    Invitation::remove(&self).map_err(|err| format!("error when removing invitation {}", err))?;
+    let hashed_password = hash_password(&password)
+      .map_err(|err| InvitationError::Internal(format!("error when hashing password {}", err)))?;
 
-    Ok(())
+    let mut user = User::new(self.handle.clone(), hashed_password, self.user_role);
+
+    user.insert().map_err(|err| match err {
+      // another signup for the same handle happened in the meantime
+      rusqlite::Error::SqliteFailure(ref failure, _) if failure.code == ErrorCode::ConstraintViolation => {
+        InvitationError::HandleTaken
+      },
+      err => InvitationError::Internal(format!("error when inserting user {}", err))
+    })?;
+
+    Invitation::remove(&self)
+      .map_err(|err| InvitationError::Internal(format!("error when removing invitation {}", err)))?;
+
+    Ok(user)
   }
 }
 
+pub enum InvitationError {
+  Expired,
+  HandleTaken,
+  Internal(String)
+}
+
 fn new_expiration_date() -> i64 {
   SystemTime::now()
     .add(Duration::new(
-      constants::INVITATION_EXPIRE_DURATION_SECONDS,
+      config::get().invitation_lifetime,
       0,
     ))
     .duration_since(UNIX_EPOCH)
@@ -179,7 +259,7 @@ fn new_expiration_date() -> i64 {
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(constants::DATABASE_PATH)?;
+  let conn = open_connection()?;
 
   conn.execute(
     "
@@ -198,7 +278,7 @@ pub fn create_table() -> Result<()> {
 }
 
 pub fn update_invitation_expiration(handle: &str) -> Result<()> {
-  let conn = Connection::open(constants::DATABASE_PATH)?;
+  let conn = open_connection()?;
   let expire_date = new_expiration_date();
 
   conn.execute(
@@ -211,4 +291,57 @@ pub fn update_invitation_expiration(handle: &str) -> Result<()> {
   )?;
 
   Ok(())
-}
\ No newline at end of file
+}
+
+/// Changes the role the pending invitation for `handle` grants once it is
+/// consumed.
+pub fn update_invitation_role(handle: &str, user_role: UserRole) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute(
+    "
+    update invitations
+    set user_role = ?1
+    where handle = ?2
+  ",
+    params![user_role as i32, handle],
+  )?;
+
+  Ok(())
+}
+
+/// Removes the invitations that expired more than
+/// `constants::STALE_INVITATION_GRACE_SECONDS` ago, the others are kept so
+/// an administrator can still extend them.
+pub fn remove_stale_invitations() -> Result<usize> {
+  let conn = open_connection()?;
+
+  let mut query = conn.prepare(
+    "
+    select id, handle, hash, expire_date, user_role
+    from invitations
+    where expire_date < ?1
+  ",
+  )?;
+
+  let stale_invitations = query.query_map(
+    params![date::now() - constants::STALE_INVITATION_GRACE_SECONDS],
+    |row| {
+      Ok(Invitation {
+        id: row.get(0)?,
+        handle: row.get(1)?,
+        hash: row.get(2)?,
+        expire_date: row.get(3)?,
+        user_role: UserRole::from_number(row.get(4)?),
+      })
+    },
+  )?
+  .collect::<Result<Vec<Invitation>>>()?;
+
+  for invitation in &stale_invitations {
+    Invitation::remove(invitation)?;
+    audit_entry::record(None, Some(invitation), None)?;
+  }
+
+  Ok(stale_invitations.len())
+}
@@ -1,13 +1,130 @@
-use rusqlite::{Result};
+use crate::config;
+use rusqlite::{params, Connection, Result};
 
 pub mod sheet;
 pub mod income;
 pub mod expense;
+pub mod transfer;
+pub mod split;
+pub mod settlement;
 pub mod category;
+pub mod goal;
+pub mod account;
 pub mod user;
 pub mod invitation;
 pub mod inherited_sheet;
 pub mod cached_sheet_value;
+pub mod session;
+pub mod password_reset;
+pub mod two_factor;
+pub mod failed_attempt;
+pub mod audit_entry;
+pub mod trash;
+pub mod integrity;
+pub mod sheet_template;
+pub mod recurring_entry;
+pub mod forecast;
+pub mod report;
+
+pub fn open_connection() -> Result<Connection> {
+  let conn = Connection::open(&config::get().database_path)?;
+
+  // sqlite only enforces the foreign keys when asked to, on every connection
+  conn.execute_batch("pragma foreign_keys = on")?;
+
+  Ok(conn)
+}
+
+/// Creates a table whose `columns` define foreign keys. A table created by
+/// an older version of calco without them is rebuilt, as sqlite cannot add
+/// foreign keys to an existing table: its rows are copied as they are, the
+/// orphans among them are left to `calco check-integrity`.
+pub fn create_table_with_foreign_keys(table: &str, columns: &str) -> Result<()> {
+  let mut conn = open_connection()?;
+
+  conn.execute(&format!("create table if not exists {} ({})", table, columns), params![])?;
+
+  if !foreign_keys_of(&conn, table)?.is_empty() {
+    return Ok(());
+  }
+
+  println!("rebuilding table {} with its foreign keys", table);
+
+  let rebuilt_table = format!("{}_rebuild", table);
+
+  // cannot be changed inside the transaction, the orphans would not be copied
+  conn.execute_batch("pragma foreign_keys = off")?;
+
+  let transaction = conn.transaction()?;
+
+  transaction.execute(&format!("create table {} ({})", rebuilt_table, columns), params![])?;
+
+  let new_columns = columns_of(&transaction, &rebuilt_table)?;
+  let copied_columns = columns_of(&transaction, table)?
+    .into_iter()
+    .filter(|column| new_columns.contains(column))
+    .collect::<Vec<String>>()
+    .join(", ");
+
+  transaction.execute(&format!(
+    "insert into {} ({}) select {} from {}",
+    rebuilt_table, copied_columns, copied_columns, table
+  ), params![])?;
+
+  // the rename is run as a batch, rusqlite rejects the rows it steps through
+  transaction.execute_batch(&format!(
+    "drop table {}; alter table {} rename to {}",
+    table, rebuilt_table, table
+  ))?;
+  transaction.commit()?;
+
+  conn.execute_batch("pragma foreign_keys = on")
+}
+
+/// A foreign key of a table, as listed by `pragma foreign_key_list`.
+pub struct ForeignKey {
+  pub id: i32,
+  pub column: String,
+  pub parent_table: String,
+
+  /// either `CASCADE`, `SET NULL` or `RESTRICT`
+  pub on_delete: String
+}
+
+pub fn foreign_keys_of(conn: &Connection, table: &str) -> Result<Vec<ForeignKey>> {
+  let mut query = conn.prepare(&format!("pragma foreign_key_list({})", table))?;
+
+  let foreign_keys = query.query_map(params![], |row| {
+    Ok(ForeignKey {
+      id: row.get(0)?,
+      parent_table: row.get(2)?,
+      column: row.get(3)?,
+      on_delete: row.get(6)?
+    })
+  })?;
+
+  foreign_keys.collect()
+}
+
+fn columns_of(conn: &Connection, table: &str) -> Result<Vec<String>> {
+  let mut query = conn.prepare(&format!("pragma table_info({})", table))?;
+  let columns = query.query_map(params![], |row| row.get::<_, String>(1))?;
+
+  columns.collect()
+}
+
+/// Adds a column to a table created by an older version of calco, as
+/// `create table if not exists` leaves existing tables untouched.
+pub fn add_column_if_missing(table: &str, column: &str, definition: &str) -> Result<()> {
+  let conn = open_connection()?;
+  let columns = columns_of(&conn, table)?;
+
+  if !columns.iter().any(|name| name == column) {
+    conn.execute(&format!("alter table {} add column {} {}", table, column, definition), params![])?;
+  }
+
+  Ok(())
+}
 
 pub fn create_database() -> Result<()> {
   println!("creating database tables");
@@ -15,63 +132,68 @@ pub fn create_database() -> Result<()> {
   sheet::create_table()
   .and(user::create_table())
   .and(category::create_table())
+  .and(goal::create_table())
+  .and(account::create_table())
   .and(income::create_table())
   .and(expense::create_table())
+  .and(transfer::create_table())
+  .and(split::create_table())
+  .and(settlement::create_table())
   .and(invitation::create_table())
   .and(inherited_sheet::create_table())
   .and(cached_sheet_value::create_table())
-  .and(create_admin_invitation())
+  .and(session::create_table())
+  .and(password_reset::create_table())
+  .and(two_factor::create_table())
+  .and(failed_attempt::create_table())
+  .and(audit_entry::create_table())
+  .and(sheet_template::create_table())
+  .and(recurring_entry::create_table())
 }
 
-pub fn create_admin_invitation() -> Result<()> {
-  use crate::constants;
-  use crate::models::invitation::{Invitation, update_invitation_expiration};
-  use crate::models::user::{UserRole};
-  use clipboard::{ClipboardProvider, ClipboardContext};
-
-  let invitation = Invitation::get_by_handle(constants::ADMIN_HANDLE)?
-  .or_else(|| {
-    let new_invitation = Invitation::new(
-      constants::ADMIN_HANDLE,
-      UserRole::Admin
-    );
+/// Deletes for good what was moved to the trash before `deleted_before`,
+/// returns the number of purged sheets, expenses, incomes and imports.
+pub fn purge_trash(deleted_before: i64) -> Result<usize> {
+  let sheets = sheet::purge_deleted_before(deleted_before)?;
+  let expenses = expense::purge_deleted_before(deleted_before)?;
+  let incomes = income::purge_deleted_before(deleted_before)?;
+  let inherited_sheets = inherited_sheet::purge_deleted_before(deleted_before)?;
 
-    Invitation::insert(&new_invitation)
-    .expect("error when inserting new admin invitation");
-
-    Some(new_invitation)
-  });
+  Ok(sheets + expenses + incomes + inherited_sheets)
+}
 
-  match invitation {
-    Some(inv) => {
-      use std::io::prelude::Write;
+/// Issues the invitation of the first administrator of the instance.
+///
+/// Nothing is done once an admin account exists. Otherwise an invitation for
+/// `handle` is created and returned so its link can be shown once, a pending
+/// invitation is only replaced when it expired or does not grant the admin
+/// role.
+pub fn bootstrap_admin(handle: &str) -> Result<Option<invitation::Invitation>> {
+  use crate::models::invitation::Invitation;
+  use crate::models::user::{User, UserRole};
 
-      update_invitation_expiration(constants::ADMIN_HANDLE)?;
+  if User::admin_exists()? {
+    return Ok(None);
+  }
 
+  if let Some(pending) = Invitation::get_by_handle(handle)? {
+    if !pending.is_expired() && pending.user_role.to_number() == UserRole::Admin.to_number() {
       println!(
-        "an admin invitation was made for {}, use it to create an account or delete it later on", 
-        constants::ADMIN_HANDLE
+        "an admin invitation for {} is pending, run `calco create-invitation --handle {} --role admin` to print its link",
+        handle,
+        handle
       );
 
-      if let Ok(context) = ClipboardProvider::new() {
-        let mut context: ClipboardContext = context;
-        if let Err(err) = context.set_contents(inv.to_url()) {
-          println!("could not set clipboard content {}", err);
-        }
-      }
-      else {
-        println!("could not write invitation link to clipboard");
-      }
-
-      println!("{}", inv.to_url());
-
-      if let Err(e) = std::fs::File::create("invitation.txt")
-      .and_then(|mut file| file.write_all(inv.to_url().as_bytes())) {
-        println!("an error occured when writing invitation to file {}", e);
-      };
+      return Ok(None);
     }
-    None => println!("could not create admin invitation")
+
+    Invitation::remove(&pending)?;
+    audit_entry::record(None, Some(&pending), None)?;
   }
 
-  Ok(())
-}
\ No newline at end of file
+  let mut invitation = Invitation::new(handle, UserRole::Admin);
+  Invitation::insert(&mut invitation)?;
+  audit_entry::record(None, None, Some(&invitation))?;
+
+  Ok(Some(invitation))
+}
@@ -0,0 +1,208 @@
+use crate::constants;
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::session;
+use crate::models::user::{User, set_user_password};
+use crate::utils::crypto::hash_password;
+use crate::utils::date::{self, format_datetime};
+use rusqlite::{params, Result};
+use uuid::Uuid;
+use maud::{Render, html, Markup};
+
+/// A one-time link issued by an administrator so a user can choose a new
+/// password, works like an `Invitation` for an existing account.
+pub struct PasswordReset {
+  pub id: i32,
+  pub user_id: i32,
+  pub hash: String,
+  pub expire_date: i64
+}
+
+impl Render for PasswordReset {
+  fn render(&self) -> Markup {
+    html! {
+      @match User::get_by_id(self.user_id) {
+        Ok(Some(user)) => { span class="handle" { (user.handle) } },
+        _ => { span class="handle" { "unknown user" } }
+      }
+
+      @if self.is_expired() {
+        span class="expire_date expired" { "expired on " (format_datetime(self.expire_date)) }
+      } @else {
+        span class="expire_date" { "expires on " (format_datetime(self.expire_date)) }
+      }
+    }
+  }
+}
+
+impl PasswordReset {
+  pub fn new(user_id: i32) -> PasswordReset {
+    PasswordReset {
+      id: 0,
+      user_id,
+      hash: Uuid::new_v4().to_string(),
+      expire_date: date::now() + constants::PASSWORD_RESET_EXPIRE_DURATION_SECONDS
+    }
+  }
+
+  /// Inserts the reset, replacing the previous ones of the same user so only
+  /// the latest link works.
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from password_resets
+      where user_id = ?1
+    ", params![self.user_id])?;
+
+    conn.execute("
+      insert into password_resets (
+        user_id, hash, expire_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3
+      )
+    ", params![self.user_id, self.hash, self.expire_date])?;
+
+    Ok(())
+  }
+
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from password_resets
+      where id = ?1
+    ", params![self.id])?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<PasswordReset>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, hash, expire_date
+      from password_resets
+      where id = ?1
+    ")?;
+
+    let mut resets = query.query_map(params![id], |row| {
+      Ok(PasswordReset {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        hash: row.get(2)?,
+        expire_date: row.get(3)?
+      })
+    })?;
+
+    resets.nth(0).transpose()
+  }
+
+  pub fn get_by_hash(hash: &str) -> Result<Option<PasswordReset>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, hash, expire_date
+      from password_resets
+      where hash = ?1
+    ")?;
+
+    let mut resets = query.query_map(params![hash], |row| {
+      Ok(PasswordReset {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        hash: row.get(2)?,
+        expire_date: row.get(3)?
+      })
+    })?;
+
+    resets.nth(0).transpose()
+  }
+
+  pub fn get_all() -> Result<Vec<PasswordReset>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, hash, expire_date
+      from password_resets
+      order by expire_date desc
+    ")?;
+
+    let resets = query.query_map(params![], |row| {
+      Ok(PasswordReset {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        hash: row.get(2)?,
+        expire_date: row.get(3)?
+      })
+    })?;
+
+    resets.collect()
+  }
+
+  pub fn is_expired(&self) -> bool {
+    self.expire_date < date::now()
+  }
+
+  pub fn to_url(&self) -> String {
+    format!("/reset-password?hash={}", self.hash)
+  }
+
+  /// Sets the new password of the user and signs them out of every device,
+  /// then removes the reset so the link cannot be used a second time.
+  pub fn consume(&self, password: &str) -> std::result::Result<(), PasswordResetError> {
+    if self.is_expired() {
+      return Err(PasswordResetError::Expired);
+    }
+
+    let hashed_password = hash_password(password)
+      .map_err(|err| PasswordResetError::Internal(format!("error when hashing password {}", err)))?;
+
+    set_user_password(self.user_id, &hashed_password)
+      .map_err(|err| PasswordResetError::Internal(format!("error when updating user password {}", err)))?;
+
+    session::remove_all_by_user_id(self.user_id)
+      .map_err(|err| PasswordResetError::Internal(format!("error when removing user sessions {}", err)))?;
+
+    self.remove()
+      .map_err(|err| PasswordResetError::Internal(format!("error when removing password reset {}", err)))?;
+
+    Ok(())
+  }
+}
+
+pub enum PasswordResetError {
+  Expired,
+  Internal(String)
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("password_resets", "
+    id integer primary key autoincrement,
+    user_id integer not null references users (id) on delete cascade,
+    hash text not null unique,
+    expire_date datetime not null
+  ")
+}
+
+pub fn remove_all_by_user_id(user_id: i32) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from password_resets
+    where user_id = ?1
+  ", params![user_id])?;
+
+  Ok(())
+}
+
+pub fn remove_expired_password_resets() -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from password_resets
+    where expire_date < ?1
+  ", params![date::now()])
+}
@@ -0,0 +1,189 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date::{self, Period};
+use rusqlite::{params, Result};
+use serde::Serialize;
+
+/// An expense or an income expected to come back every period, only used to
+/// forecast the balance of its sheet.
+#[derive(Serialize, Clone)]
+pub struct RecurringEntry {
+  pub id: i32,
+  pub sheet_id: i32,
+
+  /// either `expense` or `income`
+  pub kind: String,
+  pub name: String,
+  pub amount: i32,
+
+  /// the date of the first occurrence
+  pub start_date: i64,
+  pub period: Period,
+
+  /// no occurrence comes after it, if any
+  pub end_date: Option<i64>
+}
+
+impl Audited for RecurringEntry {
+  const ENTITY: &'static str = "recurring_entry";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
+}
+
+impl RecurringEntry {
+  /// Inserts the entry and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into recurring_entries (
+        sheet_id,
+        kind,
+        name,
+        amount,
+        start_date,
+        period,
+        end_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6,
+        ?7
+      )
+    ", params![self.sheet_id, self.kind, self.name, self.amount, self.start_date, self.period.name(), self.end_date])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from recurring_entries
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<RecurringEntry>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, sheet_id, kind, name, amount, start_date, period, end_date
+      from recurring_entries
+      where id = ?1
+    ")?;
+
+    let mut entries = query.query_map(params![id], |row| {
+      Ok(
+        RecurringEntry {
+          id: row.get(0)?,
+          sheet_id: row.get(1)?,
+          kind: row.get(2)?,
+          name: row.get(3)?,
+          amount: row.get(4)?,
+          start_date: row.get(5)?,
+          period: Period::from_name(&row.get::<_, String>(6)?).unwrap_or(Period::Month),
+          end_date: row.get(7)?
+        }
+      )
+    })?;
+
+    entries.nth(0).transpose()
+  }
+
+  /// The recurring entries of the sheet, sorted by name.
+  pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<RecurringEntry>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, sheet_id, kind, name, amount, start_date, period, end_date
+      from recurring_entries
+      where sheet_id = ?1
+      order by name
+    ")?;
+
+    let entries = query.query_map(params![sheet_id], |row| {
+      Ok(
+        RecurringEntry {
+          id: row.get(0)?,
+          sheet_id: row.get(1)?,
+          kind: row.get(2)?,
+          name: row.get(3)?,
+          amount: row.get(4)?,
+          start_date: row.get(5)?,
+          period: Period::from_name(&row.get::<_, String>(6)?).unwrap_or(Period::Month),
+          end_date: row.get(7)?
+        }
+      )
+    })?;
+
+    entries.collect()
+  }
+
+  /// The dates of the occurrences after `from` and up to `to`, both
+  /// included.
+  pub fn occurrences_between(&self, from: i64, to: i64) -> Vec<i64> {
+    let last = self.end_date.map_or(to, |end_date| end_date.min(to));
+    let mut occurrences = Vec::new();
+
+    // the months are added to the start date rather than to the previous
+    // occurrence, so the 31st stays the 31st after a shorter month
+    let mut count = 0;
+    let mut occurrence = self.start_date;
+
+    while occurrence <= last {
+      if occurrence >= from {
+        occurrences.push(occurrence);
+      }
+
+      count += 1;
+      let next = match self.period {
+        Period::None => None,
+        Period::Week => Some(self.start_date + count as i64 * 7 * 24 * 3600),
+        Period::Month => date::add_months(self.start_date, count),
+        Period::Year => date::add_months(self.start_date, count * 12)
+      };
+
+      occurrence = match next {
+        Some(next) => next,
+        None => break
+      };
+    }
+
+    occurrences
+  }
+
+  /// The amount added to the balance by each occurrence.
+  pub fn signed_amount(&self) -> i32 {
+    if self.kind == "expense" { -self.amount } else { self.amount }
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("recurring_entries", "
+    id integer primary key autoincrement,
+    sheet_id integer not null references sheets (id) on delete cascade,
+    kind text not null,
+    name text not null,
+    amount integer not null,
+    start_date integer not null,
+    period text not null,
+    end_date integer
+  ")
+}
\ No newline at end of file
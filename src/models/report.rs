@@ -0,0 +1,153 @@
+use crate::models::open_connection;
+use crate::models::sheet::Sheet;
+use chrono::prelude::*;
+use rusqlite::{params, Result};
+use std::collections::BTreeMap;
+
+/// The name the expenses without a category are reported under.
+pub const UNCATEGORIZED: &str = "no category";
+
+/// The totals of the rows of a sheet, without the sheets it imports so a
+/// month is not counted again in the sheets that import it. The entries of
+/// transfers are left out too, the money only moved between sheets.
+pub struct SheetTotals {
+  pub sheet: Sheet,
+
+  /// the date of the earliest row, the sheet is placed at it over time
+  pub date: i64,
+  pub income: i32,
+  pub expense: i32,
+
+  /// the expenses by category name
+  pub categories: BTreeMap<String, i32>
+}
+
+impl SheetTotals {
+  /// `None` when the sheet has no row to place it in time.
+  fn get_by_sheet(sheet: Sheet) -> Result<Option<SheetTotals>> {
+    let conn = open_connection()?;
+
+    let (date, income, expense) = conn.query_row("
+      select
+        (
+          select min(date) from (
+            select date from expenses where sheet_id = ?1 and deleted_date is null
+              and id not in (select expense_id from transfers)
+            union all
+            select date from incomes where sheet_id = ?1 and deleted_date is null
+              and id not in (select income_id from transfers)
+          )
+        ),
+        (
+          select coalesce(sum(amount), 0) from incomes where sheet_id = ?1 and deleted_date is null
+            and id not in (select income_id from transfers)
+        ),
+        (
+          select coalesce(sum(amount), 0) from expenses where sheet_id = ?1 and deleted_date is null
+            and id not in (select expense_id from transfers)
+        )
+      ",
+      params![sheet.id],
+      |row| Ok((row.get::<_, Option<i64>>(0)?, row.get(1)?, row.get(2)?))
+    )?;
+
+    let date = match date {
+      Some(date) => date,
+      None => return Ok(None)
+    };
+
+    let mut query = conn.prepare("
+      select categories.name, sum(amount)
+      from expenses
+      left join categories on categories.id = category_id
+      where expenses.sheet_id = ?1 and deleted_date is null
+        and expenses.id not in (select expense_id from transfers)
+      group by categories.name
+    ")?;
+
+    let rows = query.query_map(params![sheet.id], |row| {
+      Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i32>(1)?))
+    })?;
+
+    let mut categories = BTreeMap::new();
+
+    for row in rows {
+      let (name, spent) = row?;
+
+      *categories.entry(name.unwrap_or_else(|| String::from(UNCATEGORIZED))).or_insert(0) += spent;
+    }
+
+    Ok(Some(SheetTotals { sheet, date, income, expense, categories }))
+  }
+
+  pub fn net(&self) -> i32 {
+    self.income - self.expense
+  }
+
+  /// The year and month of the sheet, to find the same month a year before.
+  fn month(&self) -> (i32, u32) {
+    let datetime = NaiveDateTime::from_timestamp(self.date, 0);
+
+    (datetime.year(), datetime.month())
+  }
+}
+
+/// Sheets compared over time, the oldest first.
+pub struct Report {
+  pub sheets: Vec<SheetTotals>
+}
+
+impl Report {
+  /// Reports on the given sheets, or on all of them when there is none. Only
+  /// the sheets whose earliest row is between `from` and `to` are kept, and
+  /// the sheets without any row are left out.
+  pub fn build(sheet_ids: &[i32], from: Option<i64>, to: Option<i64>) -> Result<Report> {
+    let sheets = Sheet::get_all()?
+      .into_iter()
+      .filter(|sheet| sheet_ids.is_empty() || sheet_ids.contains(&sheet.id));
+
+    let mut totals = Vec::new();
+
+    for sheet in sheets {
+      if let Some(sheet_totals) = SheetTotals::get_by_sheet(sheet)? {
+        if from.is_none_or(|from| sheet_totals.date >= from) && to.is_none_or(|to| sheet_totals.date <= to) {
+          totals.push(sheet_totals);
+        }
+      }
+    }
+
+    totals.sort_by_key(|sheet_totals| sheet_totals.date);
+
+    Ok(Report { sheets: totals })
+  }
+
+  /// Every category name of the reported sheets, sorted.
+  pub fn category_names(&self) -> Vec<&str> {
+    let mut names: Vec<&str> = self.sheets
+      .iter()
+      .flat_map(|sheet_totals| sheet_totals.categories.keys().map(String::as_str))
+      .collect();
+
+    names.sort_unstable();
+    names.dedup();
+
+    names
+  }
+
+  /// The sheet reported before the one at `index`, for the month-over-month
+  /// changes.
+  pub fn previous(&self, index: usize) -> Option<&SheetTotals> {
+    index.checked_sub(1).map(|previous| &self.sheets[previous])
+  }
+
+  /// The latest sheet reported before the one at `index` that starts in the
+  /// same month of the previous year, for the year-over-year changes.
+  pub fn year_before(&self, index: usize) -> Option<&SheetTotals> {
+    let (year, month) = self.sheets[index].month();
+
+    self.sheets[..index]
+      .iter()
+      .rev()
+      .find(|sheet_totals| sheet_totals.month() == (year - 1, month))
+  }
+}
\ No newline at end of file
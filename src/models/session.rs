@@ -0,0 +1,248 @@
+use crate::config;
+use crate::constants;
+use crate::models::{add_column_if_missing, create_table_with_foreign_keys, open_connection};
+use crate::utils::date;
+use rusqlite::{params, Result};
+use uuid::Uuid;
+
+/// A signed in device, a user can have as many sessions as they want.
+/// The `token` is the value of the `token` cookie of the device, the
+/// `csrf_token` is embedded in the forms of the pages, see
+/// `middlewares::CsrfProtection`.
+pub struct Session {
+  pub id: i32,
+  pub user_id: i32,
+  pub token: String,
+  pub csrf_token: String,
+  pub user_agent: String,
+  pub creation_date: i64,
+  pub last_seen_date: i64,
+  pub expire_date: i64
+}
+
+impl Session {
+  pub fn new(user_id: i32, user_agent: &str) -> Session {
+    let now = date::now();
+
+    Session {
+      id: 0,
+      user_id,
+      token: Uuid::new_v4().to_string(),
+      csrf_token: Uuid::new_v4().to_string(),
+      user_agent: user_agent.to_owned(),
+      creation_date: now,
+      last_seen_date: now,
+      expire_date: new_expiration_date()
+    }
+  }
+
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into sessions (
+        user_id, token, csrf_token, user_agent, creation_date, last_seen_date, expire_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4,
+        ?5,
+        ?6,
+        ?7
+      )
+    ", params![
+      self.user_id,
+      self.token,
+      self.csrf_token,
+      self.user_agent,
+      self.creation_date,
+      self.last_seen_date,
+      self.expire_date
+    ])?;
+
+    Ok(())
+  }
+
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from sessions
+      where id = ?1
+    ", params![self.id])?;
+
+    Ok(())
+  }
+
+  pub fn is_expired(&self) -> bool {
+    self.expire_date < date::now()
+  }
+
+  /// Marks the session as seen now and pushes its expiration date back.
+  /// To avoid a write on every request, nothing is done if the session was
+  /// already seen in the last `constants::SESSION_RENEWAL_INTERVAL_SECONDS`.
+  /// Returns whether the expiration date moved.
+  pub fn renew(&mut self) -> Result<bool> {
+    let now = date::now();
+
+    if now - self.last_seen_date < constants::SESSION_RENEWAL_INTERVAL_SECONDS {
+      return Ok(false);
+    }
+
+    self.last_seen_date = now;
+    self.expire_date = new_expiration_date();
+
+    let conn = open_connection()?;
+
+    conn.execute("
+      update sessions
+      set last_seen_date = ?1,
+          expire_date = ?2
+      where id = ?3
+    ", params![self.last_seen_date, self.expire_date, self.id])?;
+
+    Ok(true)
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Session>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, token, csrf_token, user_agent, creation_date, last_seen_date, expire_date
+      from sessions
+      where id = ?1
+    ")?;
+
+    let mut sessions = query.query_map(params![id], |row| {
+      Ok(Session {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        token: row.get(2)?,
+        csrf_token: row.get(3)?,
+        user_agent: row.get(4)?,
+        creation_date: row.get(5)?,
+        last_seen_date: row.get(6)?,
+        expire_date: row.get(7)?
+      })
+    })?;
+
+    sessions.nth(0).transpose()
+  }
+
+  pub fn get_by_token(token: &str) -> Result<Option<Session>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, token, csrf_token, user_agent, creation_date, last_seen_date, expire_date
+      from sessions
+      where token = ?1
+    ")?;
+
+    let mut sessions = query.query_map(params![token], |row| {
+      Ok(Session {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        token: row.get(2)?,
+        csrf_token: row.get(3)?,
+        user_agent: row.get(4)?,
+        creation_date: row.get(5)?,
+        last_seen_date: row.get(6)?,
+        expire_date: row.get(7)?
+      })
+    })?;
+
+    sessions.nth(0).transpose()
+  }
+
+  pub fn get_all_by_user_id(user_id: i32) -> Result<Vec<Session>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, token, csrf_token, user_agent, creation_date, last_seen_date, expire_date
+      from sessions
+      where user_id = ?1
+      order by last_seen_date desc
+    ")?;
+
+    let sessions = query.query_map(params![user_id], |row| {
+      Ok(Session {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        token: row.get(2)?,
+        csrf_token: row.get(3)?,
+        user_agent: row.get(4)?,
+        creation_date: row.get(5)?,
+        last_seen_date: row.get(6)?,
+        expire_date: row.get(7)?
+      })
+    })?;
+
+    sessions.collect()
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  let conn = open_connection()?;
+
+  create_table_with_foreign_keys("sessions", "
+    id integer primary key autoincrement,
+    user_id integer not null references users (id) on delete cascade,
+    token text not null unique,
+    csrf_token text not null default '',
+    user_agent text not null,
+    creation_date datetime not null,
+    last_seen_date datetime not null,
+    expire_date datetime not null
+  ")?;
+
+  add_column_if_missing("sessions", "csrf_token", "text not null default ''")?;
+
+  // the sessions created before the column existed get their own token
+  conn.execute("
+    update sessions
+    set csrf_token = lower(hex(randomblob(16)))
+    where csrf_token = ''
+  ", params![])?;
+
+  Ok(())
+}
+
+pub fn remove_all_by_user_id(user_id: i32) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from sessions
+    where user_id = ?1
+  ", params![user_id])?;
+
+  Ok(())
+}
+
+/// Removes the sessions of the user except `session_id`, signing them out of
+/// their other devices.
+pub fn remove_all_by_user_id_except(user_id: i32, session_id: i32) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from sessions
+    where user_id = ?1
+      and id != ?2
+  ", params![user_id, session_id])?;
+
+  Ok(())
+}
+
+pub fn remove_expired_sessions() -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from sessions
+    where expire_date < ?1
+  ", params![date::now()])
+}
+
+fn new_expiration_date() -> i64 {
+  date::now() + config::get().session_lifetime as i64
+}
@@ -0,0 +1,197 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::models::split::Split;
+use crate::models::transfer::Transfer;
+use crate::utils::date;
+use rusqlite::{params, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A payment from a user to another to settle what they owe for the split
+/// expenses of a sheet. The money moves through a transfer between a sheet of
+/// each user, the settlement goes away with it.
+#[derive(Serialize, Clone)]
+pub struct Settlement {
+  pub id: i32,
+
+  /// the sheet whose split expenses are settled
+  pub sheet_id: i32,
+  pub from_user_id: i32,
+  pub to_user_id: i32,
+  pub transfer_id: i32,
+
+  /// the amount of the transfer
+  #[serde(skip)]
+  pub amount: i32,
+
+  /// the date of the transfer
+  #[serde(skip)]
+  pub date: i64,
+  pub creation_date: i64
+}
+
+/// A payment that would settle some of the balances.
+pub struct Payment {
+  pub from_user_id: i32,
+  pub to_user_id: i32,
+  pub amount: i32
+}
+
+impl Audited for Settlement {
+  const ENTITY: &'static str = "settlement";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
+}
+
+impl Settlement {
+  pub fn new(sheet_id: i32, from_user_id: i32, to_user_id: i32) -> Settlement {
+    Settlement {
+      id: 0,
+      sheet_id,
+      from_user_id,
+      to_user_id,
+      transfer_id: 0,
+      amount: 0,
+      date: 0,
+      creation_date: date::now()
+    }
+  }
+
+  /// Inserts the settlement along with its transfer and sets their ids,
+  /// either both are written or none. The cached values of the sheets of the
+  /// transfer are left to the caller.
+  pub fn insert(&mut self, transfer: &mut Transfer) -> Result<()> {
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    transfer.insert_in(&transaction)?;
+
+    transaction.execute("
+      insert into settlements (sheet_id, from_user_id, to_user_id, transfer_id, creation_date)
+      values (?1, ?2, ?3, ?4, ?5)
+    ", params![self.sheet_id, self.from_user_id, self.to_user_id, transfer.id, self.creation_date])?;
+
+    let id = transaction.last_insert_rowid() as i32;
+
+    transaction.commit()?;
+
+    self.id = id;
+    self.transfer_id = transfer.id;
+    self.amount = transfer.amount;
+    self.date = transfer.date;
+
+    Ok(())
+  }
+
+  /// The settlements of the sheet, the latest first.
+  pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Settlement>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select settlements.id, settlements.sheet_id, from_user_id, to_user_id, transfer_id,
+        expenses.amount, expenses.date, settlements.creation_date
+      from settlements
+      join transfers on transfers.id = transfer_id
+      join expenses on expenses.id = transfers.expense_id
+      where settlements.sheet_id = ?1
+      order by expenses.date desc, settlements.id desc
+    ")?;
+
+    let settlements = query.query_map(params![sheet_id], |row| {
+      Ok(
+        Settlement {
+          id: row.get(0)?,
+          sheet_id: row.get(1)?,
+          from_user_id: row.get(2)?,
+          to_user_id: row.get(3)?,
+          transfer_id: row.get(4)?,
+          amount: row.get(5)?,
+          date: row.get(6)?,
+          creation_date: row.get(7)?
+        }
+      )
+    })?;
+
+    settlements.collect()
+  }
+}
+
+/// What each user is owed, negative when they owe: the payer of a split
+/// expense is owed the parts of the others, and a settlement counts for the
+/// user who paid it and against the one who received it.
+pub fn balances(splits: &[Split], settlements: &[Settlement]) -> BTreeMap<i32, i32> {
+  let mut balances = BTreeMap::new();
+
+  for split in splits {
+    for (user_id, part) in split.parts() {
+      *balances.entry(split.paid_by).or_insert(0) += part;
+      *balances.entry(user_id).or_insert(0) -= part;
+    }
+  }
+
+  for settlement in settlements {
+    *balances.entry(settlement.from_user_id).or_insert(0) += settlement.amount;
+    *balances.entry(settlement.to_user_id).or_insert(0) -= settlement.amount;
+  }
+
+  balances
+}
+
+/// The payments settling every balance. The user who owes the most pays the
+/// one who is owed the most until one of them is settled, so there is at
+/// most one payment less than there are users with a balance.
+pub fn settle_up(balances: &BTreeMap<i32, i32>) -> Vec<Payment> {
+  let mut debtors: Vec<(i32, i32)> = balances
+    .iter()
+    .filter(|(_, balance)| **balance < 0)
+    .map(|(user_id, balance)| (*user_id, -balance))
+    .collect();
+
+  let mut creditors: Vec<(i32, i32)> = balances
+    .iter()
+    .filter(|(_, balance)| **balance > 0)
+    .map(|(user_id, balance)| (*user_id, *balance))
+    .collect();
+
+  let mut payments = Vec::new();
+
+  loop {
+    debtors.sort_by_key(|(_, owed)| -owed);
+    creditors.sort_by_key(|(_, due)| -due);
+
+    let (debtor, creditor) = match (debtors.first_mut(), creditors.first_mut()) {
+      (Some(debtor), Some(creditor)) if debtor.1 > 0 && creditor.1 > 0 => (debtor, creditor),
+      _ => break
+    };
+
+    let amount = debtor.1.min(creditor.1);
+    debtor.1 -= amount;
+    creditor.1 -= amount;
+
+    payments.push(Payment {
+      from_user_id: debtor.0,
+      to_user_id: creditor.0,
+      amount
+    });
+  }
+
+  payments
+}
+
+/// Like the splits, the users of a settlement cannot be deleted.
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("settlements", "
+    id integer primary key autoincrement,
+    sheet_id integer not null references sheets (id) on delete cascade,
+    from_user_id integer not null references users (id) on delete restrict,
+    to_user_id integer not null references users (id) on delete restrict,
+    transfer_id integer not null unique references transfers (id) on delete cascade,
+    creation_date integer not null
+  ")
+}
\ No newline at end of file
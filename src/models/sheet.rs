@@ -1,16 +1,49 @@
-use crate::constants::DATABASE_PATH;
-use crate::models::inherited_sheet;
-use crate::models::cached_sheet_value;
+use crate::models::{open_connection, add_column_if_missing, create_table_with_foreign_keys};
+use crate::models::inherited_sheet::{self, InheritedSheet};
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::category::Category;
+use crate::models::transfer::Transfer;
+use crate::models::audit_entry::Audited;
+use crate::utils::date::{self, Period};
 use rusqlite::{params, Connection, Result};
+use serde::Serialize;
 
 /// Represents a calculus sheet,
 /// it could be a month, a week or a day.
 /// Everything is tied to a sheet in the end.
 #[allow(dead_code)]
+#[derive(Serialize, Clone)]
 pub struct Sheet {
   pub id: i32,
   pub name: String,
-  pub cached_value: i32
+
+  /// derived from the rows, so it is left out of the audit log
+  #[serde(skip)]
+  pub cached_value: i32,
+
+  /// the user who created the sheet, sheets created before ownership
+  /// existed have no owner
+  pub user_id: Option<i32>,
+
+  /// when the sheet was moved to the trash
+  #[serde(skip)]
+  pub deleted_date: Option<i64>,
+
+  /// the most the expenses of the sheet should add up to, if any
+  pub budget: Option<i32>
+}
+
+impl Audited for Sheet {
+  const ENTITY: &'static str = "sheet";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.id)
+  }
 }
 
 impl Sheet {
@@ -19,55 +52,104 @@ impl Sheet {
     Sheet {
       id: 0,
       cached_value: 0,
-      name: name.to_owned()
+      name: name.to_owned(),
+      user_id: None,
+      deleted_date: None,
+      budget: None
     }
   }
 
+  /// Inserts the sheet and sets its id.
   #[allow(dead_code)]
-  pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
 
+    self.insert_in(&conn)
+  }
+
+  /// Same as `insert` on a connection of the caller, so the sheet can be
+  /// part of a larger transaction.
+  pub fn insert_in(&mut self, conn: &Connection) -> Result<()> {
     conn.execute("
       insert into sheets (
         name,
-        cached_value
+        cached_value,
+        user_id,
+        budget
       )
       values (
         ?1,
-        ?2
+        ?2,
+        ?3,
+        ?4
       )
-    ", params![self.name, self.cached_value])
-    .map(|_n| ())
+    ", params![self.name, self.cached_value, self.user_id, self.budget])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    Ok(())
   }
 
+  /// Moves the sheet to the trash along with the imports from and of it,
+  /// the sheets importing it lose its value. Its expenses and incomes stay
+  /// with it until it is restored or purged.
   #[allow(dead_code)]
   pub fn remove(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let deleted_date = date::now();
+
+    Sheet::update_inheriting_sheets(self.id, -self.cached_value)?;
+
+    let conn = open_connection()?;
+
+    conn.execute("
+      update sheets
+      set deleted_date = ?1
+      where id = ?2
+      ",
+      params![deleted_date, self.id],
+    )?;
+
+    inherited_sheet::remove_all_by_sheet_id(self.id, deleted_date)?;
+
+    Ok(())
+  }
+
+  /// Takes the sheet out of the trash with the imports that were removed
+  /// along with it. The cached values are recomputed as the sheet missed the
+  /// changes of the sheets it imports while it was in the trash.
+  pub fn restore(&self) -> Result<()> {
+    let deleted_date = match self.deleted_date {
+      Some(deleted_date) => deleted_date,
+      None => return Ok(())
+    };
+
+    let conn = open_connection()?;
 
     conn.execute("
-      delete from sheets
+      update sheets
+      set deleted_date = null
       where id = ?1
       ",
       params![self.id],
     )?;
 
-    inherited_sheet::remove_all_from_inherited_sheet_id(self.id)?;
-    inherited_sheet::remove_all_from_parent_sheet_id(self.id)?;
-    cached_sheet_value::remove_by_sheet_id(self.id)?;
+    inherited_sheet::restore_all_by_sheet_id(self.id, deleted_date)?;
 
-    Ok(())
+    Sheet::recompute_all_cached_values().map(|_count| ())
   }
 
   pub fn update(&self) -> Result<()> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     conn.execute("
       update sheets
       set name = ?1,
-          cached_value = ?2
-      where id = ?3
+          cached_value = ?2,
+          user_id = ?3,
+          budget = ?4
+      where id = ?5
       ",
-      params![self.name, self.cached_value, self.id],
+      params![self.name, self.cached_value, self.user_id, self.budget, self.id],
     )?;
 
     Ok(())
@@ -75,12 +157,12 @@ impl Sheet {
 
   #[allow(dead_code)]
   pub fn get_by_name(key: &str) -> Result<Option<Sheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, cached_value
+      select id, name, cached_value, user_id, deleted_date, budget
       from sheets
-      where name = ?1
+      where name = ?1 and deleted_date is null
     ")?;
 
     let mut configs = query.query_map(params![key], |row| {
@@ -88,7 +170,10 @@ impl Sheet {
         Sheet {
           id: row.get(0)?,
           name: row.get(1)?,
-          cached_value: row.get(2)?
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
         }
       )
     })?;
@@ -97,12 +182,38 @@ impl Sheet {
   }
 
   pub fn get_by_id(id: i32) -> Result<Option<Sheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, cached_value
+      select id, name, cached_value, user_id, deleted_date, budget
       from sheets
-      where id = ?1
+      where id = ?1 and deleted_date is null
+    ")?;
+
+    let mut configs = query.query_map(params![id], |row| {
+      Ok(
+        Sheet {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
+        }
+      )
+    })?;
+
+    configs.nth(0).transpose()
+  }
+
+  /// Returns the sheet only if it is in the trash.
+  pub fn get_deleted_by_id(id: i32) -> Result<Option<Sheet>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, cached_value, user_id, deleted_date, budget
+      from sheets
+      where id = ?1 and deleted_date is not null
     ")?;
 
     let mut configs = query.query_map(params![id], |row| {
@@ -110,7 +221,10 @@ impl Sheet {
         Sheet {
           id: row.get(0)?,
           name: row.get(1)?,
-          cached_value: row.get(2)?
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
         }
       )
     })?;
@@ -118,13 +232,41 @@ impl Sheet {
     configs.nth(0).transpose()
   }
 
+  /// The sheets in the trash, the latest deleted first.
+  pub fn get_all_deleted() -> Result<Vec<Sheet>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, cached_value, user_id, deleted_date, budget
+      from sheets
+      where deleted_date is not null
+      order by deleted_date desc
+    ")?;
+
+    let sheets = query.query_map(params![], |row| {
+      Ok(
+        Sheet {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
+        }
+      )
+    })?;
+
+    sheets.collect()
+  }
+
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<Sheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, cached_value
+      select id, name, cached_value, user_id, deleted_date, budget
       from sheets
+      where deleted_date is null
     ")?;
 
     let sheets = query.query_map(params![], |row| {
@@ -132,7 +274,35 @@ impl Sheet {
         Sheet {
           id: row.get(0)?,
           name: row.get(1)?,
-          cached_value: row.get(2)?
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
+        }
+      )
+    })?;
+
+    sheets.collect()
+  }
+
+  pub fn get_all_by_user_id(user_id: i32) -> Result<Vec<Sheet>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, cached_value, user_id, deleted_date, budget
+      from sheets
+      where user_id = ?1 and deleted_date is null
+    ")?;
+
+    let sheets = query.query_map(params![user_id], |row| {
+      Ok(
+        Sheet {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
         }
       )
     })?;
@@ -142,13 +312,15 @@ impl Sheet {
 
   #[allow(dead_code)]
   pub fn get_all_sheets_by_parent_sheet_id(sheet_id: i32) -> Result<Vec<Sheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, cached_value
+      select id, name, cached_value, user_id, sheets.deleted_date, budget
       from sheets
       join inherited_sheets on inherited_sheet_id = id
       where parent_sheet_id = ?1
+        and sheets.deleted_date is null
+        and inherited_sheets.deleted_date is null
     ")?;
 
     let sheets = query.query_map(params![sheet_id], |row| {
@@ -156,7 +328,10 @@ impl Sheet {
         Sheet {
           id: row.get(0)?,
           name: row.get(1)?,
-          cached_value: row.get(2)?
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
         }
       )
     })?;
@@ -165,13 +340,15 @@ impl Sheet {
   }
 
   pub fn get_all_sheets_by_inherited_sheet_id(inherited_sheet_id: i32) -> Result<Vec<Sheet>> {
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, name, cached_value
+      select id, name, cached_value, user_id, sheets.deleted_date, budget
       from sheets
       join inherited_sheets on parent_sheet_id = id
       where inherited_sheet_id = ?1
+        and sheets.deleted_date is null
+        and inherited_sheets.deleted_date is null
     ")?;
 
     let sheets = query.query_map(params![inherited_sheet_id], |row| {
@@ -179,7 +356,10 @@ impl Sheet {
         Sheet {
           id: row.get(0)?,
           name: row.get(1)?,
-          cached_value: row.get(2)?
+          cached_value: row.get(2)?,
+          user_id: row.get(3)?,
+          deleted_date: row.get(4)?,
+          budget: row.get(5)?
         }
       )
     })?;
@@ -203,22 +383,25 @@ impl Sheet {
     Sheet::update_inheriting_sheets(self.id, -value)
   }
 
+  /// Adds `change` to the cached value of every sheet importing the sheet,
+  /// directly or through other sheets. A sheet reached by several paths
+  /// receives the change once per path, like in `recompute_all_cached_values`,
+  /// but never twice on the same path so an inheritance loop ends.
   pub fn update_inheriting_sheets(first_sheet_id: i32, change: i32) -> Result<()> {
     use std::collections::VecDeque;
 
-    let mut sheets_to_update: VecDeque<Sheet> = VecDeque::new();
+    // every sheet is queued with the ids of the sheets visited to reach it
+    let mut sheets_to_update: VecDeque<(Sheet, Vec<i32>)> = VecDeque::new();
 
     println!("starting nested updating sequence, change : {}", change);
 
     for sheet in Sheet::get_all_sheets_by_inherited_sheet_id(first_sheet_id)? {
       println!("adding sheet {} to update queue", sheet.name);
 
-      sheets_to_update.push_back(sheet);
+      sheets_to_update.push_back((sheet, vec![first_sheet_id]));
     }
 
-    let mut current_sheet = sheets_to_update.pop_front();
-
-    let conn = Connection::open(DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
       update sheets
@@ -226,35 +409,272 @@ impl Sheet {
       where id = ?2
     ")?;
 
-    while current_sheet.is_some() {
-      if let Some(sheet) = current_sheet {
-        println!("updating sheet {}", sheet.name);
+    while let Some((sheet, mut visited)) = sheets_to_update.pop_front() {
+      if visited.contains(&sheet.id) {
+        println!("sheet {} is part of an inheritance loop, skipping it", sheet.name);
+
+        continue;
+      }
+
+      println!("updating sheet {}", sheet.name);
+
+      query.execute(params![change, sheet.id])?;
+      visited.push(sheet.id);
+
+      for child_sheet in Sheet::get_all_sheets_by_inherited_sheet_id(sheet.id)? {
+        println!("adding sheet {} to update queue", child_sheet.name);
+
+        sheets_to_update.push_back((child_sheet, visited.clone()));
+      }
+    }
 
-        query.execute(params![change, sheet.id])?;
+    Ok(())
+  }
 
-        for child_sheet in Sheet::get_all_sheets_by_inherited_sheet_id(sheet.id)? {
-          println!("adding sheet {} to update queue", child_sheet.name);
-          
-          sheets_to_update.push_back(child_sheet);
+  /// Recomputes the cached value of every sheet from its expenses, incomes
+  /// and inherited sheets. Sheets are processed from the bottom of the
+  /// inheritance tree to the top using a queue, the sheets that are part of
+  /// an inheritance loop only receive the values of the sheets outside of it.
+  ///
+  /// Returns the number of sheets that were updated.
+  pub fn recompute_all_cached_values() -> Result<usize> {
+    use std::collections::{HashMap, VecDeque};
+
+    let sheets = Sheet::get_all()?;
+    let mut values: HashMap<i32, i32> = sheets.iter().map(|sheet| (sheet.id, 0)).collect();
+
+    let conn = open_connection()?;
+
+    for (table, sign) in &[("incomes", 1), ("expenses", -1)] {
+      let mut query = conn.prepare(&format!("
+        select sheet_id, sum(amount)
+        from {}
+        where deleted_date is null
+        group by sheet_id
+      ", table))?;
+
+      let totals = query.query_map(params![], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?))
+      })?;
+
+      for total in totals {
+        let (sheet_id, amount) = total?;
+
+        if let Some(value) = values.get_mut(&sheet_id) {
+          *value += sign * amount;
         }
       }
+    }
+
+    // for every sheet, the number of inherited sheets whose value is not known yet
+    let mut pending_children: HashMap<i32, usize> = HashMap::new();
+    let mut parents: HashMap<i32, Vec<i32>> = HashMap::new();
+
+    for link in InheritedSheet::get_all()? {
+      if !values.contains_key(&link.parent_sheet_id) || !values.contains_key(&link.inherited_sheet_id) {
+        continue;
+      }
 
-      current_sheet = sheets_to_update.pop_front();
+      *pending_children.entry(link.parent_sheet_id).or_insert(0) += 1;
+      parents.entry(link.inherited_sheet_id).or_default().push(link.parent_sheet_id);
     }
 
-    Ok(())
+    let mut sheets_to_update: VecDeque<i32> = sheets
+      .iter()
+      .map(|sheet| sheet.id)
+      .filter(|id| !pending_children.contains_key(id))
+      .collect();
+
+    while let Some(sheet_id) = sheets_to_update.pop_front() {
+      let value = values[&sheet_id];
+
+      for parent_id in parents.get(&sheet_id).map(|ids| ids.as_slice()).unwrap_or(&[]) {
+        *values.get_mut(parent_id).unwrap() += value;
+
+        let pending = pending_children.get_mut(parent_id).unwrap();
+        *pending -= 1;
+
+        if *pending == 0 {
+          sheets_to_update.push_back(*parent_id);
+        }
+      }
+    }
+
+    for sheet in &sheets {
+      if pending_children.get(&sheet.id).map(|n| *n > 0).unwrap_or(false) {
+        println!("sheet {} is part of an inheritance loop, its value is partial", sheet.name);
+      }
+    }
+
+    let mut query = conn.prepare("
+      update sheets
+      set cached_value = ?1
+      where id = ?2
+    ")?;
+
+    for sheet in &sheets {
+      query.execute(params![values[&sheet.id], sheet.id])?;
+    }
+
+    Ok(sheets.len())
+  }
+
+  /// Inserts the sheet along with the rows and sets its id, the rows are
+  /// moved to it and are not cleared in their account anymore. The imports
+  /// of sheets that no longer exist are dropped, the value of the sheet is
+  /// computed from what remains. Nothing is written if one of the rows fails.
+  pub fn insert_with_rows(&mut self, rows: &mut SheetRows) -> Result<()> {
+    use std::collections::HashMap;
+
+    // the imported sheets are fetched before anything is written, the value
+    // is known before the sheet is inserted
+    let mut inherited_sheets = Vec::new();
+    let mut imported_value = 0;
+
+    for inherited_sheet in rows.inherited_sheets.drain(..) {
+      if let Some(child_sheet) = Sheet::get_by_id(inherited_sheet.inherited_sheet_id)? {
+        imported_value += child_sheet.cached_value;
+        inherited_sheets.push(inherited_sheet);
+      }
+    }
+
+    rows.inherited_sheets = inherited_sheets;
+
+    // no sheet imports the new one yet, there is nothing to propagate
+    self.cached_value = imported_value
+      + rows.incomes.iter().map(|income| income.amount).sum::<i32>()
+      - rows.expenses.iter().map(|expense| expense.amount).sum::<i32>();
+
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    self.insert_in(&transaction)?;
+
+    // the expenses refer to the categories by their id in the rows
+    let mut category_ids: HashMap<i32, i32> = HashMap::new();
+
+    for category in rows.categories.iter_mut() {
+      let row_id = category.id;
+
+      category.sheet_id = self.id;
+      category.insert_in(&transaction)?;
+
+      category_ids.insert(row_id, category.id);
+    }
+
+    for expense in rows.expenses.iter_mut() {
+      expense.sheet_id = self.id;
+      expense.category_id = expense.category_id.and_then(|id| category_ids.get(&id).copied());
+      expense.cleared_date = None;
+      expense.insert_in(&transaction)?;
+    }
+
+    for income in rows.incomes.iter_mut() {
+      income.sheet_id = self.id;
+      income.cleared_date = None;
+      income.insert_in(&transaction)?;
+    }
+
+    for inherited_sheet in rows.inherited_sheets.iter_mut() {
+      inherited_sheet.parent_sheet_id = self.id;
+      inherited_sheet.insert_in(&transaction)?;
+    }
+
+    transaction.commit()
+  }
+}
+
+/// The categories, expenses, incomes and imports of a sheet, copied into a
+/// new sheet when it is duplicated or made from a template.
+pub struct SheetRows {
+  pub categories: Vec<Category>,
+  pub expenses: Vec<Expense>,
+  pub incomes: Vec<Income>,
+  pub inherited_sheets: Vec<InheritedSheet>
+}
+
+impl SheetRows {
+  /// The rows of the sheet, without the entries of its transfers as a copy
+  /// of them would have no counterpart in the other sheet.
+  pub fn get_by_sheet_id(sheet_id: i32) -> Result<SheetRows> {
+    let transfers = Transfer::get_all_by_sheet_id(sheet_id)?;
+
+    let mut expenses = Expense::get_all_by_sheet_id(sheet_id)?;
+    expenses.retain(|expense| !transfers.iter().any(|transfer| transfer.expense_id == expense.id));
+
+    let mut incomes = Income::get_all_by_sheet_id(sheet_id)?;
+    incomes.retain(|income| !transfers.iter().any(|transfer| transfer.income_id == income.id));
+
+    Ok(
+      SheetRows {
+        categories: Category::get_all_by_sheet_id(sheet_id)?,
+        expenses,
+        incomes,
+        inherited_sheets: InheritedSheet::get_all_by_sheet_id(sheet_id)?
+      }
+    )
+  }
+
+  /// Moves the dates of every row forward by the period.
+  pub fn shift_dates(&mut self, period: Period) {
+    for expense in self.expenses.iter_mut() {
+      expense.date = date::shift(expense.date, period);
+    }
+
+    for income in self.incomes.iter_mut() {
+      income.date = date::shift(income.date, period);
+    }
+
+    for inherited_sheet in self.inherited_sheets.iter_mut() {
+      inherited_sheet.date = date::shift(inherited_sheet.date, period);
+    }
   }
 }
 
+/// The sheets outlive their owner, like the sheets created before ownership
+/// existed they are left without one.
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(DATABASE_PATH)?;
+  create_table_with_foreign_keys("sheets", "
+    id integer primary key autoincrement,
+    name text not null,
+    cached_value integer not null,
+    user_id integer references users (id) on delete set null,
+    deleted_date integer,
+    budget integer
+  ")?;
+
+  add_column_if_missing("sheets", "user_id", "integer")?;
+  add_column_if_missing("sheets", "deleted_date", "integer")?;
+  add_column_if_missing("sheets", "budget", "integer")
+}
+
+/// Gives every sheet owned by `user_id` to `new_user_id`.
+pub fn transfer_ownership(user_id: i32, new_user_id: i32) -> Result<()> {
+  let conn = open_connection()?;
 
   conn.execute("
-    create table if not exists sheets (
-      id integer primary key autoincrement,
-      name text not null,
-      cached_value integer not null
-    )
-  ", params![])
-  .map(|_n| ())
+    update sheets
+    set user_id = ?1
+    where user_id = ?2
+    ",
+    params![new_user_id, user_id],
+  )?;
+
+  Ok(())
+}
+
+/// Deletes for good the sheets that were moved to the trash before
+/// `deleted_date`, their expenses, incomes and imports go with them through
+/// the foreign keys.
+///
+/// Returns the number of purged sheets.
+pub fn purge_deleted_before(deleted_date: i64) -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from sheets
+    where deleted_date < ?1
+    ",
+    params![deleted_date],
+  )
 }
\ No newline at end of file
@@ -0,0 +1,389 @@
+use crate::models::{open_connection, add_column_if_missing, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::models::category::Category;
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::inherited_sheet::InheritedSheet;
+use crate::models::sheet::{Sheet, SheetRows};
+use crate::utils::date;
+use rusqlite::{params, Result};
+use serde::Serialize;
+
+/// The rows of a sheet saved under a name, so new sheets can start from
+/// them. The dates are kept as offsets from the earliest one, a sheet made
+/// from the template places them after the date it starts at.
+#[allow(dead_code)]
+#[derive(Serialize)]
+pub struct SheetTemplate {
+  pub id: i32,
+  pub name: String,
+  pub user_id: i32,
+  pub creation_date: i64,
+
+  /// the budget of the sheets made from the template
+  pub budget: Option<i32>,
+  pub categories: Vec<TemplateCategory>,
+  pub rows: Vec<TemplateRow>,
+  pub imports: Vec<TemplateImport>
+}
+
+/// A category of a template with its target.
+#[derive(Serialize)]
+pub struct TemplateCategory {
+  pub name: String,
+  pub target: Option<i32>
+}
+
+/// An expense or an income of a template.
+#[derive(Serialize)]
+pub struct TemplateRow {
+  /// either `expense` or `income`
+  pub kind: String,
+  pub name: String,
+  pub amount: i32,
+  pub offset: i64,
+
+  /// the name of the category of an expense
+  pub category: Option<String>
+}
+
+/// A sheet imported by the sheets made from a template.
+#[derive(Serialize)]
+pub struct TemplateImport {
+  pub inherited_sheet_id: i32,
+  pub offset: i64
+}
+
+impl Audited for SheetTemplate {
+  const ENTITY: &'static str = "sheet_template";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    None
+  }
+}
+
+impl SheetTemplate {
+  /// A template of the sheet and its rows, owned by `user_id`.
+  pub fn from_sheet(name: &str, user_id: i32, sheet: &Sheet, rows: &SheetRows) -> SheetTemplate {
+    let start = rows.expenses.iter().map(|expense| expense.date)
+      .chain(rows.incomes.iter().map(|income| income.date))
+      .chain(rows.inherited_sheets.iter().map(|inherited_sheet| inherited_sheet.date))
+      .min()
+      .unwrap_or(0);
+
+    let expenses = rows.expenses.iter().map(|expense| TemplateRow {
+      kind: String::from("expense"),
+      name: expense.name.clone(),
+      amount: expense.amount,
+      offset: expense.date - start,
+      category: rows.categories.iter()
+        .find(|category| Some(category.id) == expense.category_id)
+        .map(|category| category.name.clone())
+    });
+
+    let incomes = rows.incomes.iter().map(|income| TemplateRow {
+      kind: String::from("income"),
+      name: income.name.clone(),
+      amount: income.amount,
+      offset: income.date - start,
+      category: None
+    });
+
+    SheetTemplate {
+      id: 0,
+      name: name.to_owned(),
+      user_id,
+      creation_date: date::now(),
+      budget: sheet.budget,
+      categories: rows.categories.iter().map(|category| TemplateCategory {
+        name: category.name.clone(),
+        target: category.target
+      })
+      .collect(),
+      rows: expenses.chain(incomes).collect(),
+      imports: rows.inherited_sheets.iter().map(|inherited_sheet| TemplateImport {
+        inherited_sheet_id: inherited_sheet.inherited_sheet_id,
+        offset: inherited_sheet.date - start
+      })
+      .collect()
+    }
+  }
+
+  /// The rows of a sheet made from the template and starting at `start`,
+  /// they are not tied to any sheet yet. The categories are numbered from 1
+  /// in the order of the template.
+  pub fn to_rows(&self, start: i64) -> SheetRows {
+    let mut rows = SheetRows {
+      categories: Vec::new(),
+      expenses: Vec::new(),
+      incomes: Vec::new(),
+      inherited_sheets: Vec::new()
+    };
+
+    for (index, template_category) in self.categories.iter().enumerate() {
+      let mut category = Category::new(&template_category.name, 0);
+      category.id = index as i32 + 1;
+      category.target = template_category.target;
+
+      rows.categories.push(category);
+    }
+
+    for row in &self.rows {
+      if row.kind == "expense" {
+        let mut expense = Expense::new(&row.name, row.amount, 0, start + row.offset);
+        expense.category_id = rows.categories.iter()
+          .find(|category| Some(&category.name) == row.category.as_ref())
+          .map(|category| category.id);
+
+        rows.expenses.push(expense);
+      }
+      else {
+        rows.incomes.push(Income::new(&row.name, row.amount, 0, start + row.offset));
+      }
+    }
+
+    for import in &self.imports {
+      rows.inherited_sheets.push(InheritedSheet::new(0, import.inherited_sheet_id, start + import.offset));
+    }
+
+    rows
+  }
+
+  /// Inserts the template with its rows and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into sheet_templates (
+        name,
+        user_id,
+        creation_date,
+        budget
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4
+      )
+    ", params![self.name, self.user_id, self.creation_date, self.budget])?;
+
+    self.id = conn.last_insert_rowid() as i32;
+
+    let mut query = conn.prepare("
+      insert into sheet_template_categories (template_id, name, target)
+      values (?1, ?2, ?3)
+    ")?;
+
+    for category in &self.categories {
+      query.execute(params![self.id, category.name, category.target])?;
+    }
+
+    let mut query = conn.prepare("
+      insert into sheet_template_rows (template_id, kind, name, amount, date_offset, category)
+      values (?1, ?2, ?3, ?4, ?5, ?6)
+    ")?;
+
+    for row in &self.rows {
+      query.execute(params![self.id, row.kind, row.name, row.amount, row.offset, row.category])?;
+    }
+
+    let mut query = conn.prepare("
+      insert into sheet_template_imports (template_id, inherited_sheet_id, date_offset)
+      values (?1, ?2, ?3)
+    ")?;
+
+    for import in &self.imports {
+      query.execute(params![self.id, import.inherited_sheet_id, import.offset])?;
+    }
+
+    Ok(())
+  }
+
+  /// Deletes the template, its rows go with it through the foreign keys.
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from sheet_templates
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<SheetTemplate>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, user_id, creation_date, budget
+      from sheet_templates
+      where id = ?1
+    ")?;
+
+    let mut templates = query.query_map(params![id], |row| {
+      Ok(
+        SheetTemplate {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          user_id: row.get(2)?,
+          creation_date: row.get(3)?,
+          budget: row.get(4)?,
+          categories: Vec::new(),
+          rows: Vec::new(),
+          imports: Vec::new()
+        }
+      )
+    })?;
+
+    match templates.nth(0).transpose()? {
+      Some(mut template) => {
+        template.load_rows()?;
+
+        Ok(Some(template))
+      },
+      None => Ok(None)
+    }
+  }
+
+  /// The templates of the user with their rows, sorted by name.
+  pub fn get_all_by_user_id(user_id: i32) -> Result<Vec<SheetTemplate>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, name, user_id, creation_date, budget
+      from sheet_templates
+      where user_id = ?1
+      order by name
+    ")?;
+
+    let templates = query.query_map(params![user_id], |row| {
+      Ok(
+        SheetTemplate {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          user_id: row.get(2)?,
+          creation_date: row.get(3)?,
+          budget: row.get(4)?,
+          categories: Vec::new(),
+          rows: Vec::new(),
+          imports: Vec::new()
+        }
+      )
+    })?;
+
+    let mut templates = templates.collect::<Result<Vec<SheetTemplate>>>()?;
+
+    for template in templates.iter_mut() {
+      template.load_rows()?;
+    }
+
+    Ok(templates)
+  }
+
+  fn load_rows(&mut self) -> Result<()> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select name, target
+      from sheet_template_categories
+      where template_id = ?1
+      order by rowid
+    ")?;
+
+    let categories = query.query_map(params![self.id], |row| {
+      Ok(
+        TemplateCategory {
+          name: row.get(0)?,
+          target: row.get(1)?
+        }
+      )
+    })?;
+
+    self.categories = categories.collect::<Result<Vec<TemplateCategory>>>()?;
+
+    let mut query = conn.prepare("
+      select kind, name, amount, date_offset, category
+      from sheet_template_rows
+      where template_id = ?1
+      order by id
+    ")?;
+
+    let rows = query.query_map(params![self.id], |row| {
+      Ok(
+        TemplateRow {
+          kind: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          offset: row.get(3)?,
+          category: row.get(4)?
+        }
+      )
+    })?;
+
+    self.rows = rows.collect::<Result<Vec<TemplateRow>>>()?;
+
+    // the imports of sheets that are in the trash are not made again
+    let mut query = conn.prepare("
+      select inherited_sheet_id, date_offset
+      from sheet_template_imports
+      join sheets on sheets.id = inherited_sheet_id
+      where template_id = ?1 and sheets.deleted_date is null
+    ")?;
+
+    let imports = query.query_map(params![self.id], |row| {
+      Ok(
+        TemplateImport {
+          inherited_sheet_id: row.get(0)?,
+          offset: row.get(1)?
+        }
+      )
+    })?;
+
+    self.imports = imports.collect::<Result<Vec<TemplateImport>>>()?;
+
+    Ok(())
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("sheet_templates", "
+    id integer primary key autoincrement,
+    name text not null,
+    user_id integer not null references users (id) on delete cascade,
+    creation_date datetime not null,
+    budget integer
+  ")?;
+
+  add_column_if_missing("sheet_templates", "budget", "integer")?;
+
+  create_table_with_foreign_keys("sheet_template_categories", "
+    template_id integer not null references sheet_templates (id) on delete cascade,
+    name text not null,
+    target integer
+  ")?;
+
+  create_table_with_foreign_keys("sheet_template_rows", "
+    id integer primary key autoincrement,
+    template_id integer not null references sheet_templates (id) on delete cascade,
+    kind text not null,
+    name text not null,
+    amount integer not null,
+    date_offset integer not null,
+    category text
+  ")?;
+
+  add_column_if_missing("sheet_template_rows", "category", "text")?;
+
+  create_table_with_foreign_keys("sheet_template_imports", "
+    template_id integer not null references sheet_templates (id) on delete cascade,
+    inherited_sheet_id integer not null references sheets (id) on delete cascade,
+    date_offset integer not null
+  ")
+}
\ No newline at end of file
@@ -0,0 +1,257 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use rusqlite::{params, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// The ways an expense can be split, the value of each share being ignored,
+/// a weight, or the exact amount owed.
+pub const METHODS: [&str; 3] = ["equal", "shares", "exact"];
+
+/// An expense paid by a user on behalf of several, each owing a part of it.
+#[derive(Serialize, Clone)]
+pub struct Split {
+  pub expense_id: i32,
+
+  /// the sheet of the expense
+  #[serde(skip)]
+  pub sheet_id: i32,
+
+  /// the amount of the expense
+  #[serde(skip)]
+  pub amount: i32,
+
+  /// the user who paid the expense
+  pub paid_by: i32,
+
+  /// one of `METHODS`
+  pub method: String,
+
+  /// sorted by user id
+  pub shares: Vec<Share>
+}
+
+/// A user the expense is split with, the payer included when they owe a part
+/// too.
+#[derive(Serialize, Clone)]
+pub struct Share {
+  pub user_id: i32,
+
+  /// read according to the method of the split
+  pub value: i32
+}
+
+impl Audited for Split {
+  const ENTITY: &'static str = "split";
+
+  fn audit_id(&self) -> i32 {
+    self.expense_id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.sheet_id)
+  }
+}
+
+impl Split {
+  /// Writes the split and its shares, replacing the previous ones of the
+  /// expense.
+  pub fn save(&self) -> Result<()> {
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    transaction.execute("
+      insert or replace into splits (expense_id, paid_by, method)
+      values (?1, ?2, ?3)
+    ", params![self.expense_id, self.paid_by, self.method])?;
+
+    transaction.execute("
+      delete from split_shares
+      where expense_id = ?1
+    ", params![self.expense_id])?;
+
+    for share in &self.shares {
+      transaction.execute("
+        insert into split_shares (expense_id, user_id, value)
+        values (?1, ?2, ?3)
+      ", params![self.expense_id, share.user_id, share.value])?;
+    }
+
+    transaction.commit()
+  }
+
+  /// Deletes the split, the expense is left as it is.
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from splits
+      where expense_id = ?1
+      ",
+      params![self.expense_id],
+    )?;
+
+    Ok(())
+  }
+
+  pub fn get_by_expense_id(expense_id: i32) -> Result<Option<Split>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select expense_id, sheet_id, amount, paid_by, method
+      from splits
+      join expenses on expenses.id = expense_id
+      where expense_id = ?1
+    ")?;
+
+    let mut splits = query.query_map(params![expense_id], |row| {
+      Ok(
+        Split {
+          expense_id: row.get(0)?,
+          sheet_id: row.get(1)?,
+          amount: row.get(2)?,
+          paid_by: row.get(3)?,
+          method: row.get(4)?,
+          shares: Vec::new()
+        }
+      )
+    })?;
+
+    let mut split = match splits.nth(0).transpose()? {
+      Some(split) => split,
+      None => return Ok(None)
+    };
+
+    let mut query = conn.prepare("
+      select user_id, value
+      from split_shares
+      where expense_id = ?1
+      order by user_id
+    ")?;
+
+    let shares = query.query_map(params![expense_id], |row| {
+      Ok(
+        Share {
+          user_id: row.get(0)?,
+          value: row.get(1)?
+        }
+      )
+    })?;
+
+    split.shares = shares.collect::<Result<Vec<Share>>>()?;
+
+    Ok(Some(split))
+  }
+
+  /// The splits of the expenses of the sheet, those in the trash are left out.
+  pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Split>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select split_shares.expense_id, user_id, value
+      from split_shares
+      join expenses on expenses.id = split_shares.expense_id
+      where sheet_id = ?1
+      order by user_id
+    ")?;
+
+    let rows = query.query_map(params![sheet_id], |row| {
+      Ok((row.get::<_, i32>(0)?, Share { user_id: row.get(1)?, value: row.get(2)? }))
+    })?;
+
+    let mut shares: HashMap<i32, Vec<Share>> = HashMap::new();
+
+    for row in rows {
+      let (expense_id, share) = row?;
+
+      shares.entry(expense_id).or_default().push(share);
+    }
+
+    let mut query = conn.prepare("
+      select expense_id, sheet_id, amount, paid_by, method
+      from splits
+      join expenses on expenses.id = expense_id
+      where sheet_id = ?1 and deleted_date is null
+      order by date, expense_id
+    ")?;
+
+    let splits = query.query_map(params![sheet_id], |row| {
+      Ok(
+        Split {
+          expense_id: row.get(0)?,
+          sheet_id: row.get(1)?,
+          amount: row.get(2)?,
+          paid_by: row.get(3)?,
+          method: row.get(4)?,
+          shares: Vec::new()
+        }
+      )
+    })?;
+
+    splits
+      .map(|split| split.map(|mut split| {
+        split.shares = shares.remove(&split.expense_id).unwrap_or_default();
+        split
+      }))
+      .collect()
+  }
+
+  /// What each user of the split owes for the expense, in the order of the
+  /// shares. The parts of an equal or weighted split are rounded down, what
+  /// is left is added a unit at a time to the first users. Exact amounts no
+  /// longer adding up to the expense, after its amount changed, are read as
+  /// weights.
+  pub fn parts(&self) -> Vec<(i32, i32)> {
+    let weights: Vec<i64> = self.shares
+      .iter()
+      .map(|share| if self.method == "equal" { 1 } else { share.value as i64 })
+      .collect();
+
+    let total_weight: i64 = weights.iter().sum();
+
+    if self.method == "exact" && total_weight == self.amount as i64 {
+      return self.shares.iter().map(|share| (share.user_id, share.value)).collect();
+    }
+
+    if total_weight == 0 {
+      return Vec::new();
+    }
+
+    let mut parts: Vec<(i32, i32)> = self.shares
+      .iter()
+      .zip(&weights)
+      .map(|(share, weight)| (share.user_id, (self.amount as i64 * weight / total_weight) as i32))
+      .collect();
+
+    let mut left = self.amount - parts.iter().map(|(_, part)| part).sum::<i32>();
+
+    for (_, part) in parts.iter_mut() {
+      if left == 0 {
+        break;
+      }
+
+      *part += 1;
+      left -= 1;
+    }
+
+    parts
+  }
+}
+
+/// The shares go away with their split, which goes away with its expense.
+/// The users cannot be deleted while they paid or share a split, it would
+/// change the balances of the others.
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("splits", "
+    expense_id integer primary key references expenses (id) on delete cascade,
+    paid_by integer not null references users (id) on delete restrict,
+    method text not null
+  ")?;
+
+  create_table_with_foreign_keys("split_shares", "
+    expense_id integer not null references splits (expense_id) on delete cascade,
+    user_id integer not null references users (id) on delete restrict,
+    value integer not null,
+    primary key (expense_id, user_id)
+  ")
+}
\ No newline at end of file
@@ -0,0 +1,254 @@
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::models::audit_entry::Audited;
+use crate::utils::date;
+use rusqlite::{params, Result, Transaction};
+use serde::Serialize;
+
+/// Some money moved from a sheet to another. It is made of an expense in the
+/// sheet it leaves and an income in the one it goes to, written and removed
+/// together so both sheets stay balanced.
+#[derive(Serialize, Clone)]
+pub struct Transfer {
+  pub id: i32,
+  pub name: String,
+  pub amount: i32,
+  pub date: i64,
+  pub from_sheet_id: i32,
+  pub to_sheet_id: i32,
+
+  /// the expense in the sheet the money leaves
+  pub expense_id: i32,
+
+  /// the income in the sheet the money goes to
+  pub income_id: i32,
+
+  /// the user who created the transfer
+  pub user_id: Option<i32>,
+  pub creation_date: i64
+}
+
+impl Audited for Transfer {
+  const ENTITY: &'static str = "transfer";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    Some(self.from_sheet_id)
+  }
+}
+
+impl Transfer {
+  pub fn new(name: &str, amount: i32, date: i64, from_sheet_id: i32, to_sheet_id: i32) -> Transfer {
+    Transfer {
+      id: 0,
+      name: name.to_owned(),
+      amount,
+      date,
+      from_sheet_id,
+      to_sheet_id,
+      expense_id: 0,
+      income_id: 0,
+      user_id: None,
+      creation_date: date::now()
+    }
+  }
+
+  /// Inserts the transfer along with its expense and income and sets their
+  /// ids, either all of them are written or none. The cached values of the
+  /// sheets are left to the caller.
+  pub fn insert(&mut self) -> Result<()> {
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    self.insert_in(&transaction)?;
+
+    transaction.commit()
+  }
+
+  /// Same as `insert` inside a transaction of the caller, for the rows
+  /// written along with the transfer.
+  pub fn insert_in(&mut self, transaction: &Transaction) -> Result<()> {
+    transaction.execute("
+      insert into expenses (name, amount, date, sheet_id)
+      values (?1, ?2, ?3, ?4)
+    ", params![self.name, self.amount, self.date, self.from_sheet_id])?;
+
+    self.expense_id = transaction.last_insert_rowid() as i32;
+
+    transaction.execute("
+      insert into incomes (name, amount, date, sheet_id)
+      values (?1, ?2, ?3, ?4)
+    ", params![self.name, self.amount, self.date, self.to_sheet_id])?;
+
+    self.income_id = transaction.last_insert_rowid() as i32;
+
+    transaction.execute("
+      insert into transfers (expense_id, income_id, user_id, creation_date)
+      values (?1, ?2, ?3, ?4)
+    ", params![self.expense_id, self.income_id, self.user_id, self.creation_date])?;
+
+    self.id = transaction.last_insert_rowid() as i32;
+
+    Ok(())
+  }
+
+  /// Writes the transfer to both of its entries at once. The cached values
+  /// of the sheets are left to the caller.
+  pub fn update(&self) -> Result<()> {
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    transaction.execute("
+      update expenses
+      set name = ?1,
+          amount = ?2,
+          date = ?3,
+          sheet_id = ?4
+      where id = ?5
+      ",
+      params![self.name, self.amount, self.date, self.from_sheet_id, self.expense_id],
+    )?;
+
+    transaction.execute("
+      update incomes
+      set name = ?1,
+          amount = ?2,
+          date = ?3,
+          sheet_id = ?4
+      where id = ?5
+      ",
+      params![self.name, self.amount, self.date, self.to_sheet_id, self.income_id],
+    )?;
+
+    transaction.commit()
+  }
+
+  /// Deletes the transfer and both of its entries for good, they do not go
+  /// to the trash where they could be restored one without the other. The
+  /// cached values of the sheets are left to the caller.
+  pub fn remove(&self) -> Result<()> {
+    let mut conn = open_connection()?;
+    let transaction = conn.transaction()?;
+
+    transaction.execute("
+      delete from transfers
+      where id = ?1
+      ",
+      params![self.id],
+    )?;
+
+    transaction.execute("
+      delete from expenses
+      where id = ?1
+      ",
+      params![self.expense_id],
+    )?;
+
+    transaction.execute("
+      delete from incomes
+      where id = ?1
+      ",
+      params![self.income_id],
+    )?;
+
+    transaction.commit()
+  }
+
+  pub fn get_by_id(id: i32) -> Result<Option<Transfer>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select transfers.id, expenses.name, expenses.amount, expenses.date, expenses.sheet_id,
+        incomes.sheet_id, expense_id, income_id, transfers.user_id, transfers.creation_date
+      from transfers
+      join expenses on expenses.id = expense_id
+      join incomes on incomes.id = income_id
+      where transfers.id = ?1
+    ")?;
+
+    let mut transfers = query.query_map(params![id], |row| {
+      Ok(
+        Transfer {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          from_sheet_id: row.get(4)?,
+          to_sheet_id: row.get(5)?,
+          expense_id: row.get(6)?,
+          income_id: row.get(7)?,
+          user_id: row.get(8)?,
+          creation_date: row.get(9)?
+        }
+      )
+    })?;
+
+    transfers.nth(0).transpose()
+  }
+
+  /// The transfers leaving or reaching the sheet, the latest first.
+  pub fn get_all_by_sheet_id(sheet_id: i32) -> Result<Vec<Transfer>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select transfers.id, expenses.name, expenses.amount, expenses.date, expenses.sheet_id,
+        incomes.sheet_id, expense_id, income_id, transfers.user_id, transfers.creation_date
+      from transfers
+      join expenses on expenses.id = expense_id
+      join incomes on incomes.id = income_id
+      where expenses.sheet_id = ?1 or incomes.sheet_id = ?1
+      order by expenses.date desc, transfers.id desc
+    ")?;
+
+    let transfers = query.query_map(params![sheet_id], |row| {
+      Ok(
+        Transfer {
+          id: row.get(0)?,
+          name: row.get(1)?,
+          amount: row.get(2)?,
+          date: row.get(3)?,
+          from_sheet_id: row.get(4)?,
+          to_sheet_id: row.get(5)?,
+          expense_id: row.get(6)?,
+          income_id: row.get(7)?,
+          user_id: row.get(8)?,
+          creation_date: row.get(9)?
+        }
+      )
+    })?;
+
+    transfers.collect()
+  }
+
+  /// The id of the transfer the expense or income belongs to, `kind` being
+  /// either `expense` or `income`.
+  pub fn get_id_by_entry(kind: &str, entry_id: i32) -> Result<Option<i32>> {
+    let conn = open_connection()?;
+
+    let column = if kind == "expense" { "expense_id" } else { "income_id" };
+
+    let mut query = conn.prepare(&format!("
+      select id
+      from transfers
+      where {} = ?1
+    ", column))?;
+
+    let mut ids = query.query_map(params![entry_id], |row| row.get(0))?;
+
+    ids.nth(0).transpose()
+  }
+}
+
+/// A transfer goes away with either of its entries, when their sheet is
+/// purged from the trash the other entry stays as it is.
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("transfers", "
+    id integer primary key autoincrement,
+    expense_id integer not null unique references expenses (id) on delete cascade,
+    income_id integer not null unique references incomes (id) on delete cascade,
+    user_id integer references users (id) on delete set null,
+    creation_date integer not null
+  ")
+}
\ No newline at end of file
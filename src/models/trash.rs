@@ -0,0 +1,58 @@
+use crate::models::sheet::Sheet;
+use crate::models::expense::Expense;
+use crate::models::income::Income;
+use crate::models::inherited_sheet::InheritedSheet;
+use rusqlite::Result;
+
+/// Something that was deleted and can still be restored, until the trash is
+/// purged, see `models::purge_trash`.
+pub enum TrashItem {
+  Sheet(Sheet),
+  Expense(Expense),
+  Income(Income),
+  InheritedSheet(InheritedSheet)
+}
+
+impl TrashItem {
+  /// Everything in the trash, the latest deleted first.
+  pub fn get_all() -> Result<Vec<TrashItem>> {
+    let mut items: Vec<TrashItem> = Vec::new();
+
+    items.extend(Sheet::get_all_deleted()?.into_iter().map(TrashItem::Sheet));
+    items.extend(Expense::get_all_deleted()?.into_iter().map(TrashItem::Expense));
+    items.extend(Income::get_all_deleted()?.into_iter().map(TrashItem::Income));
+    items.extend(InheritedSheet::get_all_deleted()?.into_iter().map(TrashItem::InheritedSheet));
+
+    items.sort_by_key(|item| -item.deleted_date());
+
+    Ok(items)
+  }
+
+  /// What was deleted after `deleted_date`, to offer undoing it.
+  pub fn get_all_deleted_since(deleted_date: i64) -> Result<Vec<TrashItem>> {
+    let items = TrashItem::get_all()?;
+
+    Ok(items.into_iter().filter(|item| item.deleted_date() > deleted_date).collect())
+  }
+
+  pub fn deleted_date(&self) -> i64 {
+    let deleted_date = match self {
+      TrashItem::Sheet(sheet) => sheet.deleted_date,
+      TrashItem::Expense(expense) => expense.deleted_date,
+      TrashItem::Income(income) => income.deleted_date,
+      TrashItem::InheritedSheet(inherited_sheet) => inherited_sheet.deleted_date
+    };
+
+    deleted_date.unwrap_or(0)
+  }
+
+  /// The sheet the item was deleted from, none for the sheets themselves.
+  pub fn sheet_id(&self) -> Option<i32> {
+    match self {
+      TrashItem::Sheet(_) => None,
+      TrashItem::Expense(expense) => Some(expense.sheet_id),
+      TrashItem::Income(income) => Some(income.sheet_id),
+      TrashItem::InheritedSheet(inherited_sheet) => Some(inherited_sheet.parent_sheet_id)
+    }
+  }
+}
@@ -0,0 +1,318 @@
+use crate::constants;
+use crate::models::{open_connection, create_table_with_foreign_keys};
+use crate::utils::date;
+use crate::utils::totp;
+use ring::digest;
+use rusqlite::{params, Result};
+use uuid::Uuid;
+
+/// The TOTP secret of a user. The secret is stored as soon as the user starts
+/// the enrollment but two-factor authentication is only `enabled` once they
+/// confirmed a first code from their authenticator app.
+pub struct TwoFactor {
+  pub user_id: i32,
+  pub secret: String,
+  pub enabled: bool,
+  pub last_used_step: i64
+}
+
+impl TwoFactor {
+  pub fn new(user_id: i32, secret: String) -> TwoFactor {
+    TwoFactor {
+      user_id,
+      secret,
+      enabled: false,
+      last_used_step: 0
+    }
+  }
+
+  /// Inserts the secret, replacing the previous one of the user.
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert or replace into two_factors (
+        user_id, secret, enabled, last_used_step
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4
+      )
+    ", params![self.user_id, self.secret, self.enabled, self.last_used_step])?;
+
+    Ok(())
+  }
+
+  pub fn get_by_user_id(user_id: i32) -> Result<Option<TwoFactor>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select user_id, secret, enabled, last_used_step
+      from two_factors
+      where user_id = ?1
+    ")?;
+
+    let mut two_factors = query.query_map(params![user_id], |row| {
+      Ok(TwoFactor {
+        user_id: row.get(0)?,
+        secret: row.get(1)?,
+        enabled: row.get(2)?,
+        last_used_step: row.get(3)?
+      })
+    })?;
+
+    two_factors.nth(0).transpose()
+  }
+
+  /// Returns the secret of the user only if two-factor authentication is
+  /// enabled, a pending enrollment does not count.
+  pub fn get_enabled_by_user_id(user_id: i32) -> Result<Option<TwoFactor>> {
+    Ok(TwoFactor::get_by_user_id(user_id)?.filter(|two_factor| two_factor.enabled))
+  }
+
+  /// Checks the code and remembers its time step so it cannot be used again.
+  pub fn verify_code(&mut self, code: &str) -> Result<bool> {
+    let step = match totp::verify(&self.secret, code, self.last_used_step) {
+      Some(step) => step,
+      None => return Ok(false)
+    };
+
+    self.last_used_step = step;
+
+    let conn = open_connection()?;
+
+    conn.execute("
+      update two_factors
+      set last_used_step = ?1
+      where user_id = ?2
+    ", params![self.last_used_step, self.user_id])?;
+
+    Ok(true)
+  }
+
+  pub fn enable(&mut self) -> Result<()> {
+    self.enabled = true;
+
+    let conn = open_connection()?;
+
+    conn.execute("
+      update two_factors
+      set enabled = 1
+      where user_id = ?1
+    ", params![self.user_id])?;
+
+    Ok(())
+  }
+}
+
+/// A pending sign in, the password was right and the user still has to
+/// give a code. The `token` is the value of the `two_factor` cookie.
+pub struct TwoFactorChallenge {
+  pub id: i32,
+  pub user_id: i32,
+  pub token: String,
+  pub attempts: i32,
+  pub expire_date: i64
+}
+
+impl TwoFactorChallenge {
+  pub fn new(user_id: i32) -> TwoFactorChallenge {
+    TwoFactorChallenge {
+      id: 0,
+      user_id,
+      token: Uuid::new_v4().to_string(),
+      attempts: 0,
+      expire_date: date::now() + constants::TWO_FACTOR_CHALLENGE_EXPIRE_DURATION_SECONDS
+    }
+  }
+
+  pub fn insert(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      insert into two_factor_challenges (
+        user_id, token, attempts, expire_date
+      )
+      values (
+        ?1,
+        ?2,
+        ?3,
+        ?4
+      )
+    ", params![self.user_id, self.token, self.attempts, self.expire_date])?;
+
+    Ok(())
+  }
+
+  pub fn remove(&self) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("
+      delete from two_factor_challenges
+      where id = ?1
+    ", params![self.id])?;
+
+    Ok(())
+  }
+
+  pub fn get_by_token(token: &str) -> Result<Option<TwoFactorChallenge>> {
+    let conn = open_connection()?;
+
+    let mut query = conn.prepare("
+      select id, user_id, token, attempts, expire_date
+      from two_factor_challenges
+      where token = ?1
+    ")?;
+
+    let mut challenges = query.query_map(params![token], |row| {
+      Ok(TwoFactorChallenge {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        token: row.get(2)?,
+        attempts: row.get(3)?,
+        expire_date: row.get(4)?
+      })
+    })?;
+
+    challenges.nth(0).transpose()
+  }
+
+  pub fn is_expired(&self) -> bool {
+    self.expire_date < date::now()
+  }
+
+  /// Counts a wrong code, the challenge is removed once
+  /// `constants::TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS` is reached and the user
+  /// has to sign in again.
+  pub fn add_failed_attempt(&mut self) -> Result<()> {
+    self.attempts += 1;
+
+    if self.attempts >= constants::TWO_FACTOR_CHALLENGE_MAX_ATTEMPTS {
+      return self.remove();
+    }
+
+    let conn = open_connection()?;
+
+    conn.execute("
+      update two_factor_challenges
+      set attempts = ?1
+      where id = ?2
+    ", params![self.attempts, self.id])?;
+
+    Ok(())
+  }
+}
+
+pub fn create_table() -> Result<()> {
+  create_table_with_foreign_keys("two_factors", "
+    user_id integer primary key references users (id) on delete cascade,
+    secret text not null,
+    enabled integer not null,
+    last_used_step integer not null
+  ")?;
+
+  create_table_with_foreign_keys("recovery_codes", "
+    id integer primary key autoincrement,
+    user_id integer not null references users (id) on delete cascade,
+    hash text not null
+  ")?;
+
+  create_table_with_foreign_keys("two_factor_challenges", "
+    id integer primary key autoincrement,
+    user_id integer not null references users (id) on delete cascade,
+    token text not null unique,
+    attempts integer not null,
+    expire_date datetime not null
+  ")
+}
+
+/// Disables two-factor authentication for the user, removing their secret,
+/// recovery codes and pending challenges.
+pub fn remove_all_by_user_id(user_id: i32) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from two_factors
+    where user_id = ?1
+  ", params![user_id])?;
+
+  conn.execute("
+    delete from recovery_codes
+    where user_id = ?1
+  ", params![user_id])?;
+
+  conn.execute("
+    delete from two_factor_challenges
+    where user_id = ?1
+  ", params![user_id])?;
+
+  Ok(())
+}
+
+/// Replaces the recovery codes of the user, only their hashes are stored so
+/// they can be shown only once.
+pub fn replace_recovery_codes(user_id: i32, codes: &[String]) -> Result<()> {
+  let mut conn = open_connection()?;
+  let transaction = conn.transaction()?;
+
+  transaction.execute("
+    delete from recovery_codes
+    where user_id = ?1
+  ", params![user_id])?;
+
+  for code in codes {
+    transaction.execute("
+      insert into recovery_codes (user_id, hash)
+      values (?1, ?2)
+    ", params![user_id, hash_recovery_code(code)])?;
+  }
+
+  transaction.commit()
+}
+
+/// Removes the recovery code if it belongs to the user, returns whether it
+/// did.
+pub fn use_recovery_code(user_id: i32, code: &str) -> Result<bool> {
+  let conn = open_connection()?;
+
+  let count = conn.execute("
+    delete from recovery_codes
+    where user_id = ?1
+      and hash = ?2
+  ", params![user_id, hash_recovery_code(code)])?;
+
+  Ok(count > 0)
+}
+
+pub fn count_recovery_codes(user_id: i32) -> Result<i64> {
+  let conn = open_connection()?;
+
+  conn.query_row("
+    select count(*)
+    from recovery_codes
+    where user_id = ?1
+  ", params![user_id], |row| row.get(0))
+}
+
+pub fn remove_expired_challenges() -> Result<usize> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    delete from two_factor_challenges
+    where expire_date < ?1
+  ", params![date::now()])
+}
+
+/// The recovery codes are random enough for a plain digest, unlike passwords
+/// they do not need a slow hash.
+fn hash_recovery_code(code: &str) -> String {
+  let normalized = code.trim().to_lowercase();
+
+  digest::digest(&digest::SHA256, normalized.as_bytes())
+    .as_ref()
+    .iter()
+    .map(|byte| format!("{:02x}", byte))
+    .collect()
+}
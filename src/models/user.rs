@@ -1,11 +1,11 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Result};
 use serde::{Serialize, Deserialize};
-use std::time::{SystemTime, Duration, UNIX_EPOCH};
-use std::ops::Add;
 use std::option::Option;
 use maud::{Render, Markup, html};
 
-use crate::constants;
+use crate::models::open_connection;
+use crate::models::audit_entry::Audited;
+use crate::models::{password_reset, session, two_factor};
 
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub enum UserRole {
@@ -23,48 +23,67 @@ impl UserRole {
     }
   }
 
+  pub fn from_name(name: &str) -> Option<UserRole> {
+    match name {
+      "guest" => Some(UserRole::Guest),
+      "admin" => Some(UserRole::Admin),
+      _ => None
+    }
+  }
+
   #[allow(dead_code)]
   pub fn to_number(&self) -> i32 {
     *self as i32
   }
+
+  pub fn to_name(self) -> &'static str {
+    match self {
+      UserRole::Admin => "admin",
+      UserRole::Guest => "guest",
+      UserRole::None => "visitor"
+    }
+  }
 }
 
 impl Render for UserRole {
   fn render(&self) -> Markup {
     html! {
-      @match *self {
-        UserRole::Admin => "admin",
-        UserRole::Guest => "guest",
-        UserRole::None => "visitor"
-      }
+      (self.to_name())
     }
   }
 }
 
+#[derive(Serialize)]
 pub struct User {
   pub id: i32,
   pub handle: String,
+
+  #[serde(skip)]
   pub password: String,
-  pub token: String,
-  pub token_expire_date: i64,
   pub role: UserRole,
 }
 
 impl Render for User {
   fn render(&self) -> Markup {
     html! {
-      ul {
-        li class="id" { "id: " (self.id) }
-        li class="handle" { "handle: " (self.handle) }
-        li class="password" { "password: " (self.password) }
-        li class="token" { "token: " (self.token) }
-        li class="token_expire_date" { "token_expire_date: " (self.token_expire_date) }
-        li class="role" { "role: " (self.role) }
-      }
+      span class="handle" { (self.handle) }
+      span class="role" { (self.role) }
     }
   }
 }
 
+impl Audited for User {
+  const ENTITY: &'static str = "user";
+
+  fn audit_id(&self) -> i32 {
+    self.id
+  }
+
+  fn audit_sheet_id(&self) -> Option<i32> {
+    None
+  }
+}
+
 impl User {
   pub fn new(
     handle: String,
@@ -75,8 +94,6 @@ impl User {
       id: 0,
       handle,
       password,
-      token: String::new(),
-      token_expire_date: 0,
       role,
     }
   }
@@ -87,7 +104,7 @@ impl User {
 
   #[allow(dead_code)]
   pub fn delete(&self) -> Result<()> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
   
     conn.execute("
       delete from users
@@ -97,8 +114,9 @@ impl User {
     Ok(())
   }
 
-  pub fn insert(&self) -> Result<()> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+  /// Inserts the user and sets its id.
+  pub fn insert(&mut self) -> Result<()> {
+    let conn = open_connection()?;
   
     conn.execute("
       insert into users (
@@ -107,27 +125,39 @@ impl User {
       values (
         ?1,
         ?2,
-        ?3,
-        ?4,
-        ?5
+        '',
+        0,
+        ?3
       )
     ", params![
       self.handle,
       self.password,
-      self.token,
-      self.token_expire_date,
       self.role as i32
     ])?;
+
+    self.id = conn.last_insert_rowid() as i32;
   
     Ok(())
   }
 
+  pub fn admin_exists() -> Result<bool> {
+    let conn = open_connection()?;
+
+    let count: i32 = conn.query_row("
+      select count(*)
+      from users
+      where role = ?1
+    ", params![UserRole::Admin as i32], |row| row.get(0))?;
+
+    Ok(count > 0)
+  }
+
   #[allow(dead_code)]
   pub fn get_all() -> Result<Vec<User>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+    let conn = open_connection()?;
 
     let mut query = conn.prepare("
-      select id, handle, password, token, token_expire_date, role
+      select id, handle, password, role
       from users
     ")?;
 
@@ -136,32 +166,28 @@ impl User {
         id: row.get(0)?,
         handle: row.get(1)?,
         password: row.get(2)?,
-        token: row.get(3)?,
-        token_expire_date: row.get(4)?,
-        role: UserRole::from_number(row.get(5)?),
+        role: UserRole::from_number(row.get(3)?),
       })
     })?;
 
     users.collect()
   }
 
-  pub fn get_by_handle(handle: &str) -> Result<Option<User>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+  pub fn get_by_id(id: i32) -> Result<Option<User>> {
+    let conn = open_connection()?;
   
     let mut query = conn.prepare("
-      select id, handle, password, token, token_expire_date, role
+      select id, handle, password, role
       from users
-      where handle = ?1
+      where id = ?1
     ")?;
   
-    let mut users = query.query_map(params![handle], |row| {
+    let mut users = query.query_map(params![id], |row| {
       Ok(User {
         id: row.get(0)?,
         handle: row.get(1)?,
         password: row.get(2)?,
-        token: row.get(3)?,
-        token_expire_date: row.get(4)?,
-        role: UserRole::from_number(row.get(5)?),
+        role: UserRole::from_number(row.get(3)?),
       })
     })?;
   
@@ -169,68 +195,129 @@ impl User {
     .transpose()
   }
 
-  pub fn get_by_token(token: &str) -> Result<Option<User>> {
-    let conn = Connection::open(constants::DATABASE_PATH)?;
+  pub fn get_by_handle(handle: &str) -> Result<Option<User>> {
+    let conn = open_connection()?;
   
     let mut query = conn.prepare("
-      select id, handle, password, token, token_expire_date, role
+      select id, handle, password, role
       from users
-      where token = ?1
+      where handle = ?1
     ")?;
   
-    let mut users = query.query_map(params![token], |row| {
+    let mut users = query.query_map(params![handle], |row| {
       Ok(User {
         id: row.get(0)?,
         handle: row.get(1)?,
         password: row.get(2)?,
-        token: row.get(3)?,
-        token_expire_date: row.get(4)?,
-        role: UserRole::from_number(row.get(5)?),
+        role: UserRole::from_number(row.get(3)?),
       })
     })?;
   
     users.nth(0)
     .transpose()
   }
+
 }
 
 pub fn create_table() -> Result<()> {
-  let conn = Connection::open(constants::DATABASE_PATH)?;
+  let conn = open_connection()?;
 
   conn.execute("
     create table if not exists users (
       id integer primary key autoincrement,
       handle text not null,
       password text not null,
+      -- unused since sessions have their own table, kept for the databases
+      -- created by older versions
       token text not null,
       token_expire_date datetime not null,
       role int not null
     )
   ", params![])?;
 
+  // the index cannot be created while older databases hold duplicates, the
+  // server refuses to start until they are deleted
+  if !get_duplicated_handles()?.is_empty() {
+    return Ok(());
+  }
+
+  conn.execute("
+    create unique index if not exists users_handle
+    on users (handle)
+  ", params![])?;
+
+  Ok(())
+}
+
+/// Returns the handles shared by several users, only databases created
+/// before handles were unique can have some.
+pub fn get_duplicated_handles() -> Result<Vec<String>> {
+  let conn = open_connection()?;
+  let mut query = conn.prepare("
+    select handle
+    from users
+    group by handle
+    having count(*) > 1
+  ")?;
+
+  let handles = query.query_map(params![], |row| row.get::<_, String>(0))?;
+
+  handles.collect()
+}
+
+pub fn set_user_password(id: i32, password: &str) -> Result<()> {
+  let conn = open_connection()?;
+
+  conn.execute("
+    update users
+    set password = ?1
+    where id = ?2
+  ", params![password, id])?;
+
   Ok(())
 }
 
-pub fn set_user_token(handle: &str, token: &str) -> Result<i64> {
-  let conn = Connection::open(constants::DATABASE_PATH)?;
-  let expiration_date = new_expiration_date();
+pub fn set_user_role(id: i32, role: UserRole) -> Result<()> {
+  let conn = open_connection()?;
 
   conn.execute("
     update users
-    set token = ?1,
-        token_expire_date = ?2
-    where handle = ?3
-  ", params![
-    token,
-    expiration_date,
-    handle
-  ])?;
-
-  Ok(expiration_date)
+    set role = ?1
+    where id = ?2
+  ", params![role as i32, id])?;
+
+  Ok(())
 }
 
+/// Tells whether the user paid or shares a split, or took part in a
+/// settlement. Such a user cannot be deleted.
+pub fn is_in_shared_expenses(id: i32) -> Result<bool> {
+  let conn = open_connection()?;
+
+  conn.query_row("
+    select
+      exists (select 1 from splits where paid_by = ?1)
+      or exists (select 1 from split_shares where user_id = ?1)
+      or exists (select 1 from settlements where from_user_id = ?1 or to_user_id = ?1)
+  ", params![id], |row| row.get(0))
+}
+
+/// Deletes the user along with all their sessions, password resets and
+/// two-factor secrets. Refuses while the user is part of splits or
+/// settlements, even in the databases whose tables still cascade.
 pub fn delete_user_by_id(id: i32) -> Result<()> {
-  let conn = Connection::open(constants::DATABASE_PATH)?;
+  if is_in_shared_expenses(id)? {
+    return Err(rusqlite::Error::SqliteFailure(
+      rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY),
+      Some(String::from("the user is part of splits or settlements"))
+    ));
+  }
+
+  session::remove_all_by_user_id(id)?;
+  password_reset::remove_all_by_user_id(id)?;
+  two_factor::remove_all_by_user_id(id)?;
+
+  let conn = open_connection()?;
 
   conn.execute("
     delete from users
@@ -239,11 +326,3 @@ pub fn delete_user_by_id(id: i32) -> Result<()> {
 
   Ok(())
 }
-
-fn new_expiration_date() -> i64 {
-  SystemTime::now()
-      .add(Duration::new(constants::USER_TOKEN_EXPIRE_DURATION_SECONDS, 0))
-      .duration_since(UNIX_EPOCH)
-      .unwrap()
-      .as_secs() as i64
-}
\ No newline at end of file
@@ -0,0 +1,121 @@
+use crate::components;
+use crate::config;
+use crate::models::account::{Account, Statement};
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let account_id = req
+    .match_info()
+    .get("account_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such account"))?;
+
+  let account = Account::get_by_id(account_id)
+    .map_err(|err| {
+      println!("error when fetching account {}", err);
+
+      AppError::internal("error when fetching account from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such account"))?;
+
+  let entries = account.get_entries()
+    .map_err(|err| {
+      println!("error when fetching account entries {}", err);
+
+      AppError::internal("error when fetching account entries from database")
+    })?;
+
+  let statements = Statement::get_all_by_account_id(account.id)
+    .map_err(|err| {
+      println!("error when fetching statements {}", err);
+
+      AppError::internal("error when fetching statements from database")
+    })?;
+
+  let currency = &config::get().default_currency;
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        h1 { (account.name) }
+        a href={"/account/" (account.id) "/edit"} { "edit" }
+      }
+      a href={"/account/" (account.id) "/statements/new"} class="button" { "add statement" span { "+" } }
+    }
+
+    section class="balances" {
+      dl {
+        dt { "kind" }
+        dd { (account.kind) }
+
+        dt { "opening balance" }
+        dd { (account.opening_balance) span { (currency) } }
+
+        dt { "balance" }
+        dd { (account.balance_at(&entries, i64::MAX, false)) span { (currency) } }
+
+        dt { "cleared balance" }
+        dd { (account.balance_at(&entries, i64::MAX, true)) span { (currency) } }
+      }
+    }
+
+    section class="statements" {
+      h4 { "statements" }
+
+      @if statements.is_empty() {
+        p { "record the balance of a bank statement to reconcile the account" }
+      }
+
+      @for statement in &statements {
+        div class="statement row" {
+          span class="date" { (date::format_date(statement.date)) }
+          span class="amount" { (statement.balance) span { (currency) } }
+          a href={"/account/" (account.id) "/reconcile?statement=" (statement.id)} { "reconcile" }
+
+          form method="post" action="/api/statements/delete-by-id" {
+            (components::csrf_field(&req))
+            input type="hidden" name="id" value=(statement.id);
+            input.link type="submit" value="delete";
+          }
+        }
+      }
+    }
+
+    section class="entries" {
+      h4 { "expenses and incomes" }
+
+      @if entries.is_empty() {
+        p { "choose this account on expenses and incomes to see them here" }
+      }
+
+      @for entry in entries.iter().rev() {
+        div class={"entry row " (entry.kind)} {
+          span class="date" { (date::format_date(entry.date)) }
+          span class="name" {
+            (entry.name) " in " a href={"/sheet/" (entry.sheet_id)} { (entry.sheet_name) }
+          }
+          span class="amount" { (entry.amount) span { (currency) } }
+          span class="cleared" {
+            @if entry.cleared_date.is_some() { "cleared" }
+          }
+        }
+      }
+    }
+  };
+
+  let view = components::page("accounts", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
@@ -0,0 +1,147 @@
+use crate::components;
+use crate::config;
+use crate::models::account::{Account, Statement};
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::{html, Markup};
+use actix_web::web::{self, HttpRequest};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ReconcileQuery {
+  /// the statement to reconcile against, the latest one when missing
+  pub statement: Option<i32>
+}
+
+pub async fn render(req: HttpRequest, query: web::Query<ReconcileQuery>, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let account_id = req
+    .match_info()
+    .get("account_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such account"))?;
+
+  let account = Account::get_by_id(account_id)
+    .map_err(|err| {
+      println!("error when fetching account {}", err);
+
+      AppError::internal("error when fetching account from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such account"))?;
+
+  let statements = Statement::get_all_by_account_id(account.id)
+    .map_err(|err| {
+      println!("error when fetching statements {}", err);
+
+      AppError::internal("error when fetching statements from database")
+    })?;
+
+  let statement = match query.statement {
+    Some(statement_id) => Some(
+      statements
+        .iter()
+        .find(|statement| statement.id == statement_id)
+        .ok_or_else(|| AppError::not_found("no such statement"))?
+    ),
+    None => statements.first()
+  };
+
+  let entries = account.get_entries()
+    .map_err(|err| {
+      println!("error when fetching account entries {}", err);
+
+      AppError::internal("error when fetching account entries from database")
+    })?;
+
+  let currency = &config::get().default_currency;
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        h1 { "Reconciling " (account.name) }
+        a href={"/account/" (account.id)} { "back to the account" }
+      }
+    }
+
+    @match statement {
+      None => {
+        p {
+          "there is no statement to reconcile against, "
+          a href={"/account/" (account.id) "/statements/new"} { "add one" }
+        }
+      },
+      Some(statement) => {
+        // the whole day of the statement is included
+        @let end_of_day = statement.date + 24 * 3600 - 1;
+        @let balance = account.balance_at(&entries, end_of_day, false);
+        @let cleared_balance = account.balance_at(&entries, end_of_day, true);
+
+        section class="balances" {
+          dl {
+            dt { "statement of " (date::format_date(statement.date)) }
+            dd { (statement.balance) span { (currency) } }
+
+            dt { "balance on that day" }
+            dd {
+              (balance) span { (currency) }
+              (difference(statement.balance - balance))
+            }
+
+            dt { "cleared balance on that day" }
+            dd {
+              (cleared_balance) span { (currency) }
+              (difference(statement.balance - cleared_balance))
+            }
+          }
+        }
+
+        form class="reconcile" method="post" action="/api/accounts/reconcile" {
+          (components::csrf_field(&req))
+          input type="hidden" name="statement_id" value=(statement.id);
+
+          p { "check the expenses and incomes that appear on the statement" }
+
+          @for entry in entries.iter().filter(|entry| entry.date <= end_of_day) {
+            @let field = format!("cleared-{}-{}", entry.kind, entry.id);
+
+            label class={"entry row " (entry.kind)} for=(field) {
+              @if entry.cleared_date.is_some() {
+                input id=(field) type="checkbox" name=(field) checked;
+              } @else {
+                input id=(field) type="checkbox" name=(field);
+              }
+              span class="date" { (date::format_date(entry.date)) }
+              span class="name" { (entry.name) " in " (entry.sheet_name) }
+              span class="amount" { (entry.amount) span { (currency) } }
+            }
+          }
+
+          input type="submit" value="save";
+        }
+      }
+    }
+  };
+
+  let view = components::page("accounts", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// How far a balance is from the one of the statement.
+fn difference(amount: i32) -> Markup {
+  html! {
+    @if amount == 0 {
+      span class="reconciled" { "matches the statement" }
+    } @else {
+      span class="difference" { "off by " (amount) }
+    }
+  }
+}
\ No newline at end of file
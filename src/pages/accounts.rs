@@ -0,0 +1,104 @@
+use crate::components;
+use crate::config;
+use crate::models::account::{Account, Statement};
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(_req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let accounts = Account::get_all()
+    .map_err(|err| {
+      println!("error when fetching accounts {}", err);
+
+      AppError::internal("error when fetching accounts from database")
+    })?;
+
+  let mut rows = Vec::new();
+
+  for account in &accounts {
+    let entries = account.get_entries()
+      .map_err(|err| {
+        println!("error when fetching account entries {}", err);
+
+        AppError::internal("error when fetching account entries from database")
+      })?;
+
+    let statement = Statement::get_all_by_account_id(account.id)
+      .map_err(|err| {
+        println!("error when fetching statements {}", err);
+
+        AppError::internal("error when fetching statements from database")
+      })?
+      .into_iter()
+      .next();
+
+    // the difference is measured on the day of the statement
+    let difference = statement
+      .as_ref()
+      .map(|statement| statement.balance - account.balance_at(&entries, statement.date + 24 * 3600 - 1, true));
+
+    rows.push((
+      account,
+      account.balance_at(&entries, i64::MAX, false),
+      account.balance_at(&entries, i64::MAX, true),
+      statement,
+      difference
+    ));
+  }
+
+  let currency = &config::get().default_currency;
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Your accounts" }
+      a href="/accounts/new" class="button" { "new account" span { "+" } }
+    }
+
+    @if accounts.is_empty() {
+      p { "add the accounts your money is in to reconcile them with the statements of the bank" }
+    }
+
+    ul class="accounts" {
+      @for (account, balance, cleared_balance, statement, difference) in &rows {
+        li class="account" {
+          div class="row" {
+            a href={"/account/" (account.id)} class="name" { (account.name) }
+            span class="amount" { (balance) span { (currency) } }
+          }
+
+          div class="row details" {
+            span { (account.kind) }
+            span { "cleared " (cleared_balance) }
+          }
+
+          @if let (Some(statement), Some(difference)) = (statement, difference) {
+            div class="row details" {
+              span { "statement of " (date::format_date(statement.date)) ": " (statement.balance) }
+
+              @if *difference == 0 {
+                span class="reconciled" { "reconciled" }
+              } @else {
+                a class="difference" href={"/account/" (account.id) "/reconcile?statement=" (statement.id)} {
+                  "off by " (difference)
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  };
+
+  let view = components::page("accounts", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
@@ -0,0 +1,103 @@
+use crate::components;
+use crate::models::user::User;
+use crate::models::sheet::Sheet;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AdminUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _admin: AdminUser) -> Result<HttpResponse, AppError> {
+  let user_id = req
+    .match_info()
+    .get("user_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such user"))?;
+
+  let user = User::get_by_id(user_id)
+    .map_err(|err| {
+      println!("error when fetching user {}", err);
+
+      AppError::internal("error when fetching user from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such user"))?;
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("users"))
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/users/delete-by-id" {
+        (components::csrf_field(&req))
+        h4 { "Deleting " (user.handle) }
+
+        input type="hidden" name="id" value=(user.id);
+
+        @match Sheet::get_all_by_user_id(user.id) {
+          Ok(sheets) => {
+            @if sheets.is_empty() {
+              p { (user.handle) " does not own any sheet." }
+              input type="hidden" name="sheets_action" value="delete";
+            } @else {
+              p { (user.handle) " owns " (sheets.len()) " sheets:" }
+              ul {
+                @for sheet in &sheets {
+                  li { (sheet.name) }
+                }
+              }
+
+              fieldset {
+                legend { "what should happen to these sheets" }
+
+                div {
+                  input id="transfer" type="radio" name="sheets_action" value="transfer" checked;
+                  label for="transfer" { "give them to" }
+
+                  select name="transfer_to" {
+                    @match User::get_all() {
+                      Ok(users) => {
+                        @for other in users.iter().filter(|other| other.id != user.id) {
+                          option value=(other.id) { (other.handle) }
+                        }
+                      },
+                      Err(e) => {
+                        "an error occured when fetching users list " (e)
+                      }
+                    }
+                  }
+                }
+
+                div {
+                  input id="delete" type="radio" name="sheets_action" value="delete";
+                  label for="delete" { "delete them" }
+                }
+              }
+            }
+          },
+
+          Err(e) => {
+            "an error occured when fetching the sheets of the user " (e)
+          }
+        }
+
+        div class="row" {
+          a href="/admin/users" { "cancel" }
+          input type="submit" value="delete";
+        }
+      }
+    }
+  };
+
+  let view = components::page("admin", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
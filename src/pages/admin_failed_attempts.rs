@@ -0,0 +1,68 @@
+use crate::components;
+use crate::models::failed_attempt::FailedAttempt;
+
+use crate::utils::req_auth::AdminUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _admin: AdminUser) -> HttpResponse {
+  let attempts_result = FailedAttempt::get_latest(200);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("failed attempts"))
+    }
+
+    p {
+      "The latest failed sign in attempts. Unlocking a handle or an ip forgets "
+      "all its failures."
+    }
+
+    @match attempts_result {
+      Ok(attempts) => {
+        div class="admin-list failed-attempts" {
+          @if attempts.is_empty() {
+            p { "no failed attempt" }
+          }
+
+          @for attempt in &attempts {
+            div.failed-attempt.element {
+              div.row {
+                (attempt)
+
+                div.actions {
+                  @if let Some(handle) = &attempt.handle {
+                    form method="post" action="/api/failed-attempts/clear" {
+                      (components::csrf_field(&req))
+                      input type="hidden" name="handle" value=(handle);
+                      input.link type="submit" value="unlock handle";
+                    }
+                  }
+
+                  form method="post" action="/api/failed-attempts/clear" {
+                    (components::csrf_field(&req))
+                    input type="hidden" name="ip" value=(attempt.ip);
+                    input.link type="submit" value="unlock ip";
+                  }
+                }
+              }
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching failed attempts list " (e)
+      }
+    }
+  };
+
+  let view = components::page("admin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -0,0 +1,42 @@
+use crate::components;
+use crate::constants::HISTORY_LENGTH;
+use crate::models::audit_entry::{AuditEntry, AuditFilter};
+
+use crate::utils::req_auth::AdminUser;
+
+use maud::html;
+use actix_web::web;
+use actix_web::HttpResponse;
+
+pub async fn render(filter: web::Query<AuditFilter>, _admin: AdminUser) -> HttpResponse {
+  let entries_result = AuditEntry::get_latest(None, &filter, HISTORY_LENGTH);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("history"))
+    }
+
+    p {
+      "The latest changes made to the sheets, the users and the invitations."
+    }
+
+    (components::audit_filter_form("/admin/history", &filter))
+
+    @match entries_result {
+      Ok(entries) => {
+        (components::audit_log(&entries))
+      },
+
+      Err(e) => {
+        "an error occured when fetching the history " (e)
+      }
+    }
+  };
+
+  let view = components::page("admin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -0,0 +1,112 @@
+use crate::api::invitations::CreateInvitationBody;
+use crate::components;
+use crate::models::user::UserRole;
+use crate::models::invitation::Invitation;
+
+use crate::utils::req_auth::AdminUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _admin: AdminUser) -> HttpResponse {
+  let view = render_form(&req, &CreateInvitationBody::default(), &FormErrors::new());
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
+
+/// The invitation form above the pending invitations, the api renders it
+/// again with the rejected handle and role.
+pub fn render_form(req: &HttpRequest, form: &CreateInvitationBody, errors: &FormErrors) -> Markup {
+  let origin = {
+    let connection_info = req.connection_info();
+
+    format!("{}://{}", connection_info.scheme(), connection_info.host())
+  };
+
+  let invitations_result = Invitation::get_all();
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("invitations"))
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/invitations" {
+        (components::csrf_field(req))
+        h4 { "Inviting someone" }
+        fieldset {
+          legend { "fill the information" }
+
+          div {
+            label for="handle" { "Handle" }
+            input id="handle" type="text" name="handle" value=(form.handle);
+            (components::field_error(errors, "handle"))
+          }
+
+          div {
+            label for="role" { "Role" }
+            select id="role" name="role" {
+              @for role in &[UserRole::Guest, UserRole::Admin] {
+                @if role.to_number() == form.role {
+                  option value=(role.to_number()) selected { (role) }
+                } @else {
+                  option value=(role.to_number()) { (role) }
+                }
+              }
+            }
+            (components::field_error(errors, "role"))
+          }
+
+          div class="row" {
+            input type="submit" value="invite";
+          }
+        }
+      }
+    }
+
+    @match invitations_result {
+      Ok(invitations) => {
+        div class="admin-list invitations" {
+          @if invitations.is_empty() {
+            p { "no pending invitation" }
+          }
+
+          @for invitation in &invitations {
+            div.invitation.element {
+              div.row {
+                (invitation)
+
+                div.actions {
+                  form method="post" action="/api/invitations/extend-by-id" {
+                    (components::csrf_field(req))
+                    input type="hidden" name="id" value=(invitation.id);
+                    input.link type="submit" value="extend";
+                  }
+
+                  form method="post" action="/api/invitations/delete-by-id" {
+                    (components::csrf_field(req))
+                    input type="hidden" name="id" value=(invitation.id);
+                    input.link type="submit" value="revoke";
+                  }
+                }
+              }
+
+              input.link-to-copy type="text" readonly value={(origin) (invitation.to_url())};
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching invitations list " (e)
+      }
+    }
+  };
+
+  components::page("admin", &content)
+}
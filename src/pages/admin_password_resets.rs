@@ -0,0 +1,68 @@
+use crate::components;
+use crate::models::password_reset::PasswordReset;
+
+use crate::utils::req_auth::AdminUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _admin: AdminUser) -> HttpResponse {
+  let origin = {
+    let connection_info = req.connection_info();
+
+    format!("{}://{}", connection_info.scheme(), connection_info.host())
+  };
+
+  let resets_result = PasswordReset::get_all();
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("password resets"))
+    }
+
+    p {
+      "Reset links are issued from the " a href="/admin/users" { "users" } " page, "
+      "send the link to the user so they can choose a new password."
+    }
+
+    @match resets_result {
+      Ok(resets) => {
+        div class="admin-list password-resets" {
+          @if resets.is_empty() {
+            p { "no pending password reset" }
+          }
+
+          @for reset in &resets {
+            div.password-reset.element {
+              div.row {
+                (reset)
+
+                div.actions {
+                  form method="post" action="/api/password-resets/delete-by-id" {
+                    (components::csrf_field(&req))
+                    input type="hidden" name="id" value=(reset.id);
+                    input.link type="submit" value="revoke";
+                  }
+                }
+              }
+
+              input.link-to-copy type="text" readonly value={(origin) (reset.to_url())};
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching password resets list " (e)
+      }
+    }
+  };
+
+  let view = components::page("admin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
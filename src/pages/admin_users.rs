@@ -0,0 +1,87 @@
+use crate::components;
+use crate::models::user::{User, UserRole};
+use crate::models::sheet::Sheet;
+use crate::models::two_factor::TwoFactor;
+
+use crate::utils::req_auth::AdminUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _admin: AdminUser) -> HttpResponse {
+  let users_result = User::get_all();
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Administration" }
+      (components::admin_menu("users"))
+    }
+
+    @match users_result {
+      Ok(users) => {
+        div class="admin-list users" {
+          @for user in &users {
+            div.user.element {
+              div.row {
+                (user)
+
+                span.sheets {
+                  @match Sheet::get_all_by_user_id(user.id) {
+                    Ok(sheets) => { (sheets.len()) " sheets" },
+                    Err(e) => { "error fetching sheets " (e) }
+                  }
+                }
+
+                div.actions {
+                  form method="post" action="/api/users/role-by-id" {
+                    (components::csrf_field(&req))
+                    input type="hidden" name="id" value=(user.id);
+
+                    select name="role" {
+                      @for role in &[UserRole::Guest, UserRole::Admin] {
+                        @if role.to_number() == user.role.to_number() {
+                          option value=(role.to_number()) selected { (role) }
+                        } @else {
+                          option value=(role.to_number()) { (role) }
+                        }
+                      }
+                    }
+
+                    input.link type="submit" value="change role";
+                  }
+
+                  @if let Ok(Some(_)) = TwoFactor::get_enabled_by_user_id(user.id) {
+                    form method="post" action="/api/users/reset-two-factor-by-id" {
+                      (components::csrf_field(&req))
+                      input type="hidden" name="id" value=(user.id);
+                      input.link type="submit" value="reset 2FA";
+                    }
+                  }
+
+                  form method="post" action="/api/password-resets" {
+                    (components::csrf_field(&req))
+                    input type="hidden" name="user_id" value=(user.id);
+                    input.link type="submit" value="reset password";
+                  }
+
+                  a href={"/admin/users/" (user.id) "/delete"} { "delete" }
+                }
+              }
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching users list " (e)
+      }
+    }
+  };
+
+  let view = components::page("admin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -0,0 +1,89 @@
+use crate::api::sheet::DuplicateSheetByIdBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+use crate::utils::date::Period;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = DuplicateSheetByIdBody {
+    id: sheet.id,
+    name: format!("{} (copy)", sheet.name),
+    shift: String::from(Period::Month.name())
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &DuplicateSheetByIdBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    div class="title-row" {
+      div class="left" {
+        h1 { "Your sheets" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/sheets/duplicate-by-id" {
+        (components::csrf_field(req))
+        h4 { "Duplicating sheet" }
+
+        input type="hidden" name="id" value=(form.id);
+
+        div {
+          label for="name" { "Name" }
+          input id="name" type="text" name="name" value=(form.name);
+          (components::field_error(errors, "name"))
+        }
+
+        div {
+          label for="shift" { "Move the dates forward by" }
+          select id="shift" name="shift" {
+            @for period in &Period::ALL {
+              @if period.name() == form.shift {
+                option value=(period.name()) selected { (period.name()) }
+              } @else {
+                option value=(period.name()) { (period.name()) }
+              }
+            }
+          }
+          (components::field_error(errors, "shift"))
+        }
+
+        div class="row" {
+          a href={"/sheet/"(form.id)} { "cancel" }
+          input type="submit" value="duplicate";
+        }
+      }
+    }
+  };
+
+  components::page("duplicate-sheet", &content)
+}
\ No newline at end of file
@@ -0,0 +1,95 @@
+use crate::api::account::UpdateAccountByIdBody;
+use crate::components;
+use crate::models::account::Account;
+use crate::pages::new_account::kind_select;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let account_id = req
+    .match_info()
+    .get("account_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such account"))?;
+
+  let account = Account::get_by_id(account_id)
+    .map_err(|err| {
+      println!("error when fetching account {}", err);
+
+      AppError::internal("error when fetching account from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such account"))?;
+
+  let form = UpdateAccountByIdBody {
+    name: account.name,
+    kind: account.kind,
+    opening_balance: account.opening_balance.to_string(),
+    id: account.id
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &UpdateAccountByIdBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Editing an account" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/accounts/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing the account" }
+        fieldset {
+          legend { "the opening balance is the one before the expenses and incomes of the account" }
+
+          input type="hidden" name="id" value=(form.id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          (kind_select(&form.kind, errors))
+
+          div {
+            label for="opening_balance" { "Opening balance" }
+            input id="opening_balance" type="number" name="opening_balance" value=(form.opening_balance);
+            (components::field_error(errors, "opening_balance"))
+          }
+
+          div class="row" {
+            a href={"/account/" (form.id)} { "cancel" }
+            input type="submit" value="update";
+          }
+        }
+      }
+
+      form method="post" action="/api/accounts/delete-by-id" {
+        (components::csrf_field(req))
+        input type="hidden" name="id" value=(form.id);
+        input type="submit" value="delete the account" class="link";
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
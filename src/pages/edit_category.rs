@@ -0,0 +1,93 @@
+use crate::api::category::UpdateCategoryByIdBody;
+use crate::components;
+use crate::models::category::Category;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let category_id = req
+    .match_info()
+    .get("category_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such category"))?;
+
+  let category = Category::get_by_id(category_id)
+    .map_err(|err| {
+      println!("error when fetching category {}", err);
+
+      AppError::internal("error when fetching category from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such category"))?;
+
+  let form = UpdateCategoryByIdBody {
+    name: category.name,
+    target: category.target.map(|target| target.to_string()).unwrap_or_default(),
+    id: category.id
+  };
+
+  let view = render_form(&req, &form, category.sheet_id, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The category form does not hold the sheet, `sheet_id` is where cancel
+/// goes back to.
+pub fn render_form(req: &HttpRequest, form: &UpdateCategoryByIdBody, sheet_id: i32, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Editing a category" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/categories/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing the category" }
+        fieldset {
+          legend { "the target is the amount planned for its expenses" }
+
+          input type="hidden" name="id" value=(form.id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="target" { "Target" }
+            input id="target" type="number" name="target" value=(form.target);
+            (components::field_error(errors, "target"))
+          }
+
+          div class="row" {
+            a href={"/sheet/" (sheet_id)} { "cancel" }
+            input type="submit" value="update";
+          }
+        }
+      }
+
+      form method="post" action="/api/categories/delete-by-id" {
+        (components::csrf_field(req))
+        input type="hidden" name="id" value=(form.id);
+        input type="submit" value="delete the category" class="link";
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
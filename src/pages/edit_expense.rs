@@ -1,61 +1,54 @@
+use crate::api::expense::UpdateExpenseByIdBody;
 use crate::components;
 use crate::models::expense::Expense;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::errors::AppError;
+use crate::utils::date;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
 
-use maud::html;
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
 
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let expense_id = req
     .match_info()
     .get("expense_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
+    .map_err(|_| AppError::not_found("no such expense"))?;
 
-  let expense_result = Expense::get_by_id(expense_id);
-  let some_expense = match expense_result {
-    Ok(some_expense) => some_expense,
-    Err(e) => {
-      let content = html! {
-        p {
-          "An error occured when trying to load edit-expense page" (e)
-        }
-      };
+  let expense = Expense::get_by_id(expense_id)
+    .map_err(|err| {
+      println!("error when fetching expense {}", err);
 
-      let view = components::page("edit-expense", &content);
-      
-      return HttpResponse::Ok()
-      .content_type("text/html")
-      .body(view.into_string());
-    }
+      AppError::internal("error when fetching expense from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such expense"))?;
+
+  let form = UpdateExpenseByIdBody {
+    name: expense.name,
+    amount: expense.amount.to_string(),
+    date: date::format_date(expense.date),
+    id: expense.id,
+    category_id: expense.category_id.map(|id| id.to_string()).unwrap_or_default(),
+    goal_id: expense.goal_id.map(|id| id.to_string()).unwrap_or_default(),
+    account_id: expense.account_id.map(|id| id.to_string()).unwrap_or_default()
   };
 
+  let view = render_form(&req, &form, expense.sheet_id, &FormErrors::new());
+  
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The expense form does not hold the sheet, `sheet_id` lists its categories
+/// and is where cancel goes back to.
+pub fn render_form(req: &HttpRequest, form: &UpdateExpenseByIdBody, sheet_id: i32, errors: &FormErrors) -> Markup {
   let content = html! {
     div class="title-row" {
       div class="left" {
@@ -63,50 +56,42 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
       }
     }
 
-    @match some_expense {
-      Some(expense) => {
+    div class="form-wrapper" {
+      form method="post" action="/api/expenses/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing expense" }
 
-        div class="form-wrapper" {
-          form method="post" action="/api/expenses/update-by-id" {
-            h4 { "Editing expense" }
+        input type="hidden" name="id" value=(form.id);
 
-            input type="hidden" name="id" value=(expense_id);
+        div {
+          label for="name" { "Name" }
+          input id="name" type="text" name="name" value=(form.name);
+          (components::field_error(errors, "name"))
+        }
 
-            div {
-              label for="name" { "Name" }
-              input id="name" type="text" name="name" value=(expense.name);
-            }
-  
-            div {
-              label for="amount" { "Amount" }
-              input id="amount" type="number" name="amount" value=(expense.amount);
-            }
-  
-            div {
-              label for="date" { "Date" }
-              input id="date" type="date" name="date" value=(expense.date);
-            }
-      
-            div class="row" {
-              a href={"/sheet/"(expense.sheet_id)} { "cancel" }
-              input type="submit" value="update";
-            }
-          }
+        div {
+          label for="amount" { "Amount" }
+          input id="amount" type="number" name="amount" value=(form.amount);
+          (components::field_error(errors, "amount"))
         }
 
-      },
-      None => {
-        div class="form-wrapper" {
-          "no expense with such id " span { (expense_id) }
-          a href="/sheets" { "go back" }
+        div {
+          label for="date" { "Date" }
+          input id="date" type="date" name="date" value=(form.date);
+          (components::field_error(errors, "date"))
+        }
+
+        (components::category_select(sheet_id, &form.category_id, errors))
+        (components::goal_select(&form.goal_id, errors))
+        (components::account_select(&form.account_id, errors))
+  
+        div class="row" {
+          a href={"/sheet/"(sheet_id)} { "cancel" }
+          input type="submit" value="update";
         }
       }
     }
   };
 
-  let view = components::page("rename-sheet", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("rename-sheet", &content)
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+use crate::api::goal::UpdateGoalByIdBody;
+use crate::components;
+use crate::models::goal::Goal;
+
+use crate::errors::AppError;
+use crate::utils::date;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let goal_id = req
+    .match_info()
+    .get("goal_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such goal"))?;
+
+  let goal = Goal::get_by_id(goal_id)
+    .map_err(|err| {
+      println!("error when fetching goal {}", err);
+
+      AppError::internal("error when fetching goal from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such goal"))?;
+
+  let form = UpdateGoalByIdBody {
+    name: goal.name,
+    target: goal.target.to_string(),
+    deadline: goal.deadline.map(date::format_date).unwrap_or_default(),
+    sheet_id: goal.sheet_id.map(|id| id.to_string()).unwrap_or_default(),
+    id: goal.id
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &UpdateGoalByIdBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Editing a goal" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/goals/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing the goal" }
+        fieldset {
+          legend { "the expenses and incomes linked to the goal and its sheet count toward it" }
+
+          input type="hidden" name="id" value=(form.id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="target" { "Target" }
+            input id="target" type="number" name="target" value=(form.target);
+            (components::field_error(errors, "target"))
+          }
+
+          div {
+            label for="deadline" { "Deadline" }
+            input id="deadline" type="date" name="deadline" value=(form.deadline);
+            (components::field_error(errors, "deadline"))
+          }
+
+          (components::sheet_select(&form.sheet_id, errors))
+
+          div class="row" {
+            a href={"/goal/" (form.id)} { "cancel" }
+            input type="submit" value="update";
+          }
+        }
+      }
+
+      form method="post" action="/api/goals/delete-by-id" {
+        (components::csrf_field(req))
+        input type="hidden" name="id" value=(form.id);
+        input type="submit" value="delete the goal" class="link";
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
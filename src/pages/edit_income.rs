@@ -1,61 +1,53 @@
+use crate::api::income::UpdateincomeByIdBody;
 use crate::components;
 use crate::models::income::Income;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::errors::AppError;
+use crate::utils::date;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
 
-use maud::html;
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
 
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let income_id = req
     .match_info()
     .get("income_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
+    .map_err(|_| AppError::not_found("no such income"))?;
 
-  let income_result = Income::get_by_id(income_id);
-  let some_income = match income_result {
-    Ok(some_income) => some_income,
-    Err(e) => {
-      let content = html! {
-        p {
-          "An error occured when trying to load edit-income page" (e)
-        }
-      };
+  let income = Income::get_by_id(income_id)
+    .map_err(|err| {
+      println!("error when fetching income {}", err);
 
-      let view = components::page("edit-income", &content);
-      
-      return HttpResponse::Ok()
-      .content_type("text/html")
-      .body(view.into_string());
-    }
+      AppError::internal("error when fetching income from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such income"))?;
+
+  let form = UpdateincomeByIdBody {
+    name: income.name,
+    amount: income.amount.to_string(),
+    date: date::format_date(income.date),
+    id: income.id,
+    goal_id: income.goal_id.map(|id| id.to_string()).unwrap_or_default(),
+    account_id: income.account_id.map(|id| id.to_string()).unwrap_or_default()
   };
 
+  let view = render_form(&req, &form, income.sheet_id, &FormErrors::new());
+  
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The income form does not hold the sheet, `sheet_id` is where cancel goes
+/// back to.
+pub fn render_form(req: &HttpRequest, form: &UpdateincomeByIdBody, sheet_id: i32, errors: &FormErrors) -> Markup {
   let content = html! {
     div class="title-row" {
       div class="left" {
@@ -63,50 +55,41 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
       }
     }
 
-    @match some_income {
-      Some(income) => {
+    div class="form-wrapper" {
+      form method="post" action="/api/incomes/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing income" }
 
-        div class="form-wrapper" {
-          form method="post" action="/api/incomes/update-by-id" {
-            h4 { "Editing income" }
+        input type="hidden" name="id" value=(form.id);
 
-            input type="hidden" name="id" value=(income_id);
+        div {
+          label for="name" { "Name" }
+          input id="name" type="text" name="name" value=(form.name);
+          (components::field_error(errors, "name"))
+        }
 
-            div {
-              label for="name" { "Name" }
-              input id="name" type="text" name="name" value=(income.name);
-            }
-  
-            div {
-              label for="amount" { "Amount" }
-              input id="amount" type="number" name="amount" value=(income.amount);
-            }
-  
-            div {
-              label for="date" { "Date" }
-              input id="date" type="date" name="date" value=(income.date);
-            }
-      
-            div class="row" {
-              a href={"/sheet/"(income.sheet_id)} { "cancel" }
-              input type="submit" value="update";
-            }
-          }
+        div {
+          label for="amount" { "Amount" }
+          input id="amount" type="number" name="amount" value=(form.amount);
+          (components::field_error(errors, "amount"))
         }
 
-      },
-      None => {
-        div class="form-wrapper" {
-          "no income with such id " span { (income_id) }
-          a href="/sheets" { "go back" }
+        div {
+          label for="date" { "Date" }
+          input id="date" type="date" name="date" value=(form.date);
+          (components::field_error(errors, "date"))
+        }
+
+        (components::goal_select(&form.goal_id, errors))
+        (components::account_select(&form.account_id, errors))
+  
+        div class="row" {
+          a href={"/sheet/"(sheet_id)} { "cancel" }
+          input type="submit" value="update";
         }
       }
     }
   };
 
-  let view = components::page("rename-sheet", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("rename-sheet", &content)
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+use crate::api::transfer::UpdateTransferByIdBody;
+use crate::components;
+use crate::models::transfer::Transfer;
+use crate::pages::new_transfer::sheet_field;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let transfer_id = req
+    .match_info()
+    .get("transfer_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such transfer"))?;
+
+  let transfer = Transfer::get_by_id(transfer_id)
+    .map_err(|err| {
+      println!("error when fetching transfer {}", err);
+
+      AppError::internal("error when fetching transfer from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such transfer"))?;
+
+  let form = UpdateTransferByIdBody {
+    name: transfer.name,
+    amount: transfer.amount.to_string(),
+    date: date::format_date(transfer.date),
+    from_sheet_id: transfer.from_sheet_id.to_string(),
+    to_sheet_id: transfer.to_sheet_id.to_string(),
+    id: transfer.id
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &UpdateTransferByIdBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Editing a transfer" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/transfers/update-by-id" {
+        (components::csrf_field(req))
+        h4 { "Editing the transfer" }
+        fieldset {
+          legend { "its expense and income are changed together" }
+
+          input type="hidden" name="id" value=(form.id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="amount" { "Amount" }
+            input id="amount" type="number" name="amount" value=(form.amount);
+            (components::field_error(errors, "amount"))
+          }
+
+          div {
+            label for="date" { "Date" }
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
+          }
+
+          (sheet_field("from_sheet_id", "From", &form.from_sheet_id, errors))
+          (sheet_field("to_sheet_id", "To", &form.to_sheet_id, errors))
+
+          div class="row" {
+            a href={"/sheet/" (form.from_sheet_id)} { "cancel" }
+            input type="submit" value="update";
+          }
+        }
+      }
+
+      form method="post" action="/api/transfers/delete-by-id" {
+        (components::csrf_field(req))
+        input type="hidden" name="id" value=(form.id);
+        input type="submit" value="delete the transfer" class="link";
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
@@ -0,0 +1,165 @@
+use crate::components;
+use crate::config;
+use crate::models::expense::Expense;
+use crate::models::split::{self, Split};
+use crate::models::user::User;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+use std::collections::HashMap;
+
+pub async fn render(req: HttpRequest, user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let expense_id = req
+    .match_info()
+    .get("expense_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such expense"))?;
+
+  let expense = Expense::get_by_id(expense_id)
+    .map_err(|err| {
+      println!("error when fetching expense {}", err);
+
+      AppError::internal("error when fetching expense from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such expense"))?;
+
+  let split = Split::get_by_expense_id(expense.id)
+    .map_err(|err| {
+      println!("error when fetching split {}", err);
+
+      AppError::internal("error when fetching split from database")
+    })?;
+
+  let mut form = HashMap::new();
+
+  match split {
+    Some(split) => {
+      form.insert(String::from("paid_by"), split.paid_by.to_string());
+      form.insert(String::from("method"), split.method);
+
+      for share in &split.shares {
+        form.insert(format!("share-{}", share.user_id), share.value.to_string());
+      }
+    },
+
+    // a new split is shared equally with everyone, paid by the current user
+    None => {
+      form.insert(String::from("paid_by"), user.user.id.to_string());
+      form.insert(String::from("method"), String::from(split::METHODS[0]));
+
+      for user in User::get_all().unwrap_or_default() {
+        form.insert(format!("share-{}", user.id), String::from("1"));
+      }
+    }
+  }
+
+  let view = render_form(&req, &expense, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The fields are kept by name since there is one share field per user.
+/// The parts of the saved split, if any, are shown below it.
+pub fn render_form(req: &HttpRequest, expense: &Expense, form: &HashMap<String, String>, errors: &FormErrors) -> Markup {
+  let field = |name: &str| form.get(name).cloned().unwrap_or_default();
+  let users = User::get_all().unwrap_or_default();
+  let saved_split = Split::get_by_expense_id(expense.id).ok().flatten();
+  let currency = &config::get().default_currency;
+
+  let handle = |user_id: i32| users
+    .iter()
+    .find(|user| user.id == user_id)
+    .map(|user| user.handle.clone())
+    .unwrap_or_default();
+
+  let content = html! {
+    div class="title-row" {
+      div class="left" {
+        h1 { "Splitting " (expense.name) }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/splits" {
+        (components::csrf_field(req))
+        h4 { "Splitting " (expense.amount) " " (currency) " between users" }
+        fieldset {
+          legend { "with equal parts any value includes the user, leave it empty to leave them out" }
+
+          input type="hidden" name="expense_id" value=(expense.id);
+
+          div {
+            label for="paid_by" { "Paid by" }
+            select id="paid_by" name="paid_by" {
+              @for user in &users {
+                @if user.id.to_string() == field("paid_by") {
+                  option value=(user.id) selected { (user.handle) }
+                } @else {
+                  option value=(user.id) { (user.handle) }
+                }
+              }
+            }
+            (components::field_error(errors, "paid_by"))
+          }
+
+          div {
+            label for="method" { "Split" }
+            select id="method" name="method" {
+              @for (method, label) in split::METHODS.iter().zip(&["in equal parts", "by shares", "by exact amounts"]) {
+                @if *method == field("method") {
+                  option value=(method) selected { (label) }
+                } @else {
+                  option value=(method) { (label) }
+                }
+              }
+            }
+            (components::field_error(errors, "method"))
+          }
+
+          @for user in &users {
+            @let name = format!("share-{}", user.id);
+
+            div {
+              label for=(name) { (user.handle) }
+              input id=(name) type="number" name=(name) value=(field(&name));
+            }
+          }
+          (components::field_error(errors, "shares"))
+
+          div class="row" {
+            a href={"/sheet/" (expense.sheet_id)} { "cancel" }
+            input type="submit" value="save";
+          }
+        }
+      }
+
+      @if let Some(split) = &saved_split {
+        h4 { "Currently, paid by " (handle(split.paid_by)) }
+
+        ul {
+          @for (user_id, part) in split.parts() {
+            li { (handle(user_id)) " owes " (part) " " (currency) }
+          }
+        }
+
+        form method="post" action="/api/splits/delete-by-id" {
+          (components::csrf_field(req))
+          input type="hidden" name="expense_id" value=(expense.id);
+          input type="submit" value="stop splitting the expense" class="link";
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
@@ -0,0 +1,135 @@
+use crate::components;
+use crate::config;
+use crate::models::goal::Goal;
+use crate::models::sheet::Sheet;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let goal_id = req
+    .match_info()
+    .get("goal_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such goal"))?;
+
+  let goal = Goal::get_by_id(goal_id)
+    .map_err(|err| {
+      println!("error when fetching goal {}", err);
+
+      AppError::internal("error when fetching goal from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such goal"))?;
+
+  let contributions = goal.get_contributions()
+    .map_err(|err| {
+      println!("error when fetching goal contributions {}", err);
+
+      AppError::internal("error when fetching goal contributions from database")
+    })?;
+
+  let sheet = match goal.sheet_id {
+    Some(sheet_id) => Sheet::get_by_id(sheet_id)
+      .map_err(|err| {
+        println!("error when fetching sheet {}", err);
+
+        AppError::internal("error when fetching sheet from database")
+      })?,
+    None => None
+  };
+
+  let projection = goal.project(&contributions, date::now());
+  let currency = &config::get().default_currency;
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        h1 { (goal.name) }
+        a href={"/goal/" (goal.id) "/edit"} { "edit" }
+      }
+      div {
+        @if let Some(sheet) = &sheet {
+          "saved in " a href={"/sheet/" (sheet.id)} { (sheet.name) }
+        }
+      }
+    }
+
+    section class="projection" {
+      div class="row" {
+        span class="name" { "saved" }
+        span class="amount" { (projection.saved) " / " (goal.target) span { (currency) } }
+      }
+
+      progress value=(projection.saved.max(0).min(goal.target)) max=(goal.target) {}
+
+      dl {
+        dt { "deadline" }
+        dd {
+          @match goal.deadline {
+            Some(deadline) => { (date::format_date(deadline)) },
+            None => { "none" }
+          }
+        }
+
+        dt { "average monthly contribution" }
+        dd { (projection.monthly_average) span { (currency) } }
+
+        @if projection.remaining == 0 {
+          dt { "completion" }
+          dd class="reached" { "reached" }
+        } @else {
+          dt { "expected completion" }
+          dd {
+            @match projection.completion_date {
+              Some(completion_date) => {
+                (date::format_date(completion_date))
+
+                @if let Some(deadline) = goal.deadline {
+                  @if completion_date > deadline {
+                    span class="late" { "after the deadline" }
+                  }
+                }
+              },
+              None => { "never at the current pace" }
+            }
+          }
+        }
+
+        @if let Some(monthly_needed) = projection.monthly_needed {
+          dt { "needed monthly to meet the deadline" }
+          dd { (monthly_needed) span { (currency) } }
+        }
+      }
+    }
+
+    section class="contributions" {
+      h4 { "contributions" }
+
+      @if contributions.is_empty() {
+        p { "link expenses or incomes to the goal, or choose a sheet for it" }
+      }
+
+      @for contribution in contributions.iter().rev() {
+        div class="contribution row" {
+          span class="date" { (date::format_date(contribution.date)) }
+          span class="name" { (contribution.source) }
+          span class="amount" { (contribution.amount) span { (currency) } }
+        }
+      }
+    }
+  };
+
+  let view = components::page("goals", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
@@ -0,0 +1,77 @@
+use crate::components;
+use crate::config;
+use crate::models::goal::Goal;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(_req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let goals = Goal::get_all()
+    .map_err(|err| {
+      println!("error when fetching goals {}", err);
+
+      AppError::internal("error when fetching goals from database")
+    })?;
+
+  let now = date::now();
+  let mut projections = Vec::new();
+
+  for goal in &goals {
+    let contributions = goal.get_contributions()
+      .map_err(|err| {
+        println!("error when fetching goal contributions {}", err);
+
+        AppError::internal("error when fetching goal contributions from database")
+      })?;
+
+    projections.push(goal.project(&contributions, now));
+  }
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Your goals" }
+      a href="/goals/new" class="button" { "new goal" span { "+" } }
+    }
+
+    ul class="goals" {
+      @for (goal, projection) in goals.iter().zip(&projections) {
+        li class="goal" {
+          div class="row" {
+            a href={"/goal/" (goal.id)} class="name" { (goal.name) }
+            span class="amount" {
+              (projection.saved) " / " (goal.target)
+              span { (config::get().default_currency) }
+            }
+          }
+
+          progress value=(projection.saved.max(0).min(goal.target)) max=(goal.target) {}
+
+          div class="row details" {
+            @if let Some(deadline) = goal.deadline {
+              span { "before " (date::format_date(deadline)) }
+            }
+
+            @if projection.remaining == 0 {
+              span class="reached" { "reached" }
+            } @else if let Some(completion_date) = projection.completion_date {
+              span { "expected on " (date::format_date(completion_date)) }
+            }
+          }
+        }
+      }
+    }
+  };
+
+  let view = components::page("goals", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
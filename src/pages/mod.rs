@@ -9,4 +9,42 @@ pub mod new_sheet_expense;
 pub mod edit_expense;
 pub mod new_sheet_income;
 pub mod edit_income;
-pub mod new_sheet_inherited_sheet;
\ No newline at end of file
+pub mod new_sheet_inherited_sheet;
+pub mod admin_users;
+pub mod admin_delete_user;
+pub mod admin_invitations;
+pub mod sessions;
+pub mod settings;
+pub mod settings_two_factor;
+pub mod signin_two_factor;
+pub mod signout;
+pub mod reset_password;
+pub mod admin_password_resets;
+pub mod admin_failed_attempts;
+pub mod admin_history;
+pub mod sheet_history;
+pub mod trash;
+pub mod duplicate_sheet;
+pub mod new_sheet_template;
+pub mod new_sheet_category;
+pub mod edit_category;
+pub mod sheet_budget;
+pub mod goals;
+pub mod goal;
+pub mod new_goal;
+pub mod edit_goal;
+pub mod sheet_forecast;
+pub mod new_recurring_entry;
+pub mod reports;
+pub mod accounts;
+pub mod account;
+pub mod new_account;
+pub mod edit_account;
+pub mod new_statement;
+pub mod account_reconcile;
+pub mod new_transfer;
+pub mod edit_transfer;
+pub mod expense_split;
+pub mod sheet_settle_up;
+pub mod new_settlement;
+pub mod not_found;
\ No newline at end of file
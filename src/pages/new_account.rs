@@ -0,0 +1,89 @@
+use crate::api::account::CreateAccountBody;
+use crate::components;
+use crate::models::account;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let form = CreateAccountBody {
+    kind: String::from(account::KINDS[0]),
+    opening_balance: String::from("0"),
+    ..CreateAccountBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateAccountBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Adding an account" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/accounts" {
+        (components::csrf_field(req))
+        h4 { "Creating a new account" }
+        fieldset {
+          legend { "the opening balance is the one before the expenses and incomes of the account" }
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          (kind_select(&form.kind, errors))
+
+          div {
+            label for="opening_balance" { "Opening balance" }
+            input id="opening_balance" type="number" name="opening_balance" value=(form.opening_balance);
+            (components::field_error(errors, "opening_balance"))
+          }
+
+          div class="row" {
+            a href="/accounts" { "cancel" }
+            input type="submit" value="create";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
+
+/// The field choosing the kind of an account, also used by the edit form.
+pub fn kind_select(selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    div {
+      label for="kind" { "Kind" }
+      select id="kind" name="kind" {
+        @for kind in &account::KINDS {
+          @if *kind == selected {
+            option value=(kind) selected { (kind) }
+          } @else {
+            option value=(kind) { (kind) }
+          }
+        }
+      }
+      (components::field_error(errors, "kind"))
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+use crate::api::goal::CreateGoalBody;
+use crate::components;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let view = render_form(&req, &CreateGoalBody::default(), &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateGoalBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Adding a goal" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/goals" {
+        (components::csrf_field(req))
+        h4 { "Creating a new goal" }
+        fieldset {
+          legend { "the expenses and incomes linked to the goal and its sheet count toward it" }
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="target" { "Target" }
+            input id="target" type="number" name="target" value=(form.target);
+            (components::field_error(errors, "target"))
+          }
+
+          div {
+            label for="deadline" { "Deadline" }
+            input id="deadline" type="date" name="deadline" value=(form.deadline);
+            (components::field_error(errors, "deadline"))
+          }
+
+          (components::sheet_select(&form.sheet_id, errors))
+
+          div class="row" {
+            a href="/goals" { "cancel" }
+            input type="submit" value="create";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
@@ -0,0 +1,128 @@
+use crate::api::recurring_entry::CreateRecurringEntryBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+use crate::utils::date::{self, Period};
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = CreateRecurringEntryBody {
+    sheet_id: sheet.id,
+    kind: String::from("expense"),
+    start_date: date::format_date(date::now()),
+    period: String::from(Period::Month.name()),
+    ..CreateRecurringEntryBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateRecurringEntryBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Adding a recurring entry" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/recurring-entries" {
+        (components::csrf_field(req))
+        h4 { "Creating a new recurring entry" }
+        fieldset {
+          legend { "it is only used to forecast the balance of the sheet" }
+
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
+
+          div {
+            label for="kind" { "Kind" }
+            select id="kind" name="kind" {
+              @for kind in &["expense", "income"] {
+                @if *kind == form.kind {
+                  option value=(kind) selected { (kind) }
+                } @else {
+                  option value=(kind) { (kind) }
+                }
+              }
+            }
+            (components::field_error(errors, "kind"))
+          }
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="amount" { "Amount" }
+            input id="amount" type="number" name="amount" value=(form.amount);
+            (components::field_error(errors, "amount"))
+          }
+
+          div {
+            label for="start_date" { "First date" }
+            input id="start_date" type="date" name="start_date" value=(form.start_date);
+            (components::field_error(errors, "start_date"))
+          }
+
+          div {
+            label for="period" { "Every" }
+            select id="period" name="period" {
+              @for period in Period::ALL.iter().filter(|period| **period != Period::None) {
+                @if period.name() == form.period {
+                  option value=(period.name()) selected { (period.name()) }
+                } @else {
+                  option value=(period.name()) { (period.name()) }
+                }
+              }
+            }
+            (components::field_error(errors, "period"))
+          }
+
+          div {
+            label for="end_date" { "Last date" }
+            input id="end_date" type="date" name="end_date" value=(form.end_date);
+            (components::field_error(errors, "end_date"))
+          }
+
+          div class="row" {
+            a href={"/sheet/" (form.sheet_id) "/forecast"} { "cancel" }
+            input type="submit" value="create";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+use crate::api::settlement::CreateSettlementBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+use crate::models::user::User;
+use crate::pages::new_transfer::sheet_field;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::{self, HttpRequest};
+use actix_web::HttpResponse;
+use serde::Deserialize;
+
+/// The payment suggested on the settle-up page, all of it can be left out.
+#[derive(Deserialize)]
+pub struct SettlementQuery {
+  pub from_user_id: Option<i32>,
+  pub to_user_id: Option<i32>,
+  pub amount: Option<i32>
+}
+
+pub async fn render(req: HttpRequest, query: web::Query<SettlementQuery>, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  // the money leaves a sheet of the payer and reaches one of the receiver
+  let first_sheet_of = |user_id: Option<i32>| -> Result<String, AppError> {
+    let sheets = match user_id {
+      Some(user_id) => Sheet::get_all_by_user_id(user_id)
+        .map_err(|err| {
+          println!("error when fetching sheets {}", err);
+
+          AppError::internal("error when fetching sheets from database")
+        })?,
+      None => Vec::new()
+    };
+
+    Ok(sheets.first().map(|sheet| sheet.id.to_string()).unwrap_or_default())
+  };
+
+  let form = CreateSettlementBody {
+    sheet_id: sheet.id,
+    from_user_id: query.from_user_id.map(|id| id.to_string()).unwrap_or_default(),
+    to_user_id: query.to_user_id.map(|id| id.to_string()).unwrap_or_default(),
+    amount: query.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+    date: date::format_date(date::now()),
+    from_sheet_id: first_sheet_of(query.from_user_id)?,
+    to_sheet_id: first_sheet_of(query.to_user_id)?
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// `form.sheet_id` is the settled sheet, cancel goes back to its settle up
+/// page.
+pub fn render_form(req: &HttpRequest, form: &CreateSettlementBody, errors: &FormErrors) -> Markup {
+  let users = User::get_all().unwrap_or_default();
+
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Recording a settlement" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/settlements" {
+        (components::csrf_field(req))
+        h4 { "Recording a payment between users" }
+        fieldset {
+          legend { "the money is moved by a transfer between a sheet of each user" }
+
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
+
+          (user_field("from_user_id", "Paid by", &form.from_user_id, &users, errors))
+          (user_field("to_user_id", "Paid to", &form.to_user_id, &users, errors))
+
+          div {
+            label for="amount" { "Amount" }
+            input id="amount" type="number" name="amount" value=(form.amount);
+            (components::field_error(errors, "amount"))
+          }
+
+          div {
+            label for="date" { "Date" }
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
+          }
+
+          (sheet_field("from_sheet_id", "From the sheet", &form.from_sheet_id, errors))
+          (sheet_field("to_sheet_id", "To the sheet", &form.to_sheet_id, errors))
+
+          div class="row" {
+            a href={"/sheet/" (form.sheet_id) "/settle-up"} { "cancel" }
+            input type="submit" value="record";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
+
+fn user_field(field: &'static str, label: &str, selected: &str, users: &[User], errors: &FormErrors) -> Markup {
+  html! {
+    div {
+      label for=(field) { (label) }
+      select id=(field) name=(field) {
+        option value="" { "choose a user" }
+
+        @for user in users {
+          @if user.id.to_string() == selected {
+            option value=(user.id) selected { (user.handle) }
+          } @else {
+            option value=(user.id) { (user.handle) }
+          }
+        }
+      }
+      (components::field_error(errors, field))
+    }
+  }
+}
\ No newline at end of file
@@ -1,35 +1,32 @@
+use crate::api::sheet::CreateSheetBody;
+use crate::api::sheet_template::CreateSheetFromTemplateBody;
 use crate::components;
+use crate::models::sheet_template::SheetTemplate;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
 
-use maud::html;
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, current: AuthenticatedUser) -> HttpResponse {
+  let view = render_form(
+    &req,
+    current.user.id,
+    &CreateSheetBody::default(),
+    &CreateSheetFromTemplateBody::default(),
+    &FormErrors::new()
+  );
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
 
+/// The blank sheet form and the template form share the page, the api
+/// renders both again whichever one is rejected.
+pub fn render_form(req: &HttpRequest, user_id: i32, form: &CreateSheetBody, template_form: &CreateSheetFromTemplateBody, errors: &FormErrors) -> Markup {
   let content = html! {
     img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
 
@@ -42,14 +39,16 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
     div class="form-wrapper" {
       form method="post" action="/api/sheets" {
+        (components::csrf_field(req))
         h4 { "Creating a new sheet" }
         fieldset {
           legend { "fill the information" }
           div {
             label for="name" { "Name" }
-            input id="name" type="text" name="name";
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
           }
-  
+
           div class="row" {
             a href="/sheets" { "cancel" }
             input type="submit" value="create";
@@ -57,11 +56,71 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
         }
       }
     }
+
+    @match SheetTemplate::get_all_by_user_id(user_id) {
+      Ok(templates) => {
+        @if !templates.is_empty() {
+          div class="form-wrapper" {
+            form method="post" action="/api/sheet-templates/instantiate" {
+              (components::csrf_field(req))
+              h4 { "Starting from a template" }
+              fieldset {
+                legend { "the rows of the template are placed after the start date" }
+
+                select name="template_id" {
+                  @for template in &templates {
+                    @if Some(template.id) == template_form.template_id {
+                      option value=(template.id) selected { (template.name) }
+                    } @else {
+                      option value=(template.id) { (template.name) }
+                    }
+                  }
+                }
+                (components::field_error(errors, "template_id"))
+
+                div {
+                  label for="sheet_name" { "Name" }
+                  input id="sheet_name" type="text" name="sheet_name" value=(template_form.sheet_name);
+                  (components::field_error(errors, "sheet_name"))
+                }
+
+                div {
+                  label for="start_date" { "Start date" }
+                  input id="start_date" type="date" name="start_date" value=(template_form.start_date);
+                  (components::field_error(errors, "start_date"))
+                }
+
+                div class="row" {
+                  input type="submit" value="create";
+                }
+              }
+            }
+          }
+
+          div class="templates" {
+            h4 { "Your templates" }
+
+            @for template in &templates {
+              div class="template" {
+                span class="name" { (template.name) }
+                span class="rows" { (template.rows.len()) " rows, " (template.imports.len()) " imports" }
+
+                form method="post" action="/api/sheet-templates/delete-by-id" {
+                  (components::csrf_field(req))
+                  input type="hidden" name="id" value=(template.id);
+                  input type="submit" value="delete" class="link";
+                }
+              }
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the templates: " (e)
+      }
+    }
   };
 
-  let view = components::page("new-sheet", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("new-sheet", &content)
 }
\ No newline at end of file
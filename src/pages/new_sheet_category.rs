@@ -0,0 +1,84 @@
+use crate::api::category::CreateCategoryBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = CreateCategoryBody {
+    sheet_id: sheet.id,
+    ..CreateCategoryBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateCategoryBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Adding a category" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/categories" {
+        (components::csrf_field(req))
+        h4 { "Creating a new category" }
+        fieldset {
+          legend { "the target is the amount planned for its expenses" }
+
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="target" { "Target" }
+            input id="target" type="number" name="target" value=(form.target);
+            (components::field_error(errors, "target"))
+          }
+
+          div class="row" {
+            a href={"/sheet/" (form.sheet_id)} { "cancel" }
+            input type="submit" value="create";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
@@ -1,17 +1,46 @@
+use crate::api::income::CreateIncomeBody;
 use crate::components;
+use crate::models::sheet::Sheet;
 
-use maud::html;
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
 use actix_web::HttpResponse;
 
-pub async fn render(req: HttpRequest) -> HttpResponse {
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let sheet_id = req
     .match_info()
     .get("sheet_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = CreateIncomeBody {
+    sheet_id: sheet.id,
+    ..CreateIncomeBody::default()
+  };
 
+  let view = render_form(&req, &form, &FormErrors::new());
+  
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateIncomeBody, errors: &FormErrors) -> Markup {
   let content = html! {
     img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
 
@@ -23,29 +52,36 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
     div class="form-wrapper" {
       form method="post" action="/api/incomes" {
+        (components::csrf_field(req))
         h4 { "Creating a new income" }
         fieldset {
           legend { "fill the information" }
 
-          input type="hidden" name="sheet_id" value=(sheet_id);
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
 
           div {
             label for="name" { "Name" }
-            input id="name" type="text" name="name";
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
           }
 
           div {
             label for="amount" { "Amount" }
-            input id="amount" type="number" name="amount";
+            input id="amount" type="number" name="amount" value=(form.amount);
+            (components::field_error(errors, "amount"))
           }
 
           div {
             label for="date" { "Date" }
-            input id="date" type="date" name="date";
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
           }
+
+          (components::goal_select(&form.goal_id, errors))
+          (components::account_select(&form.account_id, errors))
   
           div class="row" {
-            a href={"/sheet/" (sheet_id)} { "cancel" }
+            a href={"/sheet/" (form.sheet_id)} { "cancel" }
             input type="submit" value="create";
           }
         }
@@ -53,9 +89,5 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     }
   };
 
-  let view = components::page("new-income", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("new-income", &content)
 }
\ No newline at end of file
@@ -1,44 +1,48 @@
+use crate::api::inherited_sheet::CreateInheritedSheetBody;
 use crate::components;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
 
-use maud::html;
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
+use actix_web::HttpResponse;
 
 use crate::models::sheet::Sheet;
 
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
-
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let sheet_id = req
     .match_info()
     .get("sheet_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
 
+  let form = CreateInheritedSheetBody {
+    sheet_id: sheet.id,
+    ..CreateInheritedSheetBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+  
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The sheet being edited is left out of the sheets it can import.
+pub fn render_form(req: &HttpRequest, form: &CreateInheritedSheetBody, errors: &FormErrors) -> Markup {
   let content = html! {
     img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
 
@@ -50,21 +54,25 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
     div class="form-wrapper" {
       form method="post" action="/api/inherited-sheets" {
+        (components::csrf_field(req))
         h4 { "Importing a new sheet" }
         fieldset {
           legend { "fill the information" }
 
-          input type="hidden" name="sheet_id" value=(sheet_id);
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
 
           @match Sheet::get_all() {
             Ok(sheets) => {
               select name="inherited_sheet_id" {
-                @for sheet in sheets {
-                  @if sheet.id != sheet_id {
+                @for sheet in sheets.iter().filter(|sheet| sheet.id != form.sheet_id) {
+                  @if Some(sheet.id) == form.inherited_sheet_id {
+                    option value=(sheet.id) selected { (sheet.name) }
+                  } @else {
                     option value=(sheet.id) { (sheet.name) }
                   }
                 }
               }
+              (components::field_error(errors, "inherited_sheet_id"))
             },
 
             Err(e) => {
@@ -74,11 +82,12 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
           div {
             label for="date" { "Date" }
-            input id="date" type="date" name="date";
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
           }
   
           div class="row" {
-            a href={"/sheet/" (sheet_id)} { "cancel" }
+            a href={"/sheet/" (form.sheet_id)} { "cancel" }
             input type="submit" value="create";
           }
         }
@@ -86,9 +95,5 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
     }
   };
 
-  let view = components::page("new-inherited-sheet", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("new-inherited-sheet", &content)
 }
\ No newline at end of file
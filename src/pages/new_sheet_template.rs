@@ -0,0 +1,76 @@
+use crate::api::sheet_template::CreateSheetTemplateBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = CreateSheetTemplateBody {
+    sheet_id: sheet.id,
+    name: sheet.name
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateSheetTemplateBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    div class="title-row" {
+      div class="left" {
+        h1 { "Your sheets" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/sheet-templates" {
+        (components::csrf_field(req))
+        h4 { "Saving as a template" }
+        fieldset {
+          legend { "the expenses, incomes and imports of the sheet are saved" }
+
+          input type="hidden" name="sheet_id" value=(form.sheet_id);
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div class="row" {
+            a href={"/sheet/"(form.sheet_id)} { "cancel" }
+            input type="submit" value="save";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet-template", &content)
+}
\ No newline at end of file
@@ -0,0 +1,86 @@
+use crate::api::account::CreateStatementBody;
+use crate::components;
+use crate::models::account::Account;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let account_id = req
+    .match_info()
+    .get("account_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such account"))?;
+
+  let account = Account::get_by_id(account_id)
+    .map_err(|err| {
+      println!("error when fetching account {}", err);
+
+      AppError::internal("error when fetching account from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such account"))?;
+
+  let form = CreateStatementBody {
+    account_id: account.id,
+    date: date::format_date(date::now()),
+    ..CreateStatementBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateStatementBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Recording a statement" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/statements" {
+        (components::csrf_field(req))
+        h4 { "Recording a statement balance" }
+        fieldset {
+          legend { "the balance the bank gives for the account on that day" }
+
+          input type="hidden" name="account_id" value=(form.account_id);
+
+          div {
+            label for="date" { "Date" }
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
+          }
+
+          div {
+            label for="balance" { "Balance" }
+            input id="balance" type="number" name="balance" value=(form.balance);
+            (components::field_error(errors, "balance"))
+          }
+
+          div class="row" {
+            a href={"/account/" (form.account_id)} { "cancel" }
+            input type="submit" value="record";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
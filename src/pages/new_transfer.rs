@@ -0,0 +1,123 @@
+use crate::api::transfer::CreateTransferBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = CreateTransferBody {
+    date: date::format_date(date::now()),
+    from_sheet_id: sheet.id.to_string(),
+    ..CreateTransferBody::default()
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &CreateTransferBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Adding a transfer" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/transfers" {
+        (components::csrf_field(req))
+        h4 { "Moving money between sheets" }
+        fieldset {
+          legend { "an expense and an income are added to the sheets" }
+
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+
+          div {
+            label for="amount" { "Amount" }
+            input id="amount" type="number" name="amount" value=(form.amount);
+            (components::field_error(errors, "amount"))
+          }
+
+          div {
+            label for="date" { "Date" }
+            input id="date" type="date" name="date" value=(form.date);
+            (components::field_error(errors, "date"))
+          }
+
+          (sheet_field("from_sheet_id", "From", &form.from_sheet_id, errors))
+          (sheet_field("to_sheet_id", "To", &form.to_sheet_id, errors))
+
+          div class="row" {
+            a href={"/sheet/" (form.from_sheet_id)} { "cancel" }
+            input type="submit" value="create";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
+
+/// The field choosing one of the sheets of a transfer, also used by the edit
+/// form.
+pub fn sheet_field(field: &'static str, label: &str, selected: &str, errors: &FormErrors) -> Markup {
+  html! {
+    @match Sheet::get_all() {
+      Ok(sheets) => {
+        div {
+          label for=(field) { (label) }
+          select id=(field) name=(field) {
+            option value="" { "choose a sheet" }
+
+            @for sheet in &sheets {
+              @if sheet.id.to_string() == selected {
+                option value=(sheet.id) selected { (sheet.name) }
+              } @else {
+                option value=(sheet.id) { (sheet.name) }
+              }
+            }
+          }
+          (components::field_error(errors, field))
+        }
+      },
+
+      Err(e) => {
+        "An error occured when loading the sheets: " (e)
+      }
+    }
+  }
+}
\ No newline at end of file
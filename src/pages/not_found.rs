@@ -0,0 +1,8 @@
+use crate::errors::AppError;
+
+use actix_web::HttpResponse;
+
+/// Answers the requests no route matched.
+pub async fn render() -> Result<HttpResponse, AppError> {
+  Err(AppError::not_found("this page does not exist"))
+}
@@ -1,61 +1,46 @@
+use crate::api::sheet::RenameSheetByIdBody;
 use crate::components;
 use crate::models::sheet::Sheet;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
 
-use maud::html;
+use maud::{html, Markup};
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
 
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let sheet_id = req
     .match_info()
     .get("sheet_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
+    .map_err(|_| AppError::not_found("no such sheet"))?;
 
-  let sheet_result = Sheet::get_by_id(sheet_id);
-  let some_sheet = match sheet_result {
-    Ok(some_sheet) => some_sheet,
-    Err(e) => {
-      let content = html! {
-        p {
-          "An error occured when trying to load sheet-rename page" (e)
-        }
-      };
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
 
-      let view = components::page("rename-sheet", &content);
-      
-      return HttpResponse::Ok()
-      .content_type("text/html")
-      .body(view.into_string());
-    }
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = RenameSheetByIdBody {
+    name: sheet.name,
+    id: sheet.id
   };
 
+  let view = render_form(&req, &form, &FormErrors::new());
+  
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &RenameSheetByIdBody, errors: &FormErrors) -> Markup {
   let content = html! {
     div class="title-row" {
       div class="left" {
@@ -63,45 +48,31 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
       }
     }
 
-    @match some_sheet {
-      Some(sheet) => {
-
-        div class="form-wrapper" {
-          form method="post" action="/api/sheets/rename-by-id" {
-            h4 { "Renaming sheet" }
-            // fieldset {
-              // legend { "enter a new name" }
+    div class="form-wrapper" {
+      form method="post" action="/api/sheets/rename-by-id" {
+        (components::csrf_field(req))
+        h4 { "Renaming sheet" }
+        // fieldset {
+          // legend { "enter a new name" }
 
-              
-            // }
+          
+        // }
 
-            input type="hidden" name="id" value=(sheet_id);
+        input type="hidden" name="id" value=(form.id);
 
-              div {
-                label for="name" { "Name" }
-                input id="name" type="text" name="name" value=(sheet.name);
-              }
-      
-              div class="row" {
-                a href={"/sheet/"(sheet_id)} { "cancel" }
-                input type="submit" value="rename";
-              }
+          div {
+            label for="name" { "Name" }
+            input id="name" type="text" name="name" value=(form.name);
+            (components::field_error(errors, "name"))
+          }
+  
+          div class="row" {
+            a href={"/sheet/"(form.id)} { "cancel" }
+            input type="submit" value="rename";
           }
-        }
-
-      },
-      None => {
-        div class="form-wrapper" {
-          "no sheet with such id " span { (sheet_id) }
-          a href={"/sheet/"(sheet_id)} { "go back" }
-        }
       }
     }
   };
 
-  let view = components::page("rename-sheet", &content);
-  
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  components::page("rename-sheet", &content)
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+use crate::components;
+use crate::config;
+use crate::models::report::Report;
+use crate::models::sheet::Sheet;
+use crate::utils::validation::{self, FormErrors};
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+/// The query of the page: the `sheets` to compare, which can be repeated,
+/// and the `from` and `to` dates of the period.
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let query = serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string())
+    .unwrap_or_default();
+
+  let field = |name: &str| query
+    .iter()
+    .find(|(key, _)| key == name)
+    .map(|(_, value)| value.as_str())
+    .unwrap_or("");
+
+  let sheet_ids: Vec<i32> = query
+    .iter()
+    .filter(|(key, _)| key == "sheets")
+    .filter_map(|(_, value)| value.parse().ok())
+    .collect();
+
+  let mut errors = FormErrors::new();
+  let from = validation::optional_date(&mut errors, "from", field("from"));
+  let to = validation::optional_date(&mut errors, "to", field("to"));
+
+  let sheets = Sheet::get_all()
+    .map_err(|err| {
+      println!("error when fetching sheets {}", err);
+
+      AppError::internal("error when fetching sheets from database")
+    })?;
+
+  // the whole day of the end date is included
+  let report = Report::build(&sheet_ids, from, to.map(|to| to + 24 * 3600 - 1))
+    .map_err(|err| {
+      println!("error when building report {}", err);
+
+      AppError::internal("error when building the report")
+    })?;
+
+  let currency = &config::get().default_currency;
+  let category_names = report.category_names();
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Reports" }
+    }
+
+    form class="report-filters" method="get" action="/reports" {
+      fieldset class="sheets-choice" {
+        legend { "sheets, all of them when none is checked" }
+
+        @for sheet in &sheets {
+          label {
+            @if sheet_ids.contains(&sheet.id) {
+              input type="checkbox" name="sheets" value=(sheet.id) checked;
+            } @else {
+              input type="checkbox" name="sheets" value=(sheet.id);
+            }
+            (sheet.name)
+          }
+        }
+      }
+
+      div {
+        label for="from" { "from" }
+        input id="from" type="date" name="from" value=(field("from"));
+        (components::field_error(&errors, "from"))
+      }
+
+      div {
+        label for="to" { "to" }
+        input id="to" type="date" name="to" value=(field("to"));
+        (components::field_error(&errors, "to"))
+      }
+
+      input type="submit" value="report";
+    }
+
+    @if report.sheets.is_empty() {
+      p { "no sheet with expenses or incomes to report on" }
+    } @else {
+      (components::report_chart(&report))
+
+      div class="legend" {
+        span class="income" { "income" }
+        span class="expense" { "expense" }
+        span class="net" { "net" }
+      }
+
+      table class="totals" {
+        thead {
+          tr {
+            th { "sheet" }
+            th { "income" }
+            th { "expense" }
+            th { "net" }
+          }
+        }
+        tbody {
+          @for (index, totals) in report.sheets.iter().enumerate() {
+            tr {
+              td { a href={"/sheet/" (totals.sheet.id)} { (totals.sheet.name) } }
+              td { (totals.income) span { (currency) } }
+              td {
+                (totals.expense) span { (currency) }
+                (changes(totals.expense, report.previous(index).map(|previous| previous.expense), report.year_before(index).map(|year_before| year_before.expense)))
+              }
+              td { (totals.net()) span { (currency) } }
+            }
+          }
+        }
+      }
+
+      h4 { "expenses by category" }
+
+      div class="categories-wrapper" {
+        table class="categories" {
+          thead {
+            tr {
+              th { "category" }
+              @for totals in &report.sheets {
+                th { (totals.sheet.name) }
+              }
+            }
+          }
+          tbody {
+            @for name in &category_names {
+              tr {
+                td { (name) }
+                @for (index, totals) in report.sheets.iter().enumerate() {
+                  td {
+                    @let spent = totals.categories.get(*name).copied().unwrap_or(0);
+                    @let previous = report.previous(index).map(|previous| previous.categories.get(*name).copied().unwrap_or(0));
+                    @let year_before = report.year_before(index).map(|year_before| year_before.categories.get(*name).copied().unwrap_or(0));
+
+                    (spent)
+                    (changes(spent, previous, year_before))
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  };
+
+  let view = components::page("reports", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+/// The month-over-month and year-over-year changes of an amount, each left
+/// out when there is nothing to compare with.
+fn changes(amount: i32, previous: Option<i32>, year_before: Option<i32>) -> Markup {
+  html! {
+    @if let Some(previous) = previous {
+      span class="change" title="month over month" { "m/m " (change(amount, previous)) }
+    }
+    @if let Some(year_before) = year_before {
+      span class="change" title="year over year" { "y/y " (change(amount, year_before)) }
+    }
+  }
+}
+
+/// The change as a percentage, or as an amount when there was nothing before.
+fn change(amount: i32, before: i32) -> String {
+  if before == 0 {
+    return format!("{:+}", amount);
+  }
+
+  let percentage = (amount - before) as f64 * 100.0 / before as f64;
+
+  format!("{:+.0}%", percentage)
+}
\ No newline at end of file
@@ -0,0 +1,60 @@
+use crate::components;
+use maud::{html};
+use actix_web::web;
+use serde::Deserialize;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+#[derive(Deserialize)]
+pub struct Info {
+  hash: String
+}
+
+pub async fn render(info: Option<web::Query<Info>>, req: HttpRequest) -> HttpResponse {
+  let content = html! {
+    img class="background-illustration" src="/static/assets/undraw_authentication_fsn5.svg";
+
+    div class="form-wrapper" {
+      @match info {
+        Some(params) => {
+          form method="post" action="/api/auth/reset-password" {
+            (components::csrf_field(&req))
+            h4 { "Password reset" }
+            fieldset {
+              legend { "Choose a new password" }
+
+              input type="hidden" name="hash" value=(params.hash);
+
+              label for="password" { "new password" }
+              input id="password" type="password" name="password";
+
+              label for="passwordconfirm" { "confirm new password" }
+              input id="passwordconfirm" name="passwordconfirm" type="password";
+
+              input type="submit" value="Submit";
+            }
+          }
+
+          p {
+            "You will be signed out of all your devices."
+          }
+        },
+        None => {
+          p {
+            "This link is incomplete, ask an administrator for a new one."
+          }
+        }
+      }
+
+      p {
+        "You can " a href="/signin" { "signin" } " if you remember your password."
+      }
+    }
+  };
+
+  let view = components::page_without_menu("signin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -1,36 +1,23 @@
 use crate::components;
-use crate::utils::req_auth::request_authentication;
+use crate::utils::req_auth::AuthenticatedUser;
 use crate::models::user::UserRole;
 
 use maud::html;
-use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
+use actix_web::HttpResponse;
 
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+pub async fn render(current: AuthenticatedUser) -> HttpResponse {
+  let is_admin = current.user.is_role_greater_or_equal(UserRole::Admin);
 
   let content = html! {
     "hello!"
+
+    @if is_admin {
+      p {
+        "manage the " a href="/admin/users" { "users" }
+        " and the " a href="/admin/invitations" { "invitations" }
+        " of the instance."
+      }
+    }
   };
 
   let view = components::page("root", &content);
@@ -0,0 +1,62 @@
+use crate::components;
+use crate::models::session::Session;
+
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::date::format_datetime;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, current: AuthenticatedUser) -> HttpResponse {
+  let current_session = current.session;
+
+  let sessions_result = Session::get_all_by_user_id(current_session.user_id);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Your active sessions" }
+    }
+
+    @match sessions_result {
+      Ok(sessions) => {
+        div class="sessions-list" {
+          @for session in &sessions {
+            div.session.element {
+              div.row {
+                span.user-agent { (session.user_agent) }
+
+                @if session.id == current_session.id {
+                  span.current { "this device" }
+                }
+
+                div.actions {
+                  form method="post" action="/api/sessions/revoke-by-id" {
+                    (components::csrf_field(&req))
+                    input type="hidden" name="id" value=(session.id);
+                    input.link type="submit" value="revoke";
+                  }
+                }
+              }
+
+              div.row.dates {
+                span { "signed in on " (format_datetime(session.creation_date)) }
+                span { "last seen on " (format_datetime(session.last_seen_date)) }
+              }
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching sessions list " (e)
+      }
+    }
+  };
+
+  let view = components::page("sessions", &content);
+  
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
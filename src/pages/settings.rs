@@ -0,0 +1,111 @@
+use crate::components;
+use crate::models::two_factor::{TwoFactor, count_recovery_codes};
+
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, current: AuthenticatedUser) -> HttpResponse {
+  let user = current.user;
+
+  let two_factor_result = TwoFactor::get_enabled_by_user_id(user.id);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Settings" }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/auth/change-password" {
+        (components::csrf_field(&req))
+        h4 { "Changing your password" }
+        fieldset {
+          legend { "fill the information" }
+
+          div {
+            label for="current_password" { "current password" }
+            input id="current_password" type="password" name="current_password";
+          }
+
+          div {
+            label for="password" { "new password" }
+            input id="password" type="password" name="password";
+          }
+
+          div {
+            label for="passwordconfirm" { "confirm new password" }
+            input id="passwordconfirm" type="password" name="passwordconfirm";
+          }
+
+          p { "Your other devices will be signed out." }
+
+          div class="row" {
+            input type="submit" value="change password";
+          }
+        }
+      }
+    }
+
+    div class="form-wrapper" {
+      @match two_factor_result {
+        Ok(Some(_)) => {
+          form method="post" action="/api/two-factor/disable" {
+            (components::csrf_field(&req))
+            h4 { "Two-factor authentication" }
+
+            p {
+              "Two-factor authentication is enabled, "
+              @match count_recovery_codes(user.id) {
+                Ok(count) => { (count) " recovery codes left." },
+                Err(e) => { "error when counting recovery codes " (e) }
+              }
+            }
+
+            fieldset {
+              legend { "confirm with your password" }
+
+              div {
+                label for="two_factor_password" { "current password" }
+                input id="two_factor_password" type="password" name="current_password";
+              }
+
+              div class="row" {
+                input type="submit" formaction="/api/two-factor/recovery-codes" value="new recovery codes";
+                input type="submit" value="disable";
+              }
+            }
+          }
+        },
+        Ok(None) => {
+          form method="post" action="/api/two-factor/setup" {
+            (components::csrf_field(&req))
+            h4 { "Two-factor authentication" }
+
+            p {
+              "Ask for a code from an authenticator app in addition to your password when signing in."
+            }
+
+            div class="row" {
+              input type="submit" value="enable";
+            }
+          }
+        },
+        Err(e) => {
+          "an error occured when fetching two-factor authentication " (e)
+        }
+      }
+    }
+
+    p {
+      "Manage the devices you are signed in on from the " a href="/sessions" { "sessions" } " page."
+    }
+  };
+
+  let view = components::page("settings", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -0,0 +1,132 @@
+use crate::components;
+use crate::models::two_factor::TwoFactor;
+
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::totp;
+
+use maud::{html, Markup, PreEscaped};
+use actix_web::web::HttpRequest;
+use actix_web::{HttpResponse, http};
+use qrcode::QrCode;
+use qrcode::render::svg;
+
+/// The enrollment page, shows the QR code of the pending secret and asks for
+/// a first code to confirm the app is set up.
+pub async fn render(req: HttpRequest, current: AuthenticatedUser) -> HttpResponse {
+  let user = current.user;
+
+  let two_factor = match TwoFactor::get_by_user_id(user.id) {
+    Ok(Some(two_factor)) if !two_factor.enabled => two_factor,
+    Ok(_) => {
+      return HttpResponse::Found()
+      .header(http::header::LOCATION, "/settings")
+      .content_type("text/plain")
+      .body("nothing to enable");
+    },
+    Err(e) => {
+      let content = html! {
+        p {
+          "An error occured when trying to load two-factor page" (e)
+        }
+      };
+
+      let view = components::page("settings", &content);
+
+      return HttpResponse::Ok()
+      .content_type("text/html")
+      .body(view.into_string());
+    }
+  };
+
+  let uri = totp::provisioning_uri(&user.handle, &two_factor.secret);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Settings" }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/two-factor/enable" {
+        (components::csrf_field(&req))
+        h4 { "Enabling two-factor authentication" }
+
+        p { "Scan this QR code with your authenticator app:" }
+
+        div.qrcode { (qr_code(&uri)) }
+
+        p {
+          "or enter the secret by hand: "
+          code.secret { (two_factor.secret) }
+        }
+
+        fieldset {
+          legend { "confirm with a first code" }
+
+          div {
+            label for="code" { "code from your authenticator app" }
+            input id="code" type="text" name="code" autocomplete="one-time-code";
+          }
+
+          div class="row" {
+            a href="/settings" { "cancel" }
+            input type="submit" value="enable";
+          }
+        }
+      }
+    }
+  };
+
+  let view = components::page("settings", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
+
+/// Shows the recovery codes right after they were generated, they are not
+/// stored in clear so this is the only time they can be seen.
+pub fn render_recovery_codes(codes: &[String]) -> HttpResponse {
+  let content = html! {
+    div class="title-row" {
+      h1 { "Settings" }
+    }
+
+    div class="form-wrapper" {
+      h4 { "Your recovery codes" }
+
+      p {
+        "Each code can be used once to sign in without your authenticator app. "
+        "Write them down somewhere safe, they will not be shown again."
+      }
+
+      ul.recovery-codes {
+        @for code in codes {
+          li { code { (code) } }
+        }
+      }
+
+      div class="row" {
+        a href="/settings" { "done" }
+      }
+    }
+  };
+
+  let view = components::page("settings", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
+
+fn qr_code(data: &str) -> Markup {
+  match QrCode::new(data.as_bytes()) {
+    Ok(code) => {
+      let image = code.render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+      html! { (PreEscaped(image)) }
+    },
+    Err(e) => html! { "an error occured when generating the QR code " (e) }
+  }
+}
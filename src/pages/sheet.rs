@@ -1,83 +1,71 @@
 use crate::components;
+use crate::config;
+use crate::constants;
 use crate::models::sheet::Sheet;
+use crate::models::category::Category;
 use crate::models::expense::Expense;
 use crate::models::income::Income;
+use crate::models::transfer::Transfer;
+use crate::models::split::Split;
+use crate::models::trash::TrashItem;
+use crate::pages::trash::sheet_names;
+use crate::utils::date;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
 
 use maud::html;
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
 
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
   let sheet_id = req
     .match_info()
     .get("sheet_id")
     .unwrap_or("0")
     .parse::<i32>()
-    .unwrap();
-
-  let sheet_result = Sheet::get_by_id(sheet_id);
-  let some_sheet = match sheet_result {
-    Ok(some_sheet) => some_sheet,
-    Err(e) => {
-      let content = html! {
-        p {
-          "An error occured when trying to load sheet page" (e)
-        }
-      };
+    .map_err(|_| AppError::not_found("no such sheet"))?;
 
-      let view = components::page("sheet", &content);
-      
-      return HttpResponse::Ok()
-      .content_type("text/html")
-      .body(view.into_string());
-    }
-  };
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
 
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
 
-  if some_sheet.is_none() {
-    let content = html! {
-      div class="form-wrapper" {
-        "no sheet with such id " span { (sheet_id) }
-        a href="/sheets" { "go back" }
-      }
-    };
-
-    let view = components::page("sheet", &content);
-    
-    return HttpResponse::Ok()
-    .content_type("text/html")
-    .body(view.into_string());
-  }
-
-  let sheet = some_sheet.unwrap();
   let expenses_result = Expense::get_all_by_sheet_id(sheet.id);
   let incomes_result = Income::get_all_by_sheet_id(sheet.id);
   let sheets_result = Sheet::get_all_sheets_by_parent_sheet_id(sheet_id);
+  let transfers = Transfer::get_all_by_sheet_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching transfers {}", err);
+
+      AppError::internal("error when fetching transfers from database")
+    })?;
+  let splits = Split::get_all_by_sheet_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching splits {}", err);
+
+      AppError::internal("error when fetching splits from database")
+    })?;
+  let categories = Category::get_all_with_spent_by_sheet_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching categories {}", err);
+
+      AppError::internal("error when fetching categories from database")
+    })?;
+
+  let total_spent: i32 = expenses_result
+    .as_ref()
+    .map(|expenses| expenses.iter().map(|expense| expense.amount).sum())
+    .unwrap_or(0);
+
+  let recently_deleted: Vec<TrashItem> = TrashItem::get_all_deleted_since(date::now() - constants::UNDO_DELAY_SECONDS)
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|item| item.sheet_id() == Some(sheet_id))
+    .collect();
 
 
   let content = html! {
@@ -85,14 +73,50 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
       div {
         h1 { (sheet.name) }
         a href={"/sheet/rename/" (sheet_id)} { "rename" }
+        a href={"/sheet/" (sheet_id) "/history"} { "history" }
+        a href={"/sheet/" (sheet_id) "/duplicate"} { "duplicate" }
+        a href={"/sheet/" (sheet_id) "/template"} { "save as template" }
+        a href={"/sheet/" (sheet_id) "/budget"} { "budget" }
+        a href={"/sheet/" (sheet_id) "/forecast"} { "forecast" }
+        a href={"/sheet/" (sheet_id) "/transfers/new"} { "transfer" }
+        a href={"/sheet/" (sheet_id) "/settle-up"} { "settle up" }
       
         form method="post" action="/api/sheets/delete-by-id" {
+          (components::csrf_field(&req))
           input type="hidden" name="id" value=(sheet_id);
           input type="submit" value="delete" class="link";
         }
       }
       div {
-        "expenses and incomes, currently at " (sheet.cached_value) span {"EUR"}
+        "expenses and incomes, currently at " (sheet.cached_value) span {(config::get().default_currency)}
+      }
+    }
+
+    @if !recently_deleted.is_empty() {
+      div class="recently-deleted" {
+        @let sheet_names = sheet_names();
+
+        @for item in &recently_deleted {
+          (components::trash_item(&req, item, &sheet_names, "undo"))
+        }
+      }
+    }
+
+    section class="budget" {
+      div class="title-row" {
+        h4 { "budget" }
+        a href={"/sheet/" (sheet_id) "/categories/new"} class="button" {
+          "new category" span{"+"}
+        }
+      }
+
+      (components::budget_progress("all expenses", total_spent, sheet.budget))
+
+      @for (category, spent) in &categories {
+        div class="category" {
+          (components::budget_progress(&category.name, *spent, category.target))
+          a href={"/category/"(category.id)"/edit"} { "edit" }
+        }
       }
     }
 
@@ -117,12 +141,30 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
                     span.amount { (expense.amount) }
                     span.name { (expense.name) }
 
-                    div.actions {
-                      a href={"/expense/"(expense.id)"/edit"} { "edit" }
-                      form method="post" action="/api/expenses/delete-by-id" {
-                        input type="hidden" name="id" value=(expense.id);
-                        input type="hidden" name="sheet_id" value=(sheet_id);
-                        input.link type="submit" value="delete";
+                    @if let Some((category, _)) = categories.iter().find(|(category, _)| Some(category.id) == expense.category_id) {
+                      span.category { (category.name) }
+                    }
+
+                    @if let Some(transfer) = transfers.iter().find(|transfer| transfer.expense_id == expense.id) {
+                      span.transfer { "transfer" }
+
+                      div.actions {
+                        a href={"/transfer/"(transfer.id)"/edit"} { "edit" }
+                      }
+                    } @else {
+                      @if splits.iter().any(|split| split.expense_id == expense.id) {
+                        span.split { "split" }
+                      }
+
+                      div.actions {
+                        a href={"/expense/"(expense.id)"/edit"} { "edit" }
+                        a href={"/expense/"(expense.id)"/split"} { "split" }
+                        form method="post" action="/api/expenses/delete-by-id" {
+                          (components::csrf_field(&req))
+                          input type="hidden" name="id" value=(expense.id);
+                          input type="hidden" name="sheet_id" value=(sheet_id);
+                          input.link type="submit" value="delete";
+                        }
                       }
                     }
                   }
@@ -158,12 +200,21 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
                     span.amount { (income.amount) }
                     span.name { (income.name) }
 
-                    div.actions {
-                      a href={"/income/"(income.id)"/edit"} { "edit" }
-                      form method="post" action="/api/incomes/delete-by-id" {
-                        input type="hidden" name="id" value=(income.id);
-                        input type="hidden" name="sheet_id" value=(sheet_id);
-                        input.link type="submit" value="delete";
+                    @if let Some(transfer) = transfers.iter().find(|transfer| transfer.income_id == income.id) {
+                      span.transfer { "transfer" }
+
+                      div.actions {
+                        a href={"/transfer/"(transfer.id)"/edit"} { "edit" }
+                      }
+                    } @else {
+                      div.actions {
+                        a href={"/income/"(income.id)"/edit"} { "edit" }
+                        form method="post" action="/api/incomes/delete-by-id" {
+                          (components::csrf_field(&req))
+                          input type="hidden" name="id" value=(income.id);
+                          input type="hidden" name="sheet_id" value=(sheet_id);
+                          input.link type="submit" value="delete";
+                        }
                       }
                     }
                   }
@@ -202,6 +253,7 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
                     div.actions {
                       a href={"/sheet/"(sheet.id)} { "edit" }
                       form method="post" action={"/api/inherited-sheets/delete"} {
+                        (components::csrf_field(&req))
                         input type="hidden" name="sheet_id" value=(sheet_id);
                         input type="hidden" name="inherited_sheet_id" value=(sheet.id);
 
@@ -227,7 +279,9 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
 
   let view = components::page("sheet", &content);
   
-  HttpResponse::Ok()
-  .content_type("text/html")
-  .body(view.into_string())
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
 }
\ No newline at end of file
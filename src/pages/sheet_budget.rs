@@ -0,0 +1,78 @@
+use crate::api::sheet::SetSheetBudgetByIdBody;
+use crate::components;
+use crate::models::sheet::Sheet;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+use crate::utils::validation::FormErrors;
+
+use maud::{html, Markup};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let form = SetSheetBudgetByIdBody {
+    budget: sheet.budget.map(|budget| budget.to_string()).unwrap_or_default(),
+    id: sheet.id
+  };
+
+  let view = render_form(&req, &form, &FormErrors::new());
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
+
+pub fn render_form(req: &HttpRequest, form: &SetSheetBudgetByIdBody, errors: &FormErrors) -> Markup {
+  let content = html! {
+    img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
+
+    div class="title-row" {
+      div class="left" {
+        h1 { "Your sheets" }
+      }
+    }
+
+    div class="form-wrapper" {
+      form method="post" action="/api/sheets/budget-by-id" {
+        (components::csrf_field(req))
+        h4 { "Setting the budget" }
+        fieldset {
+          legend { "the most the expenses of the sheet should add up to, leave it empty for none" }
+
+          input type="hidden" name="id" value=(form.id);
+
+          div {
+            label for="budget" { "Budget" }
+            input id="budget" type="number" name="budget" value=(form.budget);
+            (components::field_error(errors, "budget"))
+          }
+
+          div class="row" {
+            a href={"/sheet/" (form.id)} { "cancel" }
+            input type="submit" value="save";
+          }
+        }
+      }
+    }
+  };
+
+  components::page("new-sheet", &content)
+}
\ No newline at end of file
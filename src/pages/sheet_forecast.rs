@@ -0,0 +1,134 @@
+use crate::components;
+use crate::config;
+use crate::constants::{FORECAST_DAYS, FORECAST_MAX_DAYS};
+use crate::models::forecast::Forecast;
+use crate::models::recurring_entry::RecurringEntry;
+use crate::models::sheet::Sheet;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use serde::Deserialize;
+use actix_web::web::{self, HttpRequest};
+use actix_web::HttpResponse;
+
+#[derive(Deserialize)]
+pub struct ForecastQuery {
+  /// how many days are projected, `FORECAST_DAYS` when it is not given
+  pub days: Option<i64>
+}
+
+pub async fn render(req: HttpRequest, query: web::Query<ForecastQuery>, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let days = query.days.unwrap_or(FORECAST_DAYS).clamp(1, FORECAST_MAX_DAYS);
+
+  let forecast = Forecast::for_sheet(&sheet, days, date::now())
+    .map_err(|err| {
+      println!("error when forecasting sheet balance {}", err);
+
+      AppError::internal("error when forecasting the balance of the sheet")
+    })?;
+
+  let recurring_entries = RecurringEntry::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| {
+      println!("error when fetching recurring entries {}", err);
+
+      AppError::internal("error when fetching recurring entries from database")
+    })?;
+
+  let currency = &config::get().default_currency;
+  let final_balance = forecast.balances.last().copied().unwrap_or(sheet.cached_value);
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        h1 { (sheet.name) }
+        a href={"/sheet/" (sheet.id)} { "back to the sheet" }
+      }
+      div {
+        "projected at " (final_balance) span { (currency) } " on " (date::format_date(forecast.end_date()))
+      }
+    }
+
+    form class="forecast-days" method="get" action={"/sheet/" (sheet.id) "/forecast"} {
+      label for="days" { "days" }
+      input id="days" type="number" name="days" min="1" max=(FORECAST_MAX_DAYS) value=(days);
+      input type="submit" value="forecast";
+    }
+
+    @if let Some((negative_date, negative_balance)) = forecast.first_negative() {
+      p class="warning" {
+        "the balance is projected below zero on " (date::format_date(negative_date))
+        ", at " (negative_balance) span { (currency) }
+        ", and down to " (forecast.lowest()) span { (currency) }
+      }
+    }
+
+    (components::forecast_chart(&forecast))
+
+    section class="recurring-entries" {
+      div class="title-row" {
+        h4 { "recurring entries" }
+        a href={"/sheet/" (sheet.id) "/recurring-entries/new"} class="button" {
+          "new recurring entry" span{"+"}
+        }
+      }
+
+      @for entry in &recurring_entries {
+        div class="entry row" {
+          span class="amount" { (entry.signed_amount()) span { (currency) } }
+          span class="name" { (entry.name) }
+          span class="period" {
+            "every " (entry.period.name()) " from " (date::format_date(entry.start_date))
+            @if let Some(end_date) = entry.end_date {
+              " to " (date::format_date(end_date))
+            }
+          }
+
+          form method="post" action="/api/recurring-entries/delete-by-id" {
+            (components::csrf_field(&req))
+            input type="hidden" name="id" value=(entry.id);
+            input.link type="submit" value="delete";
+          }
+        }
+      }
+    }
+
+    section class="forecast-entries" {
+      h4 { "expected" }
+
+      @for entry in &forecast.entries {
+        div class="entry row" {
+          span class="date" { (date::format_date(entry.date)) }
+          span class="amount" { (entry.amount) span { (currency) } }
+          span class="name" { (entry.name) }
+          span class="source" { (entry.source.name()) }
+        }
+      }
+    }
+  };
+
+  let view = components::page("sheet-forecast", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
@@ -0,0 +1,70 @@
+use crate::components;
+use crate::constants::HISTORY_LENGTH;
+use crate::models::sheet::Sheet;
+use crate::models::audit_entry::{AuditEntry, AuditFilter};
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::{self, HttpRequest};
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, filter: web::Query<AuditFilter>, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  // the history outlives the sheet, it is still shown once the sheet is
+  // deleted
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?;
+
+  let entries = AuditEntry::get_latest(Some(sheet_id), &filter, HISTORY_LENGTH)
+    .map_err(|err| {
+      println!("error when fetching audit entries {}", err);
+
+      AppError::internal("error when fetching the history of the sheet")
+    })?;
+
+  if sheet.is_none() && entries.is_empty() {
+    return Err(AppError::not_found("no such sheet"));
+  }
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        @match &sheet {
+          Some(sheet) => {
+            h1 { (sheet.name) }
+            a href={"/sheet/" (sheet_id)} { "back to the sheet" }
+          },
+          None => {
+            h1 { "Deleted sheet" }
+          }
+        }
+      }
+      div {
+        "the changes made to the sheet, latest first"
+      }
+    }
+
+    (components::audit_filter_form(&format!("/sheet/{}/history", sheet_id), &filter))
+    (components::audit_log(&entries))
+  };
+
+  let view = components::page("sheet", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
@@ -0,0 +1,136 @@
+use crate::components;
+use crate::config;
+use crate::models::settlement::{self, Settlement};
+use crate::models::sheet::Sheet;
+use crate::models::split::Split;
+use crate::models::user::User;
+use crate::utils::date;
+
+use crate::errors::AppError;
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> Result<HttpResponse, AppError> {
+  let sheet_id = req
+    .match_info()
+    .get("sheet_id")
+    .unwrap_or("0")
+    .parse::<i32>()
+    .map_err(|_| AppError::not_found("no such sheet"))?;
+
+  let sheet = Sheet::get_by_id(sheet_id)
+    .map_err(|err| {
+      println!("error when fetching sheet {}", err);
+
+      AppError::internal("error when fetching sheet from database")
+    })?
+    .ok_or_else(|| AppError::not_found("no such sheet"))?;
+
+  let splits = Split::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| {
+      println!("error when fetching splits {}", err);
+
+      AppError::internal("error when fetching splits from database")
+    })?;
+
+  let settlements = Settlement::get_all_by_sheet_id(sheet.id)
+    .map_err(|err| {
+      println!("error when fetching settlements {}", err);
+
+      AppError::internal("error when fetching settlements from database")
+    })?;
+
+  let users = User::get_all()
+    .map_err(|err| {
+      println!("error when fetching users {}", err);
+
+      AppError::internal("error when fetching users from database")
+    })?;
+
+  let balances = settlement::balances(&splits, &settlements);
+  let payments = settlement::settle_up(&balances);
+  let currency = &config::get().default_currency;
+
+  // the users deleted since keep their id
+  let handle = |user_id: i32| users
+    .iter()
+    .find(|user| user.id == user_id)
+    .map(|user| user.handle.clone())
+    .unwrap_or_else(|| format!("user {}", user_id));
+
+  let content = html! {
+    div class="title-row" {
+      div {
+        h1 { "Settling up " (sheet.name) }
+        a href={"/sheet/" (sheet.id)} { "back to the sheet" }
+      }
+    }
+
+    @if splits.is_empty() {
+      p { "split the expenses of the sheet between users to see who owes whom" }
+    }
+
+    section class="balances" {
+      h4 { "balances" }
+
+      @for (user_id, balance) in &balances {
+        div class="row" {
+          span class="name" { (handle(*user_id)) }
+
+          @if *balance > 0 {
+            span class="owed" { "is owed " (balance) span { (currency) } }
+          } @else if *balance < 0 {
+            span class="owes" { "owes " (-balance) span { (currency) } }
+          } @else {
+            span { "settled" }
+          }
+        }
+      }
+    }
+
+    section class="payments" {
+      h4 { "payments to settle up" }
+
+      @if payments.is_empty() {
+        p { "everyone is settled" }
+      }
+
+      @for payment in &payments {
+        div class="row" {
+          span { (handle(payment.from_user_id)) " pays " (handle(payment.to_user_id)) }
+          span class="amount" { (payment.amount) span { (currency) } }
+          a href={
+            "/sheet/" (sheet.id) "/settlements/new?from_user_id=" (payment.from_user_id)
+            "&to_user_id=" (payment.to_user_id) "&amount=" (payment.amount)
+          } { "record" }
+        }
+      }
+
+      a href={"/sheet/" (sheet.id) "/settlements/new"} { "record another payment" }
+    }
+
+    section class="settlements" {
+      h4 { "recorded settlements" }
+
+      @for settlement in &settlements {
+        div class="row" {
+          span class="date" { (date::format_date(settlement.date)) }
+          span { (handle(settlement.from_user_id)) " paid " (handle(settlement.to_user_id)) }
+          span class="amount" { (settlement.amount) span { (currency) } }
+          a href={"/transfer/" (settlement.transfer_id) "/edit"} { "transfer" }
+        }
+      }
+    }
+  };
+
+  let view = components::page("settle-up", &content);
+
+  Ok(
+    HttpResponse::Ok()
+    .content_type("text/html")
+    .body(view.into_string())
+  )
+}
\ No newline at end of file
@@ -1,38 +1,26 @@
 use crate::components;
+use crate::config;
+use crate::constants;
 use crate::models::sheet::Sheet;
+use crate::models::trash::TrashItem;
+use crate::pages::trash::sheet_names;
+use crate::utils::date;
 
-use crate::utils::req_auth::request_authentication;
-use crate::models::user::UserRole;
+use crate::utils::req_auth::AuthenticatedUser;
 
 use maud::html;
 use actix_web::web::HttpRequest;
-use actix_web::{HttpResponse, http};
-
-pub async fn render(req: HttpRequest) -> HttpResponse {
-  let auth_result = request_authentication(&req, UserRole::Guest);
-
-  match auth_result {
-    Ok(auth) => {
-      if !auth.has_access() {
-        return HttpResponse::Found()
-        .header(http::header::LOCATION, "/signin")
-        .content_type("text/plain")
-        .body("account needed");
-      }
-    },
-    Err(e) => {
-      let view = html! {
-        "an error occured when checking account informations" (e)
-      };
-
-      return HttpResponse::InternalServerError()
-        .content_type("text/plain")
-        .body(view.into_string());
-    }
-  }
+use actix_web::HttpResponse;
 
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> HttpResponse {
   let sheets_result = Sheet::get_all();
 
+  let recently_deleted: Vec<TrashItem> = TrashItem::get_all_deleted_since(date::now() - constants::UNDO_DELAY_SECONDS)
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|item| matches!(item, TrashItem::Sheet(_)))
+    .collect();
+
   let content = html! {
     // img src="/static/assets/undraw_setup_wizard_r6mr.svg" class="background-illustration";
 
@@ -41,6 +29,16 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
       a href="/new-sheet" class="button" { "new sheet" span { "+" } }
     }
 
+    @if !recently_deleted.is_empty() {
+      div class="recently-deleted" {
+        @let sheet_names = sheet_names();
+
+        @for item in &recently_deleted {
+          (components::trash_item(&req, item, &sheet_names, "undo"))
+        }
+      }
+    }
+
 
     @match sheets_result {
       Ok(sheets) => {
@@ -49,7 +47,7 @@ pub async fn render(req: HttpRequest) -> HttpResponse {
             
             div class="sheet" {
               a href={"/sheet/" (sheet.id)} class="name" { (sheet.name) }
-              div { (sheet.cached_value) span{"EUR"} }
+              div { (sheet.cached_value) span{(config::get().default_currency)} }
 
               // div class="bottom-row" {
               //   div class="actions" {
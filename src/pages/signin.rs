@@ -4,12 +4,13 @@ use maud::{html};
 use actix_web::web::HttpRequest;
 use actix_web::HttpResponse;
 
-pub async fn render(_req: HttpRequest) -> HttpResponse {
+pub async fn render(req: HttpRequest) -> HttpResponse {
   let content = html! {
     img class="background-illustration" src="/static/assets/undraw_authentication_fsn5.svg";
 
     div class="form-wrapper" {
       form method="post" action="/api/auth/signin" {
+        (components::csrf_field(&req))
         h4 { "Sign in" }
         fieldset {
           legend { "Sign in to an account" }
@@ -0,0 +1,39 @@
+use crate::components;
+use maud::{html};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let content = html! {
+    img class="background-illustration" src="/static/assets/undraw_authentication_fsn5.svg";
+
+    div class="form-wrapper" {
+      form method="post" action="/api/auth/two-factor" {
+        (components::csrf_field(&req))
+        h4 { "Signin" }
+        fieldset {
+          legend { "Two-factor authentication" }
+
+          label for="code" { "code from your authenticator app" }
+          input id="code" type="text" name="code" autocomplete="one-time-code" autofocus;
+
+          input type="submit" value="Submit";
+        }
+      }
+
+      p {
+        "Lost your device? Enter one of your recovery codes instead."
+      }
+
+      p {
+        a href="/signin" { "Go back" } " to sign in with another account."
+      }
+    }
+  };
+
+  let view = components::page_without_menu("signin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
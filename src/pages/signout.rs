@@ -0,0 +1,34 @@
+use crate::components;
+use maud::{html};
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+
+/// Asks for a confirmation before signing out, the menu can only link to
+/// this page since signing out needs a form with a csrf token.
+pub async fn render(req: HttpRequest) -> HttpResponse {
+  let content = html! {
+    img class="background-illustration" src="/static/assets/undraw_authentication_fsn5.svg";
+
+    div class="form-wrapper" {
+      form method="post" action="/signout" {
+        (components::csrf_field(&req))
+        h4 { "Signout" }
+        fieldset {
+          legend { "Sign out of this device" }
+
+          input type="submit" value="Sign out";
+        }
+      }
+
+      p {
+        "Go back to the " a href="/" { "home page" } "."
+      }
+    }
+  };
+
+  let view = components::page_without_menu("signin", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
@@ -12,12 +12,13 @@ pub struct Info {
   hash: String
 }
 
-pub async fn render(info: Option<web::Query<Info>>, _req: HttpRequest) -> HttpResponse {
+pub async fn render(info: Option<web::Query<Info>>, req: HttpRequest) -> HttpResponse {
   let content = html! {
     img class="background-illustration" src="/static/assets/undraw_authentication_fsn5.svg";
 
     div class="form-wrapper" {
       form method="post" action="/api/auth/signup" {
+        (components::csrf_field(&req))
         h4 { "Signup" }
         fieldset {
           legend { "Create an account" }
@@ -0,0 +1,70 @@
+use crate::components;
+use crate::config;
+use crate::models::sheet::Sheet;
+use crate::models::trash::TrashItem;
+
+use crate::utils::req_auth::AuthenticatedUser;
+
+use maud::html;
+use actix_web::web::HttpRequest;
+use actix_web::HttpResponse;
+use std::collections::HashMap;
+
+pub async fn render(req: HttpRequest, _user: AuthenticatedUser) -> HttpResponse {
+  let items_result = TrashItem::get_all();
+  let sheet_names = sheet_names();
+  let retention_days = config::get().trash_retention / (24 * 3600);
+
+  let content = html! {
+    div class="title-row" {
+      h1 { "Trash" }
+    }
+
+    p {
+      "The deleted sheets, expenses, incomes and imports. They can be restored "
+      @if retention_days > 0 {
+        "for " (retention_days) " days, "
+      }
+      "then they are deleted for good."
+    }
+
+    @match items_result {
+      Ok(items) => {
+        div class="trash-list" {
+          @if items.is_empty() {
+            p { "the trash is empty" }
+          }
+
+          @for item in &items {
+            div.element {
+              (components::trash_item(&req, item, &sheet_names, "restore"))
+            }
+          }
+        }
+      },
+
+      Err(e) => {
+        "an error occured when fetching the trash " (e)
+      }
+    }
+  };
+
+  let view = components::page("trash", &content);
+
+  HttpResponse::Ok()
+  .content_type("text/html")
+  .body(view.into_string())
+}
+
+/// The names of the sheets by id, the deleted ones included since their
+/// imports show in the trash too.
+pub fn sheet_names() -> HashMap<i32, String> {
+  let sheets = Sheet::get_all().unwrap_or_default();
+  let deleted_sheets = Sheet::get_all_deleted().unwrap_or_default();
+
+  sheets
+    .into_iter()
+    .chain(deleted_sheets)
+    .map(|sheet| (sheet.id, sheet.name))
+    .collect()
+}
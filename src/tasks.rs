@@ -0,0 +1,60 @@
+use crate::config;
+use crate::constants;
+use crate::models::{self, failed_attempt, invitation, password_reset, session, two_factor};
+use crate::utils::date;
+
+use std::time::Duration;
+
+/// Spawns the maintenance tasks on the current runtime, they run once when
+/// the server starts then every `constants::MAINTENANCE_INTERVAL_SECONDS`.
+pub fn spawn_maintenance() {
+  actix_rt::spawn(async {
+    let mut interval = actix_rt::time::interval(
+      Duration::from_secs(constants::MAINTENANCE_INTERVAL_SECONDS)
+    );
+
+    loop {
+      interval.tick().await;
+
+      run_maintenance();
+    }
+  });
+}
+
+pub fn run_maintenance() {
+  match invitation::remove_stale_invitations() {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} stale invitations", count),
+    Err(e) => println!("error when purging stale invitations {}", e)
+  };
+
+  match session::remove_expired_sessions() {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} expired sessions", count),
+    Err(e) => println!("error when purging expired sessions {}", e)
+  };
+
+  match password_reset::remove_expired_password_resets() {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} expired password resets", count),
+    Err(e) => println!("error when purging expired password resets {}", e)
+  };
+
+  match two_factor::remove_expired_challenges() {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} expired two-factor challenges", count),
+    Err(e) => println!("error when purging expired two-factor challenges {}", e)
+  };
+
+  match failed_attempt::remove_old_failed_attempts() {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} old failed attempts", count),
+    Err(e) => println!("error when purging old failed attempts {}", e)
+  };
+
+  match models::purge_trash(date::now() - config::get().trash_retention as i64) {
+    Ok(0) => {},
+    Ok(count) => println!("purged {} items from the trash", count),
+    Err(e) => println!("error when purging the trash {}", e)
+  };
+}
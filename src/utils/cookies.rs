@@ -0,0 +1,22 @@
+use crate::config;
+use actix_web::cookie::{Cookie, CookieBuilder, SameSite};
+
+/// Starts a cookie with the attributes every cookie of the instance needs:
+/// not readable from javascript, not sent by cross-site requests, and only
+/// sent over https when `secure_cookies` is set.
+pub fn build(name: &str, value: String) -> CookieBuilder {
+  Cookie::build(name.to_owned(), value)
+    .path("/")
+    .http_only(true)
+    .same_site(SameSite::Lax)
+    .secure(config::get().secure_cookies)
+}
+
+
+/// The `token` cookie of a session, kept by the browser as long as the
+/// session lives without being renewed.
+pub fn session(token: String) -> Cookie<'static> {
+  build("token", token)
+    .max_age(config::get().session_lifetime as i64)
+    .finish()
+}
\ No newline at end of file
@@ -1,34 +1,138 @@
-use crate::constants::SALT_COMPONENT;
-use ring::{digest, pbkdf2};
+use crate::constants::{SALT_COMPONENT, PASSWORD_HASH_ITERATIONS};
+use ring::{constant_time, digest, pbkdf2, rand};
+use ring::rand::SecureRandom;
 use std::num::NonZeroU32;
 
 static PBKDF2_ALG: pbkdf2::Algorithm = pbkdf2::PBKDF2_HMAC_SHA512;
 const CREDENTIAL_LEN: usize = digest::SHA512_OUTPUT_LEN;
+const SALT_LEN: usize = 16;
 pub type Credential = [u8; CREDENTIAL_LEN];
 
-pub fn hash_password(handle: &str, password: &str) -> Result<String, std::string::FromUtf8Error> {
-  let salted_handle = salt(handle);
+/// Identifier of the algorithm in the PHC strings produced by `hash_password`.
+const PHC_IDENTIFIER: &str = "pbkdf2-sha512";
+
+/// Hashes the password with a random salt and returns it as a PHC string:
+/// `$pbkdf2-sha512$i=<iterations>$<salt>$<hash>`, salt and hash being
+/// encoded in unpadded base64. The string holds everything needed to verify
+/// a password later on, even if the number of iterations changes.
+pub fn hash_password(password: &str) -> Result<String, ring::error::Unspecified> {
+  let mut salt = [0u8; SALT_LEN];
+  rand::SystemRandom::new().fill(&mut salt)?;
+
   let mut to_store: Credential = [0u8; CREDENTIAL_LEN];
 
   pbkdf2::derive(
     PBKDF2_ALG,
-    NonZeroU32::new(100_00).unwrap(),
-    &salted_handle,
+    NonZeroU32::new(PASSWORD_HASH_ITERATIONS).unwrap(),
+    &salt,
     password.as_bytes(),
     &mut to_store
   );
 
-  Ok(String::from_utf8_lossy(&to_store).replace(char::from(0), ""))
+  Ok(format!(
+    "${}$i={}${}${}",
+    PHC_IDENTIFIER,
+    PASSWORD_HASH_ITERATIONS,
+    base64::encode_config(salt, base64::STANDARD_NO_PAD),
+    base64::encode_config(&to_store[..], base64::STANDARD_NO_PAD)
+  ))
 }
 
+/// Returns `count` random single-use codes such as `7kq2-m0xe`, short
+/// enough to be written down.
+pub fn generate_recovery_codes(count: usize) -> Result<Vec<String>, ring::error::Unspecified> {
+  let random = rand::SystemRandom::new();
+  let mut codes = Vec::with_capacity(count);
+
+  for _ in 0..count {
+    let mut bytes = [0u8; 5];
+    random.fill(&mut bytes)?;
+
+    let code = base32::encode(base32::Alphabet::Crockford, &bytes).to_lowercase();
+
+    codes.push(format!("{}-{}", &code[..4], &code[4..]));
+  }
+
+  Ok(codes)
+}
+
+/// Checks the attempt against the stored password, whether it is a PHC
+/// string or a hash produced by the legacy scheme.
 pub fn verify_passwords(handle: &str, unhashed_attempt: &str, actual_password: &str) -> bool {
-  match hash_password(handle, unhashed_attempt) {
-    Ok(p) => p == actual_password,
-    Err(_err) => false
+  // a legacy hash can start with a `$` too, it is only legacy when it is not
+  // a valid PHC string
+  let (iterations, salt, hash) = match parse_phc(actual_password) {
+    Some(parts) => parts,
+    None => {
+      return constant_time::verify_slices_are_equal(
+        legacy_hash_password(handle, unhashed_attempt).as_bytes(),
+        actual_password.as_bytes()
+      ).is_ok();
+    }
+  };
+
+  // the comparison of the hashes is done in constant time
+  pbkdf2::verify(
+    PBKDF2_ALG,
+    iterations,
+    &salt,
+    unhashed_attempt.as_bytes(),
+    &hash
+  ).is_ok()
+}
+
+/// Tells whether the stored password should be hashed again, either because
+/// it uses the legacy scheme or fewer iterations than the current setting.
+pub fn needs_rehash(actual_password: &str) -> bool {
+  match parse_phc(actual_password) {
+    Some((iterations, _, _)) => iterations.get() < PASSWORD_HASH_ITERATIONS,
+    None => true
   }
 }
 
-pub fn salt(handle: &str) -> Vec<u8> {
+fn parse_phc(password: &str) -> Option<(NonZeroU32, Vec<u8>, Vec<u8>)> {
+  let mut parts = password.split('$');
+
+  // the string starts with a `$`, the first part is empty
+  if parts.next() != Some("") || parts.next() != Some(PHC_IDENTIFIER) {
+    return None;
+  }
+
+  let iterations = parts.next()?
+    .strip_prefix("i=")?
+    .parse::<u32>().ok()
+    .and_then(NonZeroU32::new)?;
+
+  let salt = base64::decode_config(parts.next()?, base64::STANDARD_NO_PAD).ok()?;
+  let hash = base64::decode_config(parts.next()?, base64::STANDARD_NO_PAD).ok()?;
+
+  if parts.next().is_some() {
+    return None;
+  }
+
+  Some((iterations, salt, hash))
+}
+
+/// The hashing scheme used before the PHC strings, the salt is derived from
+/// the handle and the bytes are stored lossily. It is only kept to verify the
+/// passwords of users who did not sign in since, they get rehashed at their
+/// next sign in.
+fn legacy_hash_password(handle: &str, password: &str) -> String {
+  let salted_handle = legacy_salt(handle);
+  let mut to_store: Credential = [0u8; CREDENTIAL_LEN];
+
+  pbkdf2::derive(
+    PBKDF2_ALG,
+    NonZeroU32::new(100_00).unwrap(),
+    &salted_handle,
+    password.as_bytes(),
+    &mut to_store
+  );
+
+  String::from_utf8_lossy(&to_store).replace(char::from(0), "")
+}
+
+fn legacy_salt(handle: &str) -> Vec<u8> {
   let mut salt = Vec::with_capacity(
     SALT_COMPONENT.len() + handle.as_bytes().len()
   );
@@ -37,4 +141,4 @@ pub fn salt(handle: &str) -> Vec<u8> {
   salt.extend(handle.as_bytes());
 
   salt
-}
\ No newline at end of file
+}
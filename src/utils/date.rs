@@ -0,0 +1,94 @@
+use chrono::prelude::*;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the current unix timestamp, in seconds.
+pub fn now() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64
+}
+
+/// Formats a unix timestamp the way dates are entered in the forms,
+/// `YYYY-MM-DD`.
+pub fn format_date(timestamp: i64) -> String {
+  NaiveDateTime::from_timestamp(timestamp, 0)
+    .format("%Y-%m-%d")
+    .to_string()
+}
+
+pub fn format_datetime(timestamp: i64) -> String {
+  NaiveDateTime::from_timestamp(timestamp, 0)
+    .format("%Y-%m-%d %H:%M")
+    .to_string()
+}
+
+/// The periods the dates of a duplicated sheet can be shifted by, and the
+/// intervals of the recurring entries.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+  None,
+  Week,
+  Month,
+  Year
+}
+
+impl Period {
+  pub const ALL: [Period; 4] = [Period::None, Period::Week, Period::Month, Period::Year];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Period::None => "none",
+      Period::Week => "week",
+      Period::Month => "month",
+      Period::Year => "year"
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Period> {
+    Period::ALL.iter().copied().find(|period| period.name() == name)
+  }
+}
+
+/// Moves a unix timestamp forward by the period. Shifting by months keeps the
+/// day of the month, or uses the last day of shorter months: the 31st of
+/// january becomes the 28th or 29th of february. A date that cannot be moved
+/// that far stays where it is.
+pub fn shift(timestamp: i64, period: Period) -> i64 {
+  match period {
+    Period::None => timestamp,
+    Period::Week => timestamp + 7 * 24 * 3600,
+    Period::Month => add_months(timestamp, 1).unwrap_or(timestamp),
+    Period::Year => add_months(timestamp, 12).unwrap_or(timestamp)
+  }
+}
+
+/// Moves a unix timestamp forward by a number of months, the same way as
+/// `shift`. `None` when the date is past the last year chrono supports.
+pub fn add_months(timestamp: i64, months: u32) -> Option<i64> {
+  let datetime = NaiveDateTime::from_timestamp(timestamp, 0);
+  let total_months = datetime.month0().checked_add(months)?;
+  let year = datetime.year().checked_add((total_months / 12) as i32)?;
+  let month = total_months % 12 + 1;
+
+  // the first day of the following month, minus one day
+  let last_day = if month == 12 {
+    NaiveDate::from_ymd_opt(year.checked_add(1)?, 1, 1)
+  } else {
+    NaiveDate::from_ymd_opt(year, month + 1, 1)
+  }?
+  .pred_opt()?
+  .day();
+
+  let date = NaiveDate::from_ymd_opt(year, month, datetime.day().min(last_day))?;
+
+  Some(date.and_time(datetime.time()).timestamp())
+}
+
+/// The timestamp of midnight on the day of a unix timestamp, the way the
+/// dates of the expenses and incomes are stored.
+pub fn start_of_day(timestamp: i64) -> i64 {
+  timestamp - timestamp.rem_euclid(24 * 3600)
+}
\ No newline at end of file
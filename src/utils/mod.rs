@@ -1,2 +1,6 @@
 pub mod crypto;
-pub mod req_auth;
\ No newline at end of file
+pub mod req_auth;
+pub mod date;
+pub mod totp;
+pub mod cookies;
+pub mod validation;
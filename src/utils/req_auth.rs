@@ -1,40 +1,114 @@
-use actix_web::{HttpRequest, HttpMessage};
+use actix_web::dev::Payload;
+use actix_web::{http, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use futures::future::{ready, Ready};
+use crate::errors::AppError;
 use crate::models::user::{User, UserRole};
+use crate::models::session::Session;
+use crate::middlewares::RenewedSession;
 
-pub enum AuthenticationResult {
-  DoesHaveAccess,
-  DoesNotHaveAccess
+/// The signed in user of the request, along with the session of the device.
+/// Handlers taking it are only called for signed in users: the pages
+/// redirect the others to `/signin` and the api answers them with a 401.
+pub struct AuthenticatedUser {
+  pub user: User,
+  pub session: Session
 }
 
-impl AuthenticationResult {
-  pub fn has_access(&self) -> bool {
-    match *self {
-      AuthenticationResult::DoesHaveAccess => true,
-      AuthenticationResult::DoesNotHaveAccess => false
+/// Same as `AuthenticatedUser` but only for administrators, the other users
+/// get a 403.
+pub struct AdminUser {
+  pub user: User
+}
+
+impl FromRequest for AuthenticatedUser {
+  type Error = Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+  type Config = ();
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    ready(authenticate(req, UserRole::Guest).map(|(user, session)| AuthenticatedUser { user, session }))
+  }
+}
+
+impl FromRequest for AdminUser {
+  type Error = Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+  type Config = ();
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    ready(authenticate(req, UserRole::Admin).map(|(user, _session)| AdminUser { user }))
+  }
+}
+
+fn authenticate(req: &HttpRequest, role: UserRole) -> Result<(User, Session), Error> {
+  let session_and_user = get_session(req)
+    .and_then(|session| match session {
+      Some(session) => User::get_by_id(session.user_id)
+        .map(|user| user.map(|user| (user, session)))
+        .map_err(|err| format!("error when fetching user by id, {}", err)),
+      None => Ok(None)
+    })
+    .map_err(|err| {
+      println!("error when checking account informations {}", err);
+
+      AppError::internal("error when checking account informations")
+    })?;
+
+  let (user, session) = match session_and_user {
+    Some(session_and_user) => session_and_user,
+    None if req.path().starts_with("/api/") => {
+      return Err(AppError::unauthorized("authentication required").into());
+    },
+    None => {
+      return Err(
+        HttpResponse::Found()
+          .header(http::header::LOCATION, "/signin")
+          .content_type("text/plain")
+          .body("account needed")
+          .into()
+      );
     }
+  };
+
+  if !user.is_role_greater_or_equal(role) {
+    return Err(AppError::forbidden("you are not allowed to access this page").into());
   }
+
+  Ok((user, session))
 }
 
-pub fn request_authentication(req: &HttpRequest, role: UserRole) -> Result<AuthenticationResult, String> {
+/// Returns the session the `token` cookie of the request belongs to, if
+/// the session did not expire. The session is renewed on the way, its cookie
+/// is then sent again by the `SessionCookie` middleware.
+pub fn get_session(req: &HttpRequest) -> Result<Option<Session>, String> {
   let token_cookie = req.cookie("token");
 
   if token_cookie.is_none() {
-    return Ok(AuthenticationResult::DoesNotHaveAccess);
+    return Ok(None);
   }
   
   let token_cookie = token_cookie.unwrap();
   let token = token_cookie.value();
-  let user = User::get_by_token(token)
-    .map_err(|err| { format!("error when fetching user by token, {}", err) })?;
+  let session = Session::get_by_token(token)
+    .map_err(|err| { format!("error when fetching session by token, {}", err) })?;
 
-  if user.is_none() {
-    return Ok(AuthenticationResult::DoesNotHaveAccess);
-  }
+  match session {
+    Some(session) if session.is_expired() => {
+      session.remove()
+        .map_err(|err| { format!("error when removing expired session, {}", err) })?;
 
-  let user = user.unwrap();
-  if !user.is_role_greater_or_equal(role) {
-    return Ok(AuthenticationResult::DoesNotHaveAccess)
-  }
+      Ok(None)
+    },
+    Some(mut session) => {
+      let renewed = session.renew()
+        .map_err(|err| { format!("error when renewing session, {}", err) })?;
+
+      if renewed {
+        req.extensions_mut().insert(RenewedSession(session.token.clone()));
+      }
 
-  Ok(AuthenticationResult::DoesHaveAccess)
-}
\ No newline at end of file
+      Ok(Some(session))
+    },
+    None => Ok(None)
+  }
+}
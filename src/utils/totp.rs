@@ -0,0 +1,85 @@
+//! Time-based one-time passwords as described by RFC 6238, with the defaults
+//! every authenticator app understands: HMAC-SHA1, 6 digits, 30 seconds.
+
+use crate::utils::date;
+use ring::hmac;
+use ring::rand::{self, SecureRandom};
+
+const PERIOD_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+const SECRET_LEN: usize = 20;
+const ISSUER: &str = "Calco";
+
+/// Number of periods before and after the current one that are accepted, to
+/// tolerate clocks that drift a bit.
+const ALLOWED_DRIFT: i64 = 1;
+
+/// Returns a new random secret, encoded in base32 as expected by the
+/// authenticator apps.
+pub fn generate_secret() -> Result<String, ring::error::Unspecified> {
+  let mut secret = [0u8; SECRET_LEN];
+  rand::SystemRandom::new().fill(&mut secret)?;
+
+  Ok(base32::encode(base32::Alphabet::RFC4648 { padding: false }, &secret))
+}
+
+/// Returns the current time step.
+pub fn current_step() -> i64 {
+  date::now() / PERIOD_SECONDS
+}
+
+/// Returns the code of the secret for the given time step, as RFC 4226
+/// defines it.
+pub fn code_at(secret: &str, step: i64) -> Option<u32> {
+  let key = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret)?;
+  let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, &key);
+  let tag = hmac::sign(&key, &(step as u64).to_be_bytes());
+  let hash = tag.as_ref();
+
+  // dynamic truncation
+  let offset = (hash[hash.len() - 1] & 0xf) as usize;
+  let binary = ((hash[offset] as u32 & 0x7f) << 24)
+    | ((hash[offset + 1] as u32) << 16)
+    | ((hash[offset + 2] as u32) << 8)
+    | (hash[offset + 3] as u32);
+
+  Some(binary % 10u32.pow(DIGITS))
+}
+
+/// Checks the code against the secret and returns the time step it matched.
+/// Steps up to `last_used_step` are refused so a code cannot be used twice.
+pub fn verify(secret: &str, code: &str, last_used_step: i64) -> Option<i64> {
+  let code = code.trim();
+
+  if code.len() != DIGITS as usize {
+    return None;
+  }
+
+  let code = code.parse::<u32>().ok()?;
+  let step = current_step();
+
+  ((step - ALLOWED_DRIFT)..=(step + ALLOWED_DRIFT))
+    .filter(|candidate| *candidate > last_used_step)
+    .find(|candidate| code_at(secret, *candidate) == Some(code))
+}
+
+/// Returns the `otpauth://` uri the authenticator apps read from the QR code.
+pub fn provisioning_uri(handle: &str, secret: &str) -> String {
+  format!(
+    "otpauth://totp/{issuer}:{handle}?secret={secret}&issuer={issuer}&digits={digits}&period={period}",
+    issuer = ISSUER,
+    handle = encode_uri_component(handle),
+    secret = secret,
+    digits = DIGITS,
+    period = PERIOD_SECONDS
+  )
+}
+
+fn encode_uri_component(value: &str) -> String {
+  value.bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+      _ => format!("%{:02X}", byte)
+    })
+    .collect()
+}
@@ -0,0 +1,148 @@
+use crate::constants::{AMOUNT_MAX, NAME_MAX_LENGTH};
+use crate::utils::date::Period;
+
+use chrono::prelude::*;
+
+/// The problems found in a submitted form, by field name, so the form can be
+/// rendered again with a message next to each invalid field.
+#[derive(Debug, Default)]
+pub struct FormErrors {
+  errors: Vec<(&'static str, String)>
+}
+
+impl FormErrors {
+  pub fn new() -> FormErrors {
+    FormErrors::default()
+  }
+
+  /// Only the first error of a field is kept.
+  pub fn add(&mut self, field: &'static str, message: String) {
+    if self.get(field).is_none() {
+      self.errors.push((field, message));
+    }
+  }
+
+  pub fn get(&self, field: &str) -> Option<&str> {
+    self.errors
+      .iter()
+      .find(|(name, _)| *name == field)
+      .map(|(_, message)| message.as_str())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.errors.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &(&'static str, String)> {
+    self.errors.iter()
+  }
+}
+
+/// Returns the trimmed name, it must not be empty nor longer than
+/// `NAME_MAX_LENGTH` characters.
+pub fn name(errors: &mut FormErrors, field: &'static str, value: &str) -> String {
+  let name = value.trim();
+
+  if name.is_empty() {
+    errors.add(field, String::from("the name is required"));
+  }
+  else if name.chars().count() > NAME_MAX_LENGTH {
+    errors.add(field, format!("the name cannot be longer than {} characters", NAME_MAX_LENGTH));
+  }
+
+  name.to_owned()
+}
+
+/// Returns the amount, a whole number between 1 and `AMOUNT_MAX`.
+pub fn amount(errors: &mut FormErrors, field: &'static str, value: &str) -> i32 {
+  match value.trim().parse::<i32>() {
+    Ok(amount) if (1..=AMOUNT_MAX).contains(&amount) => amount,
+    Ok(_) => {
+      errors.add(field, format!("the amount must be between 1 and {}", AMOUNT_MAX));
+
+      0
+    },
+    Err(_) if value.trim().is_empty() => {
+      errors.add(field, String::from("the amount is required"));
+
+      0
+    },
+    Err(_) => {
+      errors.add(field, String::from("the amount must be a whole number"));
+
+      0
+    }
+  }
+}
+
+/// Same as `amount` but the value can be left empty, for the targets and
+/// budgets that are not set.
+pub fn optional_amount(errors: &mut FormErrors, field: &'static str, value: &str) -> Option<i32> {
+  if value.trim().is_empty() {
+    return None;
+  }
+
+  Some(amount(errors, field, value))
+}
+
+/// Returns the balance, a whole number that can be negative but not further
+/// from zero than `AMOUNT_MAX`.
+pub fn balance(errors: &mut FormErrors, field: &'static str, value: &str) -> i32 {
+  match value.trim().parse::<i32>() {
+    Ok(balance) if (-AMOUNT_MAX..=AMOUNT_MAX).contains(&balance) => balance,
+    Ok(_) => {
+      errors.add(field, format!("the balance must be between -{} and {}", AMOUNT_MAX, AMOUNT_MAX));
+
+      0
+    },
+    Err(_) if value.trim().is_empty() => {
+      errors.add(field, String::from("the balance is required"));
+
+      0
+    },
+    Err(_) => {
+      errors.add(field, String::from("the balance must be a whole number"));
+
+      0
+    }
+  }
+}
+
+/// Returns the timestamp of a `YYYY-MM-DD` date, at midnight.
+pub fn date(errors: &mut FormErrors, field: &'static str, value: &str) -> i64 {
+  match NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d") {
+    Ok(date) => date.and_hms(0, 0, 0).timestamp(),
+    Err(_) if value.trim().is_empty() => {
+      errors.add(field, String::from("the date is required"));
+
+      0
+    },
+    Err(_) => {
+      errors.add(field, String::from("the date must be formatted as yyyy-mm-dd"));
+
+      0
+    }
+  }
+}
+
+/// Same as `date` but the value can be left empty, for the deadlines that are
+/// not set.
+pub fn optional_date(errors: &mut FormErrors, field: &'static str, value: &str) -> Option<i64> {
+  if value.trim().is_empty() {
+    return None;
+  }
+
+  Some(date(errors, field, value))
+}
+
+/// Returns the period named by the value, `none` when it is left empty.
+pub fn period(errors: &mut FormErrors, field: &'static str, value: &str) -> Period {
+  match value.trim() {
+    "" => Period::None,
+    name => Period::from_name(name).unwrap_or_else(|| {
+      errors.add(field, String::from("the period must be none, week, month or year"));
+
+      Period::None
+    })
+  }
+}
\ No newline at end of file